@@ -0,0 +1,269 @@
+(function () {
+    "use strict";
+
+    var REFRESH_INTERVAL_MS = 2000;
+    var DASHBOARD_URL = "/api?requestType=getDashboard";
+    var TOKEN_STORAGE_KEY = "archonApiToken";
+
+    // an API token can be given once as ?token=..., it's remembered by the browser after that
+    function getApiToken() {
+        var match = /[?&]token=([^&#]*)/.exec(window.location.search);
+        if (match) {
+            var token = decodeURIComponent(match[1]);
+            try {
+                window.localStorage.setItem(TOKEN_STORAGE_KEY, token);
+            } catch (e) {}
+            return token;
+        }
+        try {
+            return window.localStorage.getItem(TOKEN_STORAGE_KEY);
+        } catch (e) {
+            return null;
+        }
+    }
+
+    var apiToken = getApiToken();
+
+    function $(id) {
+        return document.getElementById(id);
+    }
+
+    function el(tag, text, className) {
+        var node = document.createElement(tag);
+        if (text !== undefined && text !== null) {
+            node.textContent = text;
+        }
+        if (className) {
+            node.className = className;
+        }
+        return node;
+    }
+
+    function row(cells) {
+        var tr = document.createElement("tr");
+        cells.forEach(function (cell) {
+            var td = document.createElement("td");
+            if (cell instanceof Node) {
+                td.appendChild(cell);
+            } else {
+                td.textContent = cell;
+            }
+            tr.appendChild(td);
+        });
+        return tr;
+    }
+
+    function replaceRows(tbody, rows, columns, emptyText) {
+        while (tbody.firstChild) {
+            tbody.removeChild(tbody.firstChild);
+        }
+        if (rows.length === 0) {
+            var td = el("td", emptyText, "empty");
+            td.colSpan = columns;
+            var tr = document.createElement("tr");
+            tr.appendChild(td);
+            tbody.appendChild(tr);
+            return;
+        }
+        rows.forEach(function (r) {
+            tbody.appendChild(r);
+        });
+    }
+
+    function chainName(name, color) {
+        var span = el("span", name, "chain-name");
+        span.style.color = color;
+        return span;
+    }
+
+    function formatSeconds(seconds) {
+        if (seconds === null || seconds === undefined) {
+            return "-";
+        }
+        var days = Math.floor(seconds / 86400);
+        var hours = Math.floor((seconds % 86400) / 3600);
+        var minutes = Math.floor((seconds % 3600) / 60);
+        var secs = seconds % 60;
+        var hms = [hours, minutes, secs].map(function (n) {
+            return n < 10 ? "0" + n : String(n);
+        }).join(":");
+        return days > 0 ? days + "d " + hms : hms;
+    }
+
+    function formatAgo(seconds) {
+        return seconds === null || seconds === undefined ? "-" : formatSeconds(seconds) + " ago";
+    }
+
+    function formatTime(rfc3339) {
+        return new Date(rfc3339).toLocaleTimeString();
+    }
+
+    function renderCurrentBlock(snapshot) {
+        var body = $("current-block").querySelector(".body");
+        while (body.firstChild) {
+            body.removeChild(body.firstChild);
+        }
+        var block = snapshot.currentBlock;
+        if (!block) {
+            body.appendChild(el("p", "Waiting for the first block...", "empty"));
+            return;
+        }
+        var facts = el("div", null, "facts");
+        [
+            ["Chain", chainName(block.chainName, block.color)],
+            ["Block", "#" + block.height],
+            ["Base Target", block.baseTarget],
+            ["Elapsed", formatSeconds(block.elapsedSeconds)],
+            ["Grace Period", formatSeconds(snapshot.gracePeriod)],
+            ["Best Deadline", formatSeconds(block.bestDeadline)],
+            ["Generation Signature", block.generationSignature]
+        ].forEach(function (fact) {
+            var div = document.createElement("div");
+            div.appendChild(el("span", fact[0]));
+            var value = el("strong");
+            if (fact[1] instanceof Node) {
+                value.appendChild(fact[1]);
+            } else {
+                value.textContent = fact[1];
+            }
+            div.appendChild(value);
+            facts.appendChild(div);
+        });
+        body.appendChild(facts);
+    }
+
+    function describeUpstream(upstream) {
+        if (!upstream.reachable) {
+            return el("span", "Down for " + formatSeconds(upstream.outageSeconds), "health-down");
+        }
+        if (upstream.quarantined) {
+            return el("span", "Quarantined: " + upstream.quarantineReason, "health-down");
+        }
+        return el("span", "OK", "health-ok");
+    }
+
+    function renderChains(snapshot) {
+        replaceRows($("chains"), snapshot.chains.map(function (chain) {
+            return row([
+                chainName(chain.name, chain.color),
+                // overridden through the admin API
+                chain.overrideExpires ? chain.priority + " (until " + formatTime(chain.overrideExpires) + ")" : chain.priority,
+                el("span", chain.state, "state-" + chain.state),
+                chain.latestHeight > 0 ? "#" + chain.latestHeight : "-",
+                chain.queuedHeight > 0 ? "#" + chain.queuedHeight : "-",
+                formatAgo(chain.secondsSinceLastBlock),
+                describeUpstream(chain.upstream)
+            ]);
+        }), 7, "No chains are enabled.");
+    }
+
+    function renderRounds(snapshot) {
+        replaceRows($("rounds"), snapshot.recentRounds.map(function (round) {
+            return row([
+                chainName(round.chainName, round.color),
+                "#" + round.height,
+                formatTime(round.started),
+                formatSeconds(round.durationSeconds),
+                el("span", round.outcome, "outcome-" + round.outcome),
+                formatSeconds(round.bestDeadline)
+            ]);
+        }), 6, "No rounds have been mined yet.");
+    }
+
+    function renderDeadlines(snapshot) {
+        var rows = [];
+        snapshot.chains.forEach(function (chain) {
+            chain.bestDeadlines.forEach(function (best) {
+                rows.push(row([
+                    chainName(chain.name, chain.color),
+                    "#" + chain.latestHeight,
+                    best.accountId,
+                    formatSeconds(best.deadline)
+                ]));
+            });
+        });
+        replaceRows($("deadlines"), rows, 4, "No deadlines submitted for the latest blocks.");
+    }
+
+    function describeRoundSubmissions(rounds) {
+        if (rounds.length === 0) {
+            return "-";
+        }
+        return rounds.slice(0, 5).map(function (round) {
+            return round.active ? String(round.submissions) : "silent";
+        }).join(" / ");
+    }
+
+    function renderMiners(snapshot) {
+        replaceRows($("miners"), snapshot.miners.map(function (miner) {
+            var status = miner.connected ? "connected" : "disconnected";
+            return row([
+                miner.address,
+                miner.software,
+                el("span", status, "miner-" + status),
+                miner.pollIntervalSeconds === null ? "-" : miner.pollIntervalSeconds.toFixed(1) + "s",
+                miner.capacityTebibytes === null ? "-" : miner.capacityTebibytes.toFixed(2) + " TiB",
+                miner.accounts.length > 0 ? miner.accounts.join(", ") : "-",
+                describeRoundSubmissions(miner.recentRounds),
+                String(miner.silentRounds),
+                formatAgo(miner.secondsSinceLastSeen)
+            ]);
+        }), 9, "No miners have connected yet.");
+    }
+
+    function setStatus(text, ok) {
+        var status = $("status");
+        status.textContent = text;
+        status.className = "status " + (ok ? "ok" : "error");
+    }
+
+    function describeError(responseText) {
+        try {
+            return JSON.parse(responseText).reason;
+        } catch (e) {
+            return responseText;
+        }
+    }
+
+    function render(snapshot) {
+        $("version").textContent = "v" + snapshot.version;
+        $("queuing-mode").textContent = "Mode: " + snapshot.queuingMode;
+        renderCurrentBlock(snapshot);
+        renderChains(snapshot);
+        renderRounds(snapshot);
+        renderDeadlines(snapshot);
+        renderMiners(snapshot);
+        setStatus("Updated " + formatTime(snapshot.time), true);
+    }
+
+    function refresh() {
+        var xhr = new XMLHttpRequest();
+        xhr.open("GET", DASHBOARD_URL);
+        if (apiToken) {
+            xhr.setRequestHeader("Authorization", "Bearer " + apiToken);
+        }
+        xhr.onload = function () {
+            if (xhr.status === 200) {
+                try {
+                    render(JSON.parse(xhr.responseText));
+                } catch (e) {
+                    setStatus("Couldn't read dashboard data", false);
+                }
+            } else if (xhr.status === 401) {
+                setStatus("An API token is required, open this page with ?token=<your token>", false);
+            } else if (xhr.status === 403) {
+                setStatus("Access denied: " + describeError(xhr.responseText), false);
+            } else {
+                setStatus("Archon responded with HTTP " + xhr.status, false);
+            }
+            setTimeout(refresh, REFRESH_INTERVAL_MS);
+        };
+        xhr.onerror = function () {
+            setStatus("Can't reach Archon", false);
+            setTimeout(refresh, REFRESH_INTERVAL_MS);
+        };
+        xhr.send();
+    }
+
+    refresh();
+})();
@@ -0,0 +1,413 @@
+use actix_web::http::StatusCode;
+use actix_web::HttpRequest;
+use chrono::{DateTime, Local};
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+
+use crate::auth;
+use crate::config::MinerGroup;
+use crate::context::Archon;
+use crate::tls;
+
+// the header miners send their group's shared secret in
+pub const SECRET_HEADER: &'static str = "X-Archon-Secret";
+// submission rate limits are counted over this many seconds
+const RATE_LIMIT_WINDOW: i64 = 60;
+// how many rejections are kept for the API
+const MAX_REJECTION_HISTORY: usize = 100;
+// a miner refused again for the same reason is only warned about once per this many seconds
+const REJECTION_WARNING_INTERVAL: i64 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RejectionReason {
+    ClientCertificate,
+    Address,
+    Secret,
+    RateLimit,
+}
+
+impl RejectionReason {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RejectionReason::ClientCertificate => "clientCertificate",
+            RejectionReason::Address => "address",
+            RejectionReason::Secret => "secret",
+            RejectionReason::RateLimit => "rateLimit",
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            RejectionReason::RateLimit => StatusCode::TOO_MANY_REQUESTS,
+            _ => StatusCode::FORBIDDEN,
+        }
+    }
+}
+
+/// A miner request refused by access control, as kept for the audit trail.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Rejection {
+    pub time: String,
+    pub address: String,
+    pub software: String,
+    pub request_type: String,
+    pub reason: RejectionReason,
+    // the reason given to the miner
+    pub message: String,
+    // seconds until a rate limited miner can submit again
+    #[serde(skip)]
+    pub retry_after: Option<i64>,
+}
+
+/// Recent submissions by address for the rate limit, and the audit trail of refused requests.
+pub struct AccessState {
+    // Key = the miner's address, Value = times of its submissions within the rate limit window
+    submissions: Mutex<HashMap<IpAddr, VecDeque<DateTime<Local>>>>,
+    rejections: Mutex<VecDeque<Rejection>>,
+    rejection_counts: Mutex<HashMap<RejectionReason, u64>>,
+    // Key = tuple (miner address, reason), Value = tuple (time last warned about, refusals since not warned about)
+    rejection_warnings: Mutex<HashMap<(String, RejectionReason), (DateTime<Local>, u64)>>,
+}
+
+impl AccessState {
+    pub fn new() -> AccessState {
+        AccessState {
+            submissions: Mutex::new(HashMap::new()),
+            rejections: Mutex::new(VecDeque::new()),
+            rejection_counts: Mutex::new(HashMap::new()),
+            rejection_warnings: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+// IPv4 clients of an IPv6 socket show up as ::ffff:a.b.c.d, treat them as the IPv4 address
+fn get_canonical_address(address: IpAddr) -> IpAddr {
+    match address {
+        IpAddr::V6(ip) => {
+            let segments = ip.segments();
+            if segments[..6] == [0, 0, 0, 0, 0, 0xffff] {
+                IpAddr::V4(std::net::Ipv4Addr::new((segments[6] >> 8) as u8, segments[6] as u8, (segments[7] >> 8) as u8, segments[7] as u8))
+            } else {
+                address
+            }
+        }
+        IpAddr::V4(_) => address,
+    }
+}
+
+/// Parses an IP address, or a CIDR range such as 192.168.1.0/24, into the network address and prefix length.
+pub fn parse_network(network: &str) -> Option<(IpAddr, u8)> {
+    let mut parts = network.trim().splitn(2, '/');
+    let address: IpAddr = parts.next()?.parse().ok()?;
+    let max_prefix_length = if address.is_ipv4() { 32 } else { 128 };
+    let prefix_length = match parts.next() {
+        Some(prefix_length) => prefix_length.parse::<u8>().ok().filter(|prefix_length| *prefix_length <= max_prefix_length)?,
+        None => max_prefix_length,
+    };
+    // a ::ffff:a.b.c.d range is matched as the IPv4 range it maps, its prefix counts the 96 bits before the IPv4 address
+    match get_canonical_address(address) {
+        IpAddr::V4(network) if address.is_ipv6() => prefix_length.checked_sub(96).map(|prefix_length| (IpAddr::V4(network), prefix_length)),
+        network => Some((network, prefix_length)),
+    }
+}
+
+fn is_in_network(address: IpAddr, (network, prefix_length): (IpAddr, u8)) -> bool {
+    if prefix_length == 0 {
+        return address.is_ipv4() == network.is_ipv4();
+    }
+    match (address, network) {
+        (IpAddr::V4(address), IpAddr::V4(network)) => {
+            let mask = !0u32 << (32 - prefix_length as u32);
+            u32::from(address) & mask == u32::from(network) & mask
+        }
+        (IpAddr::V6(address), IpAddr::V6(network)) => {
+            let mask = !0u128 << (128 - prefix_length as u32);
+            u128::from(address) & mask == u128::from(network) & mask
+        }
+        _ => false,
+    }
+}
+
+/// Checks whether a miner group lets in miners from this address, groups without any addresses let in every address.
+pub fn is_address_in_group(miner_group: &MinerGroup, address: IpAddr) -> bool {
+    match &miner_group.addresses {
+        Some(addresses) if addresses.len() > 0 => addresses
+            .iter()
+            .filter_map(|network| parse_network(network))
+            .any(|network| is_in_network(get_canonical_address(address), network)),
+        _ => true,
+    }
+}
+
+/// Finds the miner group a request belongs to, going by the connection's real address and the secret it sent.
+/// Returns None if no miner groups are configured, everyone is allowed in then.
+fn get_miner_group<'a>(archon: &'a Archon, address: Option<IpAddr>, secret: Option<&str>) -> Result<Option<&'a MinerGroup>, RejectionReason> {
+    let miner_groups = match &archon.conf.miner_groups {
+        Some(miner_groups) if miner_groups.len() > 0 => miner_groups,
+        _ => return Ok(None),
+    };
+    let address = address.ok_or(RejectionReason::Address)?;
+    let address_groups: Vec<&MinerGroup> = miner_groups.iter().filter(|miner_group| is_address_in_group(miner_group, address)).collect();
+    if address_groups.len() == 0 {
+        return Err(RejectionReason::Address);
+    }
+    address_groups
+        .into_iter()
+        .find(|miner_group| match (&miner_group.secret, secret) {
+            (None, _) => true,
+            (Some(group_secret), Some(secret)) => auth::tokens_match(&*group_secret, secret),
+            (Some(_), None) => false,
+        })
+        .map(|miner_group| Some(miner_group))
+        .ok_or(RejectionReason::Secret)
+}
+
+// counts a submission against the address' rate limit, returns how many seconds to wait if it's over the limit
+fn check_submission_rate(archon: &Archon, address: IpAddr, now: DateTime<Local>) -> Result<(), i64> {
+    let limit = match archon.conf.miner_submission_rate_limit {
+        Some(limit) if limit > 0 => limit as usize,
+        _ => return Ok(()),
+    };
+    let mut submissions = archon.state.access.submissions.lock().unwrap();
+    // forget addresses which haven't submitted recently
+    submissions.retain(|_, times| times.back().map_or(false, |last| (now - *last).num_seconds() < RATE_LIMIT_WINDOW));
+    let times = submissions.entry(address).or_insert(VecDeque::new());
+    while times.front().map_or(false, |first| (now - *first).num_seconds() >= RATE_LIMIT_WINDOW) {
+        times.pop_front();
+    }
+    if times.len() >= limit {
+        let oldest = *times.front().unwrap();
+        return Err((RATE_LIMIT_WINDOW - (now - oldest).num_seconds()).max(1));
+    }
+    times.push_back(now);
+    Ok(())
+}
+
+fn reject(archon: &Archon, address: &str, software: &str, request_type: &str, reason: RejectionReason, message: String, retry_after: Option<i64>) -> Rejection {
+    let now = archon.now();
+    let rejection = Rejection {
+        time: now.to_rfc3339(),
+        address: String::from(address),
+        software: String::from(software),
+        request_type: String::from(request_type),
+        reason,
+        message,
+        retry_after,
+    };
+    // a misconfigured miner retries every few seconds, so repeats are only counted until the next warning
+    let mut rejection_warnings = archon.state.access.rejection_warnings.lock().unwrap();
+    let key = (String::from(address), reason);
+    match rejection_warnings.get(&key).cloned() {
+        Some((warned, suppressed)) if (now - warned).num_seconds() < REJECTION_WARNING_INTERVAL => {
+            debug!("ACCESS - Refused {} request from [{}] ({}): {}", request_type, address, software, rejection.message);
+            rejection_warnings.insert(key, (warned, suppressed + 1));
+        }
+        previous => {
+            match previous {
+                Some((_, suppressed)) if suppressed > 0 => {
+                    warn!("ACCESS - Refused {} request from [{}] ({}): {} | {} more refused since the last warning", request_type, address, software, rejection.message, suppressed);
+                }
+                _ => {
+                    warn!("ACCESS - Refused {} request from [{}] ({}): {}", request_type, address, software, rejection.message);
+                }
+            };
+            rejection_warnings.insert(key, (now, 0));
+        }
+    };
+    // forget miners which haven't been refused in a while
+    rejection_warnings.retain(|_, (warned, _)| (now - *warned).num_seconds() < REJECTION_WARNING_INTERVAL * 2);
+    drop(rejection_warnings);
+    *archon.state.access.rejection_counts.lock().unwrap().entry(reason).or_insert(0) += 1;
+    let mut rejections = archon.state.access.rejections.lock().unwrap();
+    rejections.push_front(rejection.clone());
+    rejections.truncate(MAX_REJECTION_HISTORY);
+    rejection
+}
+
+/// Checks a miner is allowed to use /burst: its client certificate, address, shared secret and submission rate.
+/// Returns the rejection to send back and record in the audit trail if it isn't.
+pub fn authorize_miner(req: &HttpRequest<Arc<Archon>>, request_type: &str, software: &str) -> Result<(), Rejection> {
+    let archon = req.state();
+    let peer_address = req.peer_addr().map(|peer_address| get_canonical_address(peer_address.ip()));
+    let address = match peer_address {
+        Some(peer_address) => peer_address.to_string(),
+        None => String::from("Unknown"),
+    };
+    match tls::authorize_miner(req) {
+        Ok(_) => {}
+        Err(why) => return Err(reject(archon, &*address, software, request_type, RejectionReason::ClientCertificate, why, None)),
+    };
+    let secret = req.headers().get(SECRET_HEADER).and_then(|value| value.to_str().ok());
+    let miner_group = match get_miner_group(archon, peer_address, secret) {
+        Ok(miner_group) => miner_group,
+        Err(RejectionReason::Secret) => {
+            let message = match secret {
+                Some(_) => format!("The {} header is not valid.", SECRET_HEADER),
+                None => format!("An {} header is required.", SECRET_HEADER),
+            };
+            return Err(reject(archon, &*address, software, request_type, RejectionReason::Secret, message, None));
+        }
+        Err(reason) => {
+            return Err(reject(archon, &*address, software, request_type, reason, String::from("This address is not allowed to mine through this proxy."), None));
+        }
+    };
+    if request_type.eq_ignore_ascii_case("submitNonce") {
+        match peer_address.map(|peer_address| check_submission_rate(archon, peer_address, archon.now())) {
+            Some(Err(retry_after)) => {
+                let message = format!("Too many submissions, the limit is {} per minute.", archon.conf.miner_submission_rate_limit.unwrap_or_default());
+                return Err(reject(archon, &*address, software, request_type, RejectionReason::RateLimit, message, Some(retry_after)));
+            }
+            _ => {}
+        };
+    }
+    match miner_group {
+        Some(miner_group) => trace!("ACCESS - {} request from [{}] allowed as miner group \"{}\"", request_type, address, miner_group.name),
+        None => {}
+    };
+    Ok(())
+}
+
+/// The most recently refused miner requests, newest first.
+pub fn get_rejections(archon: &Archon) -> Vec<Rejection> {
+    archon.state.access.rejections.lock().unwrap().iter().cloned().collect()
+}
+
+/// How many miner requests have been refused, for each reason.
+pub fn get_rejection_counts(archon: &Archon) -> Vec<(RejectionReason, u64)> {
+    let rejection_counts = archon.state.access.rejection_counts.lock().unwrap();
+    [RejectionReason::ClientCertificate, RejectionReason::Address, RejectionReason::Secret, RejectionReason::RateLimit]
+        .iter()
+        .map(|reason| (*reason, *rejection_counts.get(reason).unwrap_or(&0)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const CONFIG: &str = "
+gracePeriod: 30
+webServerBindAddress: 127.0.0.1
+webServerPort: 1337
+minerSubmissionRateLimit: 3
+minerGroups:
+  - name: lan
+    addresses:
+      - 192.168.1.0/24
+      - fd00::/8
+  - name: office
+    addresses:
+      - 10.0.0.5
+    secret: office-secret
+  - name: remote
+    secret: remote-secret
+";
+
+    fn create_archon(config: &str) -> Archon {
+        Archon::new(serde_yaml::from_str(config).unwrap())
+    }
+
+    fn ip(address: &str) -> IpAddr {
+        address.parse().unwrap()
+    }
+
+    fn get_group_name(archon: &Archon, address: Option<&str>, secret: Option<&str>) -> Result<Option<String>, RejectionReason> {
+        get_miner_group(archon, address.map(ip), secret).map(|miner_group| miner_group.map(|miner_group| miner_group.name.clone()))
+    }
+
+    #[test]
+    fn parses_networks() {
+        assert_eq!(parse_network("192.168.1.0/24"), Some((ip("192.168.1.0"), 24)));
+        assert_eq!(parse_network(" 10.0.0.5 "), Some((ip("10.0.0.5"), 32)));
+        assert_eq!(parse_network("0.0.0.0/0"), Some((ip("0.0.0.0"), 0)));
+        assert_eq!(parse_network("fd00::/8"), Some((ip("fd00::"), 8)));
+        assert_eq!(parse_network("::1"), Some((ip("::1"), 128)));
+        assert_eq!(parse_network("::/0"), Some((ip("::"), 0)));
+    }
+
+    #[test]
+    fn parses_mapped_ipv4_networks_as_ipv4() {
+        assert_eq!(parse_network("::ffff:192.168.1.7"), Some((ip("192.168.1.7"), 32)));
+        assert_eq!(parse_network("::ffff:10.0.0.0/104"), Some((ip("10.0.0.0"), 8)));
+        assert_eq!(parse_network("::ffff:10.0.0.0/64"), None);
+    }
+
+    #[test]
+    fn rejects_malformed_networks() {
+        for network in &["", "abc", "192.168.1/24", "192.168.1.0/", "192.168.1.0/33", "192.168.1.0/-1", "192.168.1.0/24/8", "fd00::/129", "300.0.0.1"] {
+            assert_eq!(parse_network(network), None, "{}", network);
+        }
+    }
+
+    #[test]
+    fn matches_addresses_in_networks() {
+        let network = parse_network("192.168.1.0/24").unwrap();
+        assert!(is_in_network(ip("192.168.1.0"), network));
+        assert!(is_in_network(ip("192.168.1.255"), network));
+        assert!(!is_in_network(ip("192.168.2.1"), network));
+        let network = parse_network("fd00::/8").unwrap();
+        assert!(is_in_network(ip("fdab::1"), network));
+        assert!(!is_in_network(ip("fe80::1"), network));
+        assert!(!is_in_network(ip("192.168.1.1"), network));
+    }
+
+    #[test]
+    fn matches_single_addresses_and_whole_families() {
+        let network = parse_network("10.0.0.5/32").unwrap();
+        assert!(is_in_network(ip("10.0.0.5"), network));
+        assert!(!is_in_network(ip("10.0.0.4"), network));
+        let network = parse_network("0.0.0.0/0").unwrap();
+        assert!(is_in_network(ip("8.8.8.8"), network));
+        assert!(!is_in_network(ip("2001:db8::1"), network));
+        let network = parse_network("::/0").unwrap();
+        assert!(is_in_network(ip("2001:db8::1"), network));
+        assert!(!is_in_network(ip("8.8.8.8"), network));
+    }
+
+    #[test]
+    fn finds_group_by_address_then_secret() {
+        let archon = create_archon(CONFIG);
+        assert_eq!(get_group_name(&archon, Some("192.168.1.20"), None), Ok(Some(String::from("lan"))));
+        assert_eq!(get_group_name(&archon, Some("::ffff:192.168.1.20"), None), Ok(Some(String::from("lan"))));
+        assert_eq!(get_group_name(&archon, Some("fd12::7"), None), Ok(Some(String::from("lan"))));
+        assert_eq!(get_group_name(&archon, Some("10.0.0.5"), Some("office-secret")), Ok(Some(String::from("office"))));
+        // an address in a group with the wrong secret can still get in through another group it's in
+        assert_eq!(get_group_name(&archon, Some("10.0.0.5"), Some("remote-secret")), Ok(Some(String::from("remote"))));
+        assert_eq!(get_group_name(&archon, Some("10.0.0.5"), None), Err(RejectionReason::Secret));
+        assert_eq!(get_group_name(&archon, Some("8.8.8.8"), Some("office-secret")), Err(RejectionReason::Secret));
+    }
+
+    #[test]
+    fn refuses_addresses_outside_every_group() {
+        let archon = create_archon(&*CONFIG.replace("  - name: remote\n    secret: remote-secret\n", ""));
+        assert_eq!(get_group_name(&archon, Some("8.8.8.8"), Some("office-secret")), Err(RejectionReason::Address));
+        assert_eq!(get_group_name(&archon, None, None), Err(RejectionReason::Address));
+    }
+
+    #[test]
+    fn lets_everyone_in_without_groups() {
+        let archon = create_archon("gracePeriod: 30\nwebServerBindAddress: 127.0.0.1\nwebServerPort: 1337\n");
+        assert_eq!(get_group_name(&archon, Some("8.8.8.8"), None), Ok(None));
+        assert_eq!(get_group_name(&archon, None, None), Ok(None));
+    }
+
+    #[test]
+    fn refuses_submissions_over_the_rate_limit() {
+        let archon = create_archon(CONFIG);
+        let start = Local.with_ymd_and_hms(2020, 1, 1, 12, 0, 0).unwrap();
+        let address = ip("192.168.1.20");
+        for seconds in 0..3 {
+            assert_eq!(check_submission_rate(&archon, address, start + chrono::Duration::seconds(seconds)), Ok(()));
+        }
+        // the 4th submission within a minute waits until the 1st is a minute old
+        assert_eq!(check_submission_rate(&archon, address, start + chrono::Duration::seconds(10)), Err(50));
+        assert_eq!(check_submission_rate(&archon, address, start + chrono::Duration::seconds(59)), Err(1));
+        assert_eq!(check_submission_rate(&archon, ip("192.168.1.21"), start + chrono::Duration::seconds(59)), Ok(()));
+        assert_eq!(check_submission_rate(&archon, address, start + chrono::Duration::seconds(60)), Ok(()));
+        assert_eq!(check_submission_rate(&archon, address, start + chrono::Duration::seconds(60)), Err(1));
+    }
+}
@@ -0,0 +1,113 @@
+use chrono::{DateTime, Local};
+use std::collections::BTreeSet;
+
+use crate::arbiter;
+use crate::capacity;
+use crate::context::Archon;
+use crate::estimation::{self, CapacityEstimate};
+use crate::miners;
+use crate::plots;
+
+/// Everything known about an account which mines through Archon.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountSummary {
+    pub account_id: String,
+    // the account's own plot capacity, in TiB, and whether it came from the config or the plot directories
+    pub capacity_tebibytes: Option<f64>,
+    pub capacity_source: Option<String>,
+    // the X-Capacity reported by connected miners which only mine this account, in TiB
+    pub reported_capacity_tebibytes: Option<f64>,
+    // the capacity the account's best deadlines in recent rounds suggest it's really mining with
+    pub effective_capacity: Option<CapacityEstimate>,
+    pub target_deadlines: Vec<AccountTargetDeadline>,
+}
+
+/// The target deadline an account's deadlines are held to for a chain's current block.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountTargetDeadline {
+    pub chain_name: String,
+    pub height: u32,
+    // none if nothing limits the account's deadlines
+    pub target_deadline: Option<u64>,
+    // whether the account's own plot capacity was used to calculate a dynamic deadline, rather than the farm's
+    pub uses_account_capacity: bool,
+}
+
+/// Every account with its own plot capacity, and every account a miner has submitted deadlines for.
+pub fn get_known_account_ids(archon: &Archon) -> BTreeSet<u64> {
+    let mut account_ids: BTreeSet<u64> = BTreeSet::new();
+    for capacities in &archon.conf.numeric_id_to_plots_size_in_tebibytes {
+        account_ids.extend(capacities.keys());
+    }
+    account_ids.extend(plots::get_scanned_account_ids(archon));
+    for miner in miners::get_miners(archon) {
+        account_ids.extend(miner.accounts);
+    }
+    account_ids
+}
+
+pub fn get_account_summaries(archon: &Archon, now: DateTime<Local>) -> Vec<AccountSummary> {
+    get_known_account_ids(archon)
+        .into_iter()
+        .map(|account_id| get_account_summary(archon, account_id, now))
+        .collect()
+}
+
+/// The total X-Capacity of the connected miners which have only submitted deadlines for this account, in TiB.
+/// Miners mining several accounts are left out, as there's no telling how their capacity is split.
+pub fn get_reported_account_capacity(archon: &Archon, account_id: u64, now: DateTime<Local>) -> Option<f64> {
+    let capacities_gibibytes: Vec<u64> = miners::get_miners(archon)
+        .into_iter()
+        .filter(|miner| miner.is_connected(now) && miner.accounts.len() == 1 && miner.accounts.contains(&account_id))
+        .filter_map(|miner| miner.capacity)
+        .collect();
+    if capacities_gibibytes.len() == 0 {
+        return None;
+    }
+    Some(capacities_gibibytes.iter().sum::<u64>() as f64 / 1024f64)
+}
+
+pub fn get_account_summary(archon: &Archon, account_id: u64, now: DateTime<Local>) -> AccountSummary {
+    let account_capacity = capacity::get_account_capacity(archon, account_id);
+    let mut target_deadlines = Vec::new();
+    for inner in &archon.conf.poc_chains {
+        for chain in inner {
+            if !chain.enabled.unwrap_or(true) {
+                continue;
+            }
+            let index = super::get_chain_index(archon, &*chain.url, &*chain.name);
+            let (mining_info, _) = match arbiter::get_current_chain_mining_info(archon, index) {
+                Some(mining_info) => mining_info,
+                None => continue,
+            };
+            let chain = match super::get_chain_from_index(archon, index) {
+                Some(chain) => chain,
+                None => continue,
+            };
+            let (target_deadline, _) = arbiter::get_target_deadline(
+                archon,
+                account_id,
+                mining_info.base_target,
+                index,
+                chain.target_deadline,
+                chain.numeric_id_to_target_deadline,
+            );
+            target_deadlines.push(AccountTargetDeadline {
+                chain_name: chain.name,
+                height: mining_info.height,
+                target_deadline: if target_deadline == u64::max_value() { None } else { Some(target_deadline) },
+                uses_account_capacity: account_capacity.is_some() && chain.use_dynamic_deadlines.unwrap_or_default(),
+            });
+        }
+    }
+    AccountSummary {
+        account_id: super::censor_account_id(archon, account_id),
+        capacity_tebibytes: account_capacity.map(|(capacity_tebibytes, _)| capacity_tebibytes),
+        capacity_source: account_capacity.map(|(_, capacity_source)| String::from(capacity_source.describe())),
+        reported_capacity_tebibytes: get_reported_account_capacity(archon, account_id, now),
+        effective_capacity: estimation::get_effective_capacity(archon, account_id),
+        target_deadlines,
+    }
+}
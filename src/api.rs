@@ -0,0 +1,1173 @@
+use actix_web::actix::{Actor, ActorContext, AsyncContext, StreamHandler};
+use actix_web::http::{header, ContentEncoding, Method, StatusCode};
+use actix_web::{error, ws, Error, HttpRequest, HttpResponse, Scope};
+use bytes::Bytes;
+use futures::future::{result, FutureResult};
+use futures::sync::mpsc;
+use futures::Stream;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use crate::access;
+use crate::accounts;
+use crate::arbiter::{self, ArbiterEvent};
+use crate::auth::{self, ApiScope};
+use crate::context::Archon;
+use crate::control;
+use crate::dashboard::{self, AccountDeadline, ChainStatus, CurrentBlock};
+use crate::events::{self, PublishedEvent};
+use crate::health;
+use crate::plots;
+
+type Handler = fn(&HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error>;
+
+// one year, overrides are meant to be temporary
+const MAX_OVERRIDE_DURATION: u64 = 31_536_000;
+
+struct Parameter {
+    name: &'static str,
+    location: &'static str,
+    description: &'static str,
+    required: bool,
+    value_type: ParameterType,
+}
+
+/// The range of values a parameter accepts, matching the type it's parsed as.
+#[derive(Clone, Copy)]
+enum ParameterType {
+    U8,
+    U32,
+    U64,
+}
+
+impl ParameterType {
+    fn get_schema(&self) -> Value {
+        match self {
+            ParameterType::U8 => json!({ "type": "integer", "minimum": 0, "maximum": u8::max_value() }),
+            ParameterType::U32 => json!({ "type": "integer", "format": "int64", "minimum": 0, "maximum": u32::max_value() }),
+            ParameterType::U64 => json!({ "type": "integer", "format": "int64", "minimum": 0 }),
+        }
+    }
+}
+
+/// A resource served under /api/v1. The routes and the OpenAPI document are both built from this list.
+struct Endpoint {
+    method: Method,
+    path: &'static str,
+    operation_id: &'static str,
+    summary: &'static str,
+    parameters: &'static [Parameter],
+    scope: ApiScope,
+    status: StatusCode,
+    content_type: &'static str,
+    schema: Value,
+    handler: Handler,
+}
+
+fn get_endpoints() -> Vec<Endpoint> {
+    vec![
+        Endpoint {
+            method: Method::GET,
+            path: "/status",
+            operation_id: "getStatus",
+            summary: "Archon's version, queuing mode and the block currently being mined.",
+            parameters: &[],
+            scope: ApiScope::Read,
+            status: StatusCode::OK,
+            content_type: "application/json",
+            schema: schema_ref("Status"),
+            handler: handle_get_status,
+        },
+        Endpoint {
+            method: Method::GET,
+            path: "/chains",
+            operation_id: "getChains",
+            summary: "Every enabled chain, with its queue state and upstream health.",
+            parameters: &[],
+            scope: ApiScope::Read,
+            status: StatusCode::OK,
+            content_type: "application/json",
+            schema: array_of(schema_ref("Chain")),
+            handler: handle_get_chains,
+        },
+        Endpoint {
+            method: Method::GET,
+            path: "/chains/{id}",
+            operation_id: "getChain",
+            summary: "A single chain, by index.",
+            parameters: &[Parameter {
+                name: "id",
+                location: "path",
+                description: "The chain's index.",
+                required: true,
+                value_type: ParameterType::U8,
+            }],
+            scope: ApiScope::Read,
+            status: StatusCode::OK,
+            content_type: "application/json",
+            schema: schema_ref("Chain"),
+            handler: handle_get_chain,
+        },
+        Endpoint {
+            method: Method::GET,
+            path: "/queue",
+            operation_id: "getQueue",
+            summary: "The block being mined, the blocks waiting to be mined and which one is next in line.",
+            parameters: &[],
+            scope: ApiScope::Read,
+            status: StatusCode::OK,
+            content_type: "application/json",
+            schema: schema_ref("Queue"),
+            handler: handle_get_queue,
+        },
+        Endpoint {
+            method: Method::GET,
+            path: "/rounds",
+            operation_id: "getRounds",
+            summary: "The most recently started rounds, newest first.",
+            parameters: &[Parameter {
+                name: "limit",
+                location: "query",
+                description: "The maximum number of rounds to return.",
+                required: false,
+                value_type: ParameterType::U64,
+            }],
+            scope: ApiScope::Read,
+            status: StatusCode::OK,
+            content_type: "application/json",
+            schema: array_of(schema_ref("Round")),
+            handler: handle_get_rounds,
+        },
+        Endpoint {
+            method: Method::GET,
+            path: "/miners",
+            operation_id: "getMiners",
+            summary: "Every miner seen in the last 24 hours, most recently seen first.",
+            parameters: &[],
+            scope: ApiScope::Read,
+            status: StatusCode::OK,
+            content_type: "application/json",
+            schema: array_of(schema_ref("Miner")),
+            handler: handle_get_miners,
+        },
+        Endpoint {
+            method: Method::GET,
+            path: "/accounts",
+            operation_id: "getAccounts",
+            summary: "Every account with its own plot capacity or which has submitted deadlines, with the target deadline it's held to for each chain's current block.",
+            parameters: &[],
+            scope: ApiScope::Read,
+            status: StatusCode::OK,
+            content_type: "application/json",
+            schema: array_of(schema_ref("Account")),
+            handler: handle_get_accounts,
+        },
+        Endpoint {
+            method: Method::GET,
+            path: "/plot-health",
+            operation_id: "getPlotHealth",
+            summary: "Checks each account's best deadlines in recent rounds against what its plots size should get, flagging accounts whose deadlines are consistently worse.",
+            parameters: &[],
+            scope: ApiScope::Read,
+            status: StatusCode::OK,
+            content_type: "application/json",
+            schema: array_of(schema_ref("PlotHealth")),
+            handler: handle_get_plot_health,
+        },
+        Endpoint {
+            method: Method::GET,
+            path: "/rejections",
+            operation_id: "getRejections",
+            summary: "The most recent miner requests refused by access control, newest first.",
+            parameters: &[],
+            scope: ApiScope::Read,
+            status: StatusCode::OK,
+            content_type: "application/json",
+            schema: array_of(schema_ref("Rejection")),
+            handler: handle_get_rejections,
+        },
+        Endpoint {
+            method: Method::GET,
+            path: "/plots",
+            operation_id: "getPlots",
+            summary: "The plot files found by the latest scan of the plot directories, with each account's capacity and any overlapping plots.",
+            parameters: &[],
+            scope: ApiScope::Read,
+            status: StatusCode::OK,
+            content_type: "application/json",
+            schema: schema_ref("PlotScan"),
+            handler: handle_get_plots,
+        },
+        Endpoint {
+            method: Method::GET,
+            path: "/deadlines",
+            operation_id: "getDeadlines",
+            summary: "Each account's best deadline, by chain and block height.",
+            parameters: &[Parameter {
+                name: "height",
+                location: "query",
+                description: "Only return deadlines for this block height.",
+                required: false,
+                value_type: ParameterType::U32,
+            }],
+            scope: ApiScope::Read,
+            status: StatusCode::OK,
+            content_type: "application/json",
+            schema: array_of(schema_ref("BlockDeadlines")),
+            handler: handle_get_deadlines,
+        },
+        Endpoint {
+            method: Method::GET,
+            path: "/events",
+            operation_id: "getEvents",
+            summary: "A live stream of events as Server-Sent Events. Also available as a WebSocket feed, one JSON event per text message, by connecting with a WebSocket upgrade request.",
+            parameters: &[],
+            scope: ApiScope::Read,
+            status: StatusCode::OK,
+            content_type: "text/event-stream",
+            schema: schema_ref("Event"),
+            handler: handle_get_events,
+        },
+        Endpoint {
+            method: Method::POST,
+            path: "/admin/chains/{id}/pause",
+            operation_id: "pauseChain",
+            summary: "Takes a chain out of the queue until it's resumed. If it's being mined, the next queued block is started.",
+            parameters: &[Parameter {
+                name: "id",
+                location: "path",
+                description: "The chain's index.",
+                required: true,
+                value_type: ParameterType::U8,
+            }],
+            scope: ApiScope::Admin,
+            status: StatusCode::OK,
+            content_type: "application/json",
+            schema: schema_ref("AdminResult"),
+            handler: handle_pause_chain,
+        },
+        Endpoint {
+            method: Method::POST,
+            path: "/admin/chains/{id}/resume",
+            operation_id: "resumeChain",
+            summary: "Puts a paused chain back in the queue.",
+            parameters: &[Parameter {
+                name: "id",
+                location: "path",
+                description: "The chain's index.",
+                required: true,
+                value_type: ParameterType::U8,
+            }],
+            scope: ApiScope::Admin,
+            status: StatusCode::OK,
+            content_type: "application/json",
+            schema: schema_ref("AdminResult"),
+            handler: handle_resume_chain,
+        },
+        Endpoint {
+            method: Method::POST,
+            path: "/admin/chains/{id}/start",
+            operation_id: "forceStartChain",
+            summary: "Starts a chain's latest block now, interrupting the current round. The round can't be interrupted by other chains' blocks.",
+            parameters: &[Parameter {
+                name: "id",
+                location: "path",
+                description: "The chain's index.",
+                required: true,
+                value_type: ParameterType::U8,
+            }],
+            scope: ApiScope::Admin,
+            status: StatusCode::ACCEPTED,
+            content_type: "application/json",
+            schema: schema_ref("AdminResult"),
+            handler: handle_force_start_chain,
+        },
+        Endpoint {
+            method: Method::POST,
+            path: "/admin/chains/{id}/drop",
+            operation_id: "dropQueuedBlock",
+            summary: "Drops a chain's queued block without mining it.",
+            parameters: &[Parameter {
+                name: "id",
+                location: "path",
+                description: "The chain's index.",
+                required: true,
+                value_type: ParameterType::U8,
+            }],
+            scope: ApiScope::Admin,
+            status: StatusCode::ACCEPTED,
+            content_type: "application/json",
+            schema: schema_ref("AdminResult"),
+            handler: handle_drop_queued_block,
+        },
+        Endpoint {
+            method: Method::POST,
+            path: "/admin/chains/{id}/override",
+            operation_id: "overrideChain",
+            summary: "Temporarily overrides a chain's priority and/or target deadline. Replaces any earlier override.",
+            parameters: &[
+                Parameter {
+                    name: "id",
+                    location: "path",
+                    description: "The chain's index.",
+                    required: true,
+                    value_type: ParameterType::U8,
+                },
+                Parameter {
+                    name: "priority",
+                    location: "query",
+                    description: "The chain's priority while the override lasts.",
+                    required: false,
+                    value_type: ParameterType::U8,
+                },
+                Parameter {
+                    name: "targetDeadline",
+                    location: "query",
+                    description: "The chain's target deadline while the override lasts.",
+                    required: false,
+                    value_type: ParameterType::U64,
+                },
+                Parameter {
+                    name: "duration",
+                    location: "query",
+                    description: "How long the override lasts, in seconds. Defaults to 3600.",
+                    required: false,
+                    value_type: ParameterType::U64,
+                },
+            ],
+            scope: ApiScope::Admin,
+            status: StatusCode::OK,
+            content_type: "application/json",
+            schema: schema_ref("AdminResult"),
+            handler: handle_override_chain,
+        },
+        Endpoint {
+            method: Method::DELETE,
+            path: "/admin/chains/{id}/override",
+            operation_id: "clearChainOverride",
+            summary: "Removes a chain's override before it runs out.",
+            parameters: &[Parameter {
+                name: "id",
+                location: "path",
+                description: "The chain's index.",
+                required: true,
+                value_type: ParameterType::U8,
+            }],
+            scope: ApiScope::Admin,
+            status: StatusCode::OK,
+            content_type: "application/json",
+            schema: schema_ref("AdminResult"),
+            handler: handle_clear_chain_override,
+        },
+        Endpoint {
+            method: Method::DELETE,
+            path: "/admin/chains/{id}/deadlines/{height}",
+            operation_id: "clearBestDeadlines",
+            summary: "Forgets every account's best deadline for a chain's block, so the next deadline from each account is forwarded again.",
+            parameters: &[
+                Parameter {
+                    name: "id",
+                    location: "path",
+                    description: "The chain's index.",
+                    required: true,
+                    value_type: ParameterType::U8,
+                },
+                Parameter {
+                    name: "height",
+                    location: "path",
+                    description: "The block height.",
+                    required: true,
+                    value_type: ParameterType::U32,
+                },
+            ],
+            scope: ApiScope::Admin,
+            status: StatusCode::OK,
+            content_type: "application/json",
+            schema: schema_ref("AdminResult"),
+            handler: handle_clear_best_deadlines,
+        },
+        Endpoint {
+            method: Method::GET,
+            path: "/openapi.json",
+            operation_id: "getOpenApiDocument",
+            summary: "This document.",
+            parameters: &[],
+            scope: ApiScope::Read,
+            status: StatusCode::OK,
+            content_type: "application/json",
+            schema: json!({ "type": "object" }),
+            handler: handle_get_openapi_document,
+        },
+    ]
+}
+
+/// Registers every /api/v1 resource on the scope.
+pub fn configure_scope(scope: Scope<Arc<Archon>>) -> Scope<Arc<Archon>> {
+    let mut scope = scope;
+    let endpoints = get_endpoints();
+    // several endpoints can share a path with different methods, each path is registered once with all of its methods
+    let mut paths: Vec<&'static str> = Vec::new();
+    for endpoint in endpoints.iter() {
+        if !paths.contains(&endpoint.path) {
+            paths.push(endpoint.path);
+        }
+    }
+    for path in paths {
+        let routes: Vec<(Method, Handler, ApiScope)> = endpoints
+            .iter()
+            .filter(|endpoint| endpoint.path == path)
+            .map(|endpoint| (endpoint.method.clone(), endpoint.handler, endpoint.scope))
+            .collect();
+        scope = scope.resource(path, move |r| {
+            for (method, handler, required_scope) in routes {
+                r.method(method).a(move |req: &HttpRequest<Arc<Archon>>| match auth::authorize(req, required_scope) {
+                    Ok(_) => handler(req),
+                    Err((status_code, reason)) => create_error_response(status_code, &*reason),
+                });
+            }
+            r.route().a(handle_method_not_allowed);
+        });
+    }
+    scope.default_resource(|r| {
+        r.route().a(handle_not_found);
+    })
+}
+
+#[derive(Serialize)]
+struct ApiError {
+    result: &'static str,
+    reason: String,
+}
+
+pub fn create_error_response(status_code: StatusCode, reason: &str) -> FutureResult<HttpResponse, Error> {
+    let body = serde_json::to_string(&ApiError {
+        result: "failure",
+        reason: String::from(reason),
+    })
+    .unwrap_or_default();
+    result(Ok(HttpResponse::build(status_code)
+        .header(header::USER_AGENT, super::web::get_user_agent_str())
+        .content_type("application/json")
+        .body(body)))
+}
+
+fn create_json_response<T: Serialize>(value: &T) -> FutureResult<HttpResponse, Error> {
+    create_json_response_with_status(StatusCode::OK, value)
+}
+
+fn create_json_response_with_status<T: Serialize>(status_code: StatusCode, value: &T) -> FutureResult<HttpResponse, Error> {
+    match serde_json::to_string(value) {
+        Ok(body) => result(Ok(HttpResponse::build(status_code)
+            .header(header::USER_AGENT, super::web::get_user_agent_str())
+            .content_type("application/json")
+            .body(body))),
+        Err(why) => {
+            error!("API - Couldn't serialize response: {:?}", why);
+            create_error_response(StatusCode::INTERNAL_SERVER_ERROR, "Couldn't serialize response.")
+        }
+    }
+}
+
+fn handle_not_found(_req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    create_error_response(StatusCode::NOT_FOUND, "There is no such resource.")
+}
+
+fn handle_method_not_allowed(req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    create_error_response(
+        StatusCode::METHOD_NOT_ALLOWED,
+        format!("{} is not allowed for this resource.", req.method()).as_str(),
+    )
+}
+
+// returns Ok(None) if the parameter wasn't given, or an error response if it couldn't be parsed
+fn get_query_parameter<T: std::str::FromStr>(req: &HttpRequest<Arc<Archon>>, name: &str) -> Result<Option<T>, FutureResult<HttpResponse, Error>> {
+    match req.query().get(name) {
+        Some(value) => match value.parse::<T>() {
+            Ok(parsed) => Ok(Some(parsed)),
+            Err(_) => Err(create_error_response(
+                StatusCode::BAD_REQUEST,
+                format!("Couldn't parse the {} parameter.", name).as_str(),
+            )),
+        },
+        None => Ok(None),
+    }
+}
+
+// returns the chain index from the path, or an error response if there's no chain with that index
+fn get_chain_index_parameter(req: &HttpRequest<Arc<Archon>>) -> Result<u8, FutureResult<HttpResponse, Error>> {
+    match req.match_info().get("id").unwrap_or("").parse::<u8>() {
+        Ok(index) => match super::get_configured_chain_from_index(req.state(), index) {
+            Some(_) => Ok(index),
+            None => Err(create_error_response(StatusCode::NOT_FOUND, "There is no chain with that index.")),
+        },
+        Err(_) => Err(create_error_response(StatusCode::BAD_REQUEST, "Chain index must be a number from 0 to 255.")),
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Status {
+    version: String,
+    time: String,
+    queuing_mode: String,
+    grace_period: u16,
+    current_block: Option<CurrentBlock>,
+}
+
+fn handle_get_status(req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    let archon = req.state();
+    let now = archon.now();
+    create_json_response(&Status {
+        version: String::from(super::VERSION),
+        time: now.to_rfc3339(),
+        queuing_mode: String::from(dashboard::get_queuing_mode(archon)),
+        grace_period: archon.conf.grace_period,
+        current_block: dashboard::get_current_block(archon, now),
+    })
+}
+
+fn handle_get_chains(req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    let archon = req.state();
+    create_json_response(&dashboard::get_chain_statuses(archon, archon.now()))
+}
+
+fn handle_get_chain(req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    let archon = req.state();
+    let index = match get_chain_index_parameter(req) {
+        Ok(index) => index,
+        Err(response) => return response,
+    };
+    match dashboard::get_chain_status(archon, index, archon.now()) {
+        Some(chain_status) => create_json_response(&chain_status),
+        None => create_error_response(StatusCode::NOT_FOUND, "There is no chain with that index."),
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Queue {
+    current_block: Option<CurrentBlock>,
+    next_chain_index: Option<u8>,
+    queued: Vec<ChainStatus>,
+}
+
+fn handle_get_queue(req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    let archon = req.state();
+    let now = archon.now();
+    create_json_response(&Queue {
+        current_block: dashboard::get_current_block(archon, now),
+        next_chain_index: arbiter::get_next_queued_chain_index(archon, now),
+        queued: dashboard::get_chain_statuses(archon, now)
+            .into_iter()
+            .filter(|chain_status| chain_status.queued_height < chain_status.latest_height)
+            .collect(),
+    })
+}
+
+fn handle_get_rounds(req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    let archon = req.state();
+    let limit = match get_query_parameter::<usize>(req, "limit") {
+        Ok(limit) => limit.unwrap_or(usize::max_value()),
+        Err(response) => return response,
+    };
+    let mut rounds = dashboard::get_round_summaries(archon, archon.now());
+    rounds.truncate(limit);
+    create_json_response(&rounds)
+}
+
+fn handle_get_miners(req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    let archon = req.state();
+    create_json_response(&dashboard::get_miner_summaries(archon, archon.now()))
+}
+
+fn handle_get_accounts(req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    let archon = req.state();
+    create_json_response(&accounts::get_account_summaries(archon, archon.now()))
+}
+
+fn handle_get_plot_health(req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    let archon = req.state();
+    create_json_response(&health::get_plot_health(archon, archon.now()))
+}
+
+fn handle_get_rejections(req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    create_json_response(&access::get_rejections(req.state()))
+}
+
+fn handle_get_plots(req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    match plots::get_plot_scan(req.state()) {
+        Some(plot_scan) => create_json_response(&plot_scan),
+        None => create_error_response(StatusCode::NOT_FOUND, "There are no plot directories configured."),
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BlockDeadlines {
+    chain_index: u8,
+    height: u32,
+    deadlines: Vec<AccountDeadline>,
+}
+
+fn handle_get_deadlines(req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    let archon = req.state();
+    let height = match get_query_parameter::<u32>(req, "height") {
+        Ok(height) => height,
+        Err(response) => return response,
+    };
+    let mut blocks: Vec<(u8, u32)> = arbiter::get_best_deadlines(archon)
+        .keys()
+        .filter(|(_, block_height)| height.map_or(true, |height| *block_height == height))
+        .cloned()
+        .collect();
+    blocks.sort_by(|(a_index, a_height), (b_index, b_height)| b_height.cmp(a_height).then(a_index.cmp(b_index)));
+    let block_deadlines: Vec<BlockDeadlines> = blocks
+        .into_iter()
+        .map(|(chain_index, height)| BlockDeadlines {
+            chain_index,
+            height,
+            deadlines: dashboard::get_account_deadlines(archon, chain_index, height),
+        })
+        .filter(|block_deadlines| block_deadlines.deadlines.len() > 0)
+        .collect();
+    create_json_response(&block_deadlines)
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AdminResult {
+    result: &'static str,
+    action: &'static str,
+    chain_index: u8,
+    message: String,
+}
+
+fn create_admin_response(status_code: StatusCode, action: &'static str, chain_index: u8, message: &str) -> FutureResult<HttpResponse, Error> {
+    create_json_response_with_status(status_code, &AdminResult {
+        result: "success",
+        action,
+        chain_index,
+        message: String::from(message),
+    })
+}
+
+fn handle_pause_chain(req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    let archon = req.state();
+    let index = match get_chain_index_parameter(req) {
+        Ok(index) => index,
+        Err(response) => return response,
+    };
+    if !control::pause_chain(archon, index, &*auth::get_requester(req), archon.now()) {
+        return create_error_response(StatusCode::CONFLICT, "This chain is already paused.");
+    }
+    arbiter::send_arbiter_event(archon, ArbiterEvent::ChainPaused(index));
+    create_admin_response(StatusCode::OK, "pause", index, "Paused, its blocks won't be mined until it's resumed.")
+}
+
+fn handle_resume_chain(req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    let archon = req.state();
+    let index = match get_chain_index_parameter(req) {
+        Ok(index) => index,
+        Err(response) => return response,
+    };
+    if !control::resume_chain(archon, index, &*auth::get_requester(req), archon.now()) {
+        return create_error_response(StatusCode::CONFLICT, "This chain isn't paused.");
+    }
+    arbiter::send_arbiter_event(archon, ArbiterEvent::ChainResumed(index));
+    create_admin_response(StatusCode::OK, "resume", index, "Resumed.")
+}
+
+// the arbiter starts the block, so the round is only started once it has handled everything before it
+fn handle_force_start_chain(req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    let archon = req.state();
+    let index = match get_chain_index_parameter(req) {
+        Ok(index) => index,
+        Err(response) => return response,
+    };
+    let (height, _) = arbiter::get_latest_chain_info(archon, index);
+    if height == 0 {
+        return create_error_response(StatusCode::CONFLICT, "This chain doesn't have a block to start yet.");
+    }
+    arbiter::send_arbiter_event(archon, ArbiterEvent::ForceStart {
+        chain_index: index,
+        requested_by: auth::get_requester(req),
+    });
+    create_admin_response(StatusCode::ACCEPTED, "forceStart", index, format!("#{} will be started.", height).as_str())
+}
+
+fn handle_drop_queued_block(req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    let archon = req.state();
+    let index = match get_chain_index_parameter(req) {
+        Ok(index) => index,
+        Err(response) => return response,
+    };
+    let latest_height = match dashboard::get_chain_status(archon, index, archon.now()) {
+        Some(chain_status) if chain_status.queued_height < chain_status.latest_height => chain_status.latest_height,
+        _ => return create_error_response(StatusCode::CONFLICT, "This chain doesn't have a block queued."),
+    };
+    arbiter::send_arbiter_event(archon, ArbiterEvent::DropQueuedBlock {
+        chain_index: index,
+        requested_by: auth::get_requester(req),
+    });
+    create_admin_response(StatusCode::ACCEPTED, "dropQueuedBlock", index, format!("#{} will be dropped.", latest_height).as_str())
+}
+
+fn handle_override_chain(req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    let archon = req.state();
+    let index = match get_chain_index_parameter(req) {
+        Ok(index) => index,
+        Err(response) => return response,
+    };
+    let priority = match get_query_parameter::<u8>(req, "priority") {
+        Ok(priority) => priority,
+        Err(response) => return response,
+    };
+    let target_deadline = match get_query_parameter::<u64>(req, "targetDeadline") {
+        Ok(target_deadline) => target_deadline,
+        Err(response) => return response,
+    };
+    let duration = match get_query_parameter::<u64>(req, "duration") {
+        Ok(duration) => duration.unwrap_or(control::DEFAULT_OVERRIDE_DURATION),
+        Err(response) => return response,
+    };
+    if priority.is_none() && target_deadline.is_none() {
+        return create_error_response(StatusCode::BAD_REQUEST, "A priority and/or targetDeadline is required.");
+    }
+    if duration == 0 || duration > MAX_OVERRIDE_DURATION {
+        return create_error_response(
+            StatusCode::BAD_REQUEST,
+            format!("The duration must be from 1 to {} seconds.", MAX_OVERRIDE_DURATION).as_str(),
+        );
+    }
+    control::override_chain(archon, index, priority, target_deadline, duration, &*auth::get_requester(req), archon.now());
+    arbiter::send_arbiter_event(archon, ArbiterEvent::OverrideChanged(index));
+    create_admin_response(StatusCode::OK, "override", index, format!("Overridden for {} seconds.", duration).as_str())
+}
+
+fn handle_clear_chain_override(req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    let archon = req.state();
+    let index = match get_chain_index_parameter(req) {
+        Ok(index) => index,
+        Err(response) => return response,
+    };
+    if !control::clear_chain_override(archon, index, &*auth::get_requester(req)) {
+        return create_error_response(StatusCode::CONFLICT, "This chain doesn't have an override.");
+    }
+    arbiter::send_arbiter_event(archon, ArbiterEvent::OverrideChanged(index));
+    create_admin_response(StatusCode::OK, "clearOverride", index, "Override cleared.")
+}
+
+fn handle_clear_best_deadlines(req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    let archon = req.state();
+    let index = match get_chain_index_parameter(req) {
+        Ok(index) => index,
+        Err(response) => return response,
+    };
+    let height = match req.match_info().get("height").unwrap_or("").parse::<u32>() {
+        Ok(height) => height,
+        Err(_) => return create_error_response(StatusCode::BAD_REQUEST, "Block height must be a number."),
+    };
+    if !control::clear_best_deadlines(archon, index, height, &*auth::get_requester(req)) {
+        return create_error_response(StatusCode::NOT_FOUND, "There are no best deadlines for that chain's block height.");
+    }
+    create_admin_response(StatusCode::OK, "clearBestDeadlines", index, format!("Cleared the best deadlines for #{}.", height).as_str())
+}
+
+fn handle_get_events(req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    let archon = req.state();
+    debug!("Events Request from [{}] (Method: {})", req.connection_info().remote().unwrap_or("Unknown"), req.method().to_string());
+    let is_websocket = match req.headers().get(header::UPGRADE) {
+        Some(upgrade) => upgrade.to_str().unwrap_or("").eq_ignore_ascii_case("websocket"),
+        None => false,
+    };
+    if is_websocket {
+        return result(ws::start(req, EventFeed { receiver: Some(events::subscribe(archon, None)) }));
+    }
+    // browsers send the id of the last event they saw when they reconnect
+    let last_event_id = req
+        .headers()
+        .get("Last-Event-ID")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+    let stream = events::subscribe(archon, last_event_id)
+        .map(|published_event| Bytes::from(published_event.to_sse()))
+        .map_err(|_| error::ErrorInternalServerError("Event stream closed."));
+    result(Ok(HttpResponse::build(StatusCode::OK)
+        .header(header::USER_AGENT, super::web::get_user_agent_str())
+        .header(header::CACHE_CONTROL, "no-cache")
+        .content_type("text/event-stream")
+        // compression would hold events back until its buffer fills
+        .content_encoding(ContentEncoding::Identity)
+        .streaming(stream)))
+}
+
+/// Sends each published event to a WebSocket client as a JSON text message.
+struct EventFeed {
+    receiver: Option<mpsc::Receiver<PublishedEvent>>,
+}
+
+impl Actor for EventFeed {
+    type Context = ws::WebsocketContext<Self, Arc<Archon>>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        match self.receiver.take() {
+            Some(receiver) => {
+                ctx.add_stream(receiver);
+            }
+            None => {}
+        };
+    }
+}
+
+impl StreamHandler<PublishedEvent, ()> for EventFeed {
+    fn handle(&mut self, published_event: PublishedEvent, ctx: &mut Self::Context) {
+        ctx.text(published_event.to_json());
+    }
+}
+
+impl StreamHandler<ws::Message, ws::ProtocolError> for EventFeed {
+    fn handle(&mut self, message: ws::Message, ctx: &mut Self::Context) {
+        match message {
+            ws::Message::Ping(message) => ctx.pong(&message),
+            ws::Message::Close(_) => ctx.stop(),
+            _ => {}
+        };
+    }
+}
+
+fn handle_get_openapi_document(_req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    create_json_response(&get_openapi_document())
+}
+
+fn schema_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", name) })
+}
+
+fn array_of(items: Value) -> Value {
+    json!({ "type": "array", "items": items })
+}
+
+fn get_openapi_document() -> Value {
+    let mut paths = serde_json::Map::new();
+    for endpoint in get_endpoints() {
+        let parameters: Vec<Value> = endpoint
+            .parameters
+            .iter()
+            .map(|parameter| {
+                json!({
+                    "name": parameter.name,
+                    "in": parameter.location,
+                    "description": parameter.description,
+                    "required": parameter.required,
+                    "schema": parameter.value_type.get_schema(),
+                })
+            })
+            .collect();
+        let mut responses = json!({
+            "401": error_response_schema("An API token is required, or the one given isn't valid."),
+            "403": error_response_schema("The API token doesn't have the scope needed, or the request isn't from the host machine in loopback only mode."),
+            "405": error_response_schema("The method isn't allowed for this resource."),
+        });
+        responses[endpoint.status.as_str()] = json!({
+            "description": endpoint.status.canonical_reason().unwrap_or("OK"),
+            "content": { endpoint.content_type: { "schema": endpoint.schema } },
+        });
+        if parameters.len() > 0 {
+            responses["400"] = error_response_schema("A parameter couldn't be parsed.");
+        }
+        if endpoint.path.contains('{') {
+            responses["404"] = error_response_schema("Nothing was found with that id.");
+        }
+        if endpoint.method != Method::GET {
+            responses["409"] = error_response_schema("The chain isn't in a state this can be done in, eg pausing a chain which is already paused.");
+        }
+        let path = paths.entry(String::from(endpoint.path)).or_insert(json!({}));
+        path[endpoint.method.as_str().to_lowercase()] = json!({
+            "operationId": endpoint.operation_id,
+            "summary": endpoint.summary,
+            "x-archon-scope": endpoint.scope.describe(),
+            "parameters": parameters,
+            "responses": responses,
+        });
+    }
+    json!({
+        "openapi": "3.0.0",
+        "info": {
+            "title": "Archon API",
+            "version": super::VERSION,
+        },
+        "servers": [{ "url": "/api/v1" }],
+        "paths": paths,
+        "components": {
+            "schemas": get_schemas(),
+            // only enforced when apiTokens are configured
+            "securitySchemes": {
+                "bearerToken": { "type": "http", "scheme": "bearer" },
+                "queryToken": { "type": "apiKey", "in": "query", "name": "token" },
+            },
+        },
+        "security": [{ "bearerToken": [] }, { "queryToken": [] }],
+    })
+}
+
+fn error_response_schema(description: &str) -> Value {
+    json!({
+        "description": description,
+        "content": { "application/json": { "schema": schema_ref("Error") } },
+    })
+}
+
+fn get_schemas() -> Value {
+    let integer = json!({ "type": "integer" });
+    let nullable_integer = json!({ "type": "integer", "nullable": true });
+    let string = json!({ "type": "string" });
+    let date_time = json!({ "type": "string", "format": "date-time" });
+    json!({
+        "Error": {
+            "type": "object",
+            "properties": {
+                "result": { "type": "string", "enum": ["failure"] },
+                "reason": string,
+            },
+        },
+        "Status": {
+            "type": "object",
+            "properties": {
+                "version": string,
+                "time": date_time,
+                "queuingMode": { "type": "string", "enum": ["priority", "fairShare", "fifo"] },
+                "gracePeriod": integer,
+                "currentBlock": schema_ref("CurrentBlock"),
+            },
+        },
+        "CurrentBlock": {
+            "type": "object",
+            "nullable": true,
+            "properties": {
+                "chainName": string,
+                "color": string,
+                "height": integer,
+                "baseTarget": integer,
+                "generationSignature": string,
+                "targetDeadline": integer,
+                "started": date_time,
+                "elapsedSeconds": integer,
+                "bestDeadline": nullable_integer,
+            },
+        },
+        "Chain": {
+            "type": "object",
+            "properties": {
+                "index": integer,
+                "name": string,
+                "color": string,
+                "priority": integer,
+                "state": { "type": "string", "enum": ["idle", "queued", "mining", "outage", "quarantined", "paused", "scheduledOff"] },
+                "latestHeight": integer,
+                "queuedHeight": integer,
+                "secondsSinceLastBlock": nullable_integer,
+                "overrideExpires": { "type": "string", "format": "date-time", "nullable": true },
+                "plotCapacityTebibytes": { "type": "number" },
+                "plotCapacitySource": { "type": "string", "enum": ["config", "miners", "plots"] },
+                "upstream": {
+                    "type": "object",
+                    "properties": {
+                        "reachable": { "type": "boolean" },
+                        "outageSeconds": nullable_integer,
+                        "quarantined": { "type": "boolean" },
+                        "quarantineReason": { "type": "string", "nullable": true },
+                    },
+                },
+                "bestDeadlines": array_of(schema_ref("AccountDeadline")),
+            },
+        },
+        "Queue": {
+            "type": "object",
+            "properties": {
+                "currentBlock": schema_ref("CurrentBlock"),
+                "nextChainIndex": nullable_integer,
+                "queued": array_of(schema_ref("Chain")),
+            },
+        },
+        "Round": {
+            "type": "object",
+            "properties": {
+                "chainName": string,
+                "color": string,
+                "height": integer,
+                "baseTarget": integer,
+                "started": date_time,
+                "durationSeconds": integer,
+                "outcome": { "type": "string", "enum": ["mining", "finished", "interrupted", "requeued"] },
+                "bestDeadline": nullable_integer,
+            },
+        },
+        "Miner": {
+            "type": "object",
+            "properties": {
+                "address": string,
+                "software": string,
+                "connected": { "type": "boolean" },
+                "firstSeen": date_time,
+                "lastSeen": date_time,
+                "secondsSinceLastSeen": integer,
+                "pollIntervalSeconds": { "type": "number", "nullable": true },
+                "capacityTebibytes": { "type": "number", "nullable": true },
+                "accounts": array_of(string.clone()),
+                "totalSubmissions": integer,
+                "silentRounds": integer,
+                "recentRounds": array_of(schema_ref("MinerRound")),
+            },
+        },
+        "MinerRound": {
+            "type": "object",
+            "properties": {
+                "chainName": string,
+                "height": integer,
+                "submissions": integer,
+                "active": { "type": "boolean" },
+            },
+        },
+        "Account": {
+            "type": "object",
+            "properties": {
+                "accountId": string,
+                "capacityTebibytes": { "type": "number", "nullable": true },
+                "capacitySource": { "type": "string", "enum": ["config", "plots"], "nullable": true },
+                "reportedCapacityTebibytes": { "type": "number", "nullable": true },
+                "effectiveCapacity": schema_ref("CapacityEstimate"),
+                "targetDeadlines": array_of(schema_ref("AccountTargetDeadline")),
+            },
+        },
+        "CapacityEstimate": {
+            "type": "object",
+            "description": "An account's plot capacity estimated from its best deadlines in recent rounds, with a 95% confidence interval. Null until it has submitted deadlines for at least 3 rounds.",
+            "nullable": true,
+            "properties": {
+                "capacityTebibytes": { "type": "number" },
+                "lowTebibytes": { "type": "number" },
+                "highTebibytes": { "type": "number" },
+                "rounds": integer,
+                "roundsWithDeadlines": integer,
+            },
+        },
+        "PlotHealth": {
+            "type": "object",
+            "properties": {
+                "accountId": string,
+                "status": { "type": "string", "enum": ["healthy", "suspect", "unhealthy", "insufficientData", "noExpectedCapacity"] },
+                "expectedCapacityTebibytes": { "type": "number", "nullable": true },
+                "expectedCapacitySource": { "type": "string", "enum": ["config", "plots", "miners"], "nullable": true },
+                "effectiveCapacity": schema_ref("CapacityEstimate"),
+                "performance": { "type": "number", "nullable": true },
+                "confidence": { "type": "number", "nullable": true, "description": "How sure the check is that the account's deadlines are worse than expected, from 0 to 1." },
+                "roundsOverExpectedMedian": integer,
+                "comparableRounds": integer,
+            },
+        },
+        "AccountTargetDeadline": {
+            "type": "object",
+            "properties": {
+                "chainName": string,
+                "height": integer,
+                "targetDeadline": nullable_integer,
+                "usesAccountCapacity": { "type": "boolean" },
+            },
+        },
+        "Rejection": {
+            "type": "object",
+            "properties": {
+                "time": date_time,
+                "address": string,
+                "software": string,
+                "requestType": string,
+                "reason": { "type": "string", "enum": ["clientCertificate", "address", "secret", "rateLimit"] },
+                "message": string,
+            },
+        },
+        "PlotScan": {
+            "type": "object",
+            "properties": {
+                "time": date_time,
+                "directories": array_of(string.clone()),
+                "accounts": array_of(schema_ref("AccountPlots")),
+                "totalCapacityTebibytes": { "type": "number" },
+                "overlaps": array_of(schema_ref("PlotOverlap")),
+                "errors": array_of(string.clone()),
+                "ignoredFiles": integer,
+            },
+        },
+        "AccountPlots": {
+            "type": "object",
+            "properties": {
+                "accountId": string,
+                "files": array_of(schema_ref("PlotFile")),
+                "nonces": integer,
+                "capacityTebibytes": { "type": "number" },
+            },
+        },
+        "PlotFile": {
+            "type": "object",
+            "properties": {
+                "path": string,
+                "startNonce": integer,
+                "nonces": integer,
+                "fileSize": integer,
+            },
+        },
+        "PlotOverlap": {
+            "type": "object",
+            "properties": {
+                "accountId": string,
+                "first": string,
+                "second": string,
+                "overlappingNonces": integer,
+            },
+        },
+        "AccountDeadline": {
+            "type": "object",
+            "properties": {
+                "accountId": string,
+                "deadline": integer,
+            },
+        },
+        "Event": {
+            "type": "object",
+            "description": "Every event has an id, time and type. The rest of its properties depend on its type.",
+            "properties": {
+                "id": integer,
+                "time": date_time,
+                "type": {
+                    "type": "string",
+                    "enum": [
+                        "newBlock", "fork", "blockStarted", "blockInterrupted", "blockRequeued",
+                        "nonceSubmitted", "nonceAccepted", "nonceRejected",
+                        "upstreamOutage", "upstreamRecovered", "chainQuarantined", "chainReleased", "adminAction",
+                        "minerAlert", "minerRecovered",
+                    ],
+                },
+                "chainIndex": nullable_integer,
+                "chainName": { "type": "string", "nullable": true },
+            },
+            "additionalProperties": true,
+        },
+        "AdminResult": {
+            "type": "object",
+            "properties": {
+                "result": { "type": "string", "enum": ["success"] },
+                "action": string,
+                "chainIndex": integer,
+                "message": string,
+            },
+        },
+        "BlockDeadlines": {
+            "type": "object",
+            "properties": {
+                "chainIndex": integer,
+                "height": integer,
+                "deadlines": array_of(schema_ref("AccountDeadline")),
+            },
+        },
+    })
+}
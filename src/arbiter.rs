@@ -6,20 +6,329 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc;
 use std::sync::Arc;
 use std::thread;
+use std::time::{Duration, Instant};
 
+use crate::capacity;
 use crate::config::PocChain;
+use crate::context::Archon;
+use crate::control;
+use crate::events::{self, ArchonEvent};
+use crate::miners;
+use crate::prediction;
+use crate::quarantine::{self, QuarantineReason};
+use crate::rounds;
+use crate::schedule;
 use crate::upstream::MiningInfo;
+use crate::watchdog;
 use crate::web::{SubmitNonceResponse, SubmitNonceErrorResponse};
 
+// the most blocks a fork can abandon, a bigger drop in height only invalidates this many of the chain's latest blocks
+const MAX_FORK_DEPTH: u32 = 10;
+
 #[derive(Debug, Clone)]
-struct MiningInfoPollingResult {
+pub struct MiningInfoPollingResult {
     mining_info: MiningInfo,
     chain: PocChain,
 }
 
-fn create_chain_nonce_submission_client(chain_index: u8) {
+impl MiningInfoPollingResult {
+    fn get_chain_index(&self, archon: &Archon) -> u8 {
+        super::get_chain_index(archon, &*self.chain.url, &*self.chain.name)
+    }
+}
+
+/// Everything the arbiter reacts to. Events are handled one at a time, in order, on the arbiter thread.
+#[derive(Debug, Clone)]
+pub enum ArbiterEvent {
+    NewBlock(MiningInfoPollingResult),
+    // a new generation signature at the same or a lower height than the chain's previous block
+    Fork(MiningInfoPollingResult),
+    // (chain index, block height) of the round whose grace period ran out
+    GracePeriodExpired(u8, u32),
+    SubmissionReceived {
+        chain_index: u8,
+        height: u32,
+        deadline: u64,
+        confirmed: bool,
+    },
+    ChainOutage(u8),
+    ChainRecovered(u8),
+    // the chain's upstream hasn't found a new block in longer than its max block interval
+    ChainStale(u8),
+    // too many submissions in a row couldn't be forwarded to the chain's upstream
+    SubmissionsFailing(u8),
+    // a submission got through to the upstream of a chain quarantined for failing submissions
+    SubmissionsRecovered(u8),
+    // a chain quarantined for failing submissions has waited long enough to be given another chance
+    QuarantineBackoffExpired(u8),
+    ScheduleChanged(u8),
+    // re-check a chain's block which the look-ahead rule is holding back, once the odds it was held back on have changed
+    LookAheadRecheck(u8),
+    // changes made through the admin API
+    ChainPaused(u8),
+    ChainResumed(u8),
+    OverrideChanged(u8),
+    OverrideExpired(u8),
+    ForceStart {
+        chain_index: u8,
+        requested_by: String,
+    },
+    DropQueuedBlock {
+        chain_index: u8,
+        requested_by: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChainState {
+    Idle,
+    Queued(u32),
+    Mining(u32),
+    Outage,
+    Quarantined,
+    Paused,
+    ScheduledOff,
+}
+
+impl ChainState {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            ChainState::Idle => "idle",
+            ChainState::Queued(_) => "queued",
+            ChainState::Mining(_) => "mining",
+            ChainState::Outage => "outage",
+            ChainState::Quarantined => "quarantined",
+            ChainState::Paused => "paused",
+            ChainState::ScheduledOff => "scheduledOff",
+        }
+    }
+}
+
+/// The arbiter's state machine. Each event is handled at an explicit point in time, so the same
+/// sequence of events and times always produces the same decisions.
+pub struct Arbiter {
+    archon: Arc<Archon>,
+    chain_states: HashMap<u8, ChainState>,
+}
+
+impl Arbiter {
+    pub fn new(archon: Arc<Archon>) -> Arbiter {
+        Arbiter {
+            archon,
+            chain_states: HashMap::new(),
+        }
+    }
+
+    pub fn get_chain_state(&self, index: u8) -> ChainState {
+        *self.chain_states.get(&index).unwrap_or(&ChainState::Idle)
+    }
+
+    pub fn handle_event(&mut self, event: ArbiterEvent, now: DateTime<Local>) {
+        let archon_context = self.archon.clone();
+        let archon = &*archon_context;
+        // events which don't change any chain's state can still call for the queue to be re-arbitrated
+        let mut arbitrate = false;
+        match event {
+            ArbiterEvent::NewBlock(mining_info_polling_result) => {
+                info!("NEW BLOCK - {}: {:?}", &*mining_info_polling_result.chain.name, mining_info_polling_result.mining_info);
+                quarantine::release_chain(archon, mining_info_polling_result.get_chain_index(archon), QuarantineReason::Stale, now);
+                update_chain_info(archon, &mining_info_polling_result, now);
+                events::publish(archon, ArchonEvent::NewBlock {
+                    chain_index: mining_info_polling_result.get_chain_index(archon),
+                    chain_name: mining_info_polling_result.chain.name.clone(),
+                    height: mining_info_polling_result.mining_info.height,
+                    base_target: mining_info_polling_result.mining_info.base_target,
+                });
+                process_new_block(archon, &mining_info_polling_result, now);
+            }
+            ArbiterEvent::Fork(mining_info_polling_result) => {
+                info!("NEW BLOCK (FORK) - {}: {:?}", &*mining_info_polling_result.chain.name, mining_info_polling_result.mining_info);
+                quarantine::release_chain(archon, mining_info_polling_result.get_chain_index(archon), QuarantineReason::Stale, now);
+                process_fork(archon, &mining_info_polling_result);
+                update_chain_info(archon, &mining_info_polling_result, now);
+                process_new_block(archon, &mining_info_polling_result, now);
+            }
+            ArbiterEvent::GracePeriodExpired(index, height) => {
+                // timers set for a round which has since been replaced are ignored
+                if is_current_round(archon, index, height) {
+                    debug!("GRACE PERIOD EXPIRED - Chain #{} - Block #{}", index, height);
+                    arbitrate = true;
+                }
+            }
+            ArbiterEvent::SubmissionReceived { chain_index, height, deadline, confirmed } => {
+                debug!("SUBMISSION - Chain #{} - Block #{} - DL={} | Confirmed={}", chain_index, height, deadline, confirmed);
+                // a confirmed deadline can release the current round before its grace period is over
+                arbitrate = confirmed && is_current_round(archon, chain_index, height);
+            }
+            ArbiterEvent::ChainOutage(index) => {
+                let new_outage = !is_chain_in_outage(archon, index);
+                archon.state.chain_outages.lock().unwrap().entry(index).or_insert(now);
+                if new_outage {
+                    events::publish(archon, ArchonEvent::UpstreamOutage {
+                        chain_index: index,
+                        chain_name: events::get_chain_name(archon, index),
+                    });
+                }
+            }
+            ArbiterEvent::ChainRecovered(index) => {
+                let outage_since = archon.state.chain_outages.lock().unwrap().remove(&index);
+                match outage_since {
+                    Some(outage_since) => events::publish(archon, ArchonEvent::UpstreamRecovered {
+                        chain_index: index,
+                        chain_name: events::get_chain_name(archon, index),
+                        outage_seconds: (now - outage_since).num_seconds(),
+                    }),
+                    None => {}
+                };
+            }
+            ArbiterEvent::ChainStale(index) => {
+                quarantine::quarantine_chain(archon, index, QuarantineReason::Stale, now);
+            }
+            ArbiterEvent::SubmissionsFailing(index) => {
+                quarantine::quarantine_chain(archon, index, QuarantineReason::SubmissionsFailing, now);
+            }
+            ArbiterEvent::SubmissionsRecovered(index) => {
+                quarantine::release_chain(archon, index, QuarantineReason::SubmissionsFailing, now);
+            }
+            ArbiterEvent::QuarantineBackoffExpired(index) => {
+                // ignored if the chain was released in the meantime
+                match quarantine::get_backoff_expiry(archon, index) {
+                    Some(expires) if expires <= now => {
+                        quarantine::release_chain(archon, index, QuarantineReason::SubmissionsFailing, now);
+                    }
+                    _ => {}
+                };
+            }
+            ArbiterEvent::ScheduleChanged(index) => {
+                debug!("SCHEDULE CHANGED - Chain #{}", index);
+                // switching the chain on or off is a state change, a new priority only matters if it has a block in play
+                arbitrate = self.has_block_in_play(index);
+            }
+            ArbiterEvent::LookAheadRecheck(index) => {
+                arbitrate = self.has_block_in_play(index);
+            }
+            ArbiterEvent::ChainPaused(index) => {
+                debug!("PAUSED - Chain #{}", index);
+            }
+            ArbiterEvent::ChainResumed(index) => {
+                debug!("RESUMED - Chain #{}", index);
+            }
+            ArbiterEvent::OverrideChanged(index) => {
+                debug!("OVERRIDE CHANGED - Chain #{}", index);
+                arbitrate = self.has_block_in_play(index);
+            }
+            ArbiterEvent::OverrideExpired(index) => {
+                control::expire_chain_override(archon, index, now);
+                arbitrate = self.has_block_in_play(index);
+            }
+            ArbiterEvent::ForceStart { chain_index, requested_by } => {
+                force_start_chain(archon, chain_index, &*requested_by, now);
+            }
+            ArbiterEvent::DropQueuedBlock { chain_index, requested_by } => {
+                drop_queued_block(archon, chain_index, &*requested_by, now);
+            }
+        };
+        for (index, _, state) in self.update_chain_states() {
+            match state {
+                // the current round shouldn't carry on if its chain can't be mined any more
+                ChainState::Outage => skip_current_round(archon, index, "Upstream is unavailable", now),
+                ChainState::Quarantined => skip_current_round(
+                    archon,
+                    index,
+                    quarantine::get_quarantine(archon, index).map_or("Quarantined", |(reason, _)| reason.describe()),
+                    now
+                ),
+                ChainState::Paused => skip_current_round(archon, index, "Paused by an admin", now),
+                ChainState::ScheduledOff => skip_current_round(archon, index, "Switched off by its schedule", now),
+                _ => {}
+            };
+            // any chain changing state can change which block is next in line, or whether it's held back
+            arbitrate = true;
+        }
+        if arbitrate {
+            arbitrate_queue(archon, now);
+            self.update_chain_states();
+        }
+    }
+
+    /// Returns the next timer event and when it's due, if anything is waiting on time alone.
+    pub fn next_timer(&self, now: DateTime<Local>) -> Option<(ArbiterEvent, DateTime<Local>)> {
+        let archon = &*self.archon;
+        let current_chain_index = get_current_chain_index(archon);
+        let mut timers = Vec::new();
+        match archon.state.chain_queue_status.lock().unwrap().get(&current_chain_index) {
+            Some((height, start_time)) => {
+                let expires = *start_time + chrono::Duration::seconds(archon.conf.grace_period as i64);
+                if expires > now {
+                    timers.push((ArbiterEvent::GracePeriodExpired(current_chain_index, *height), expires));
+                }
+            }
+            None => {}
+        };
+        // once the grace period is over, a block still waiting is being held back by the look-ahead rule,
+        // which can only change its mind when one of the higher priority chains' odds of a new block change
+        if timers.is_empty() {
+            match get_next_queued_chain_index(archon, now) {
+                Some(index) => match get_look_ahead_recheck_time(archon, index, now) {
+                    Some(due) => timers.push((ArbiterEvent::LookAheadRecheck(index), due)),
+                    None => {}
+                },
+                None => {}
+            };
+        }
+        // overrides from the admin API run out on their own, the queue needs re-checking when they do
+        match control::get_next_override_expiry(archon) {
+            Some((index, expires)) => timers.push((ArbiterEvent::OverrideExpired(index), expires)),
+            None => {}
+        };
+        match quarantine::get_next_backoff_expiry(archon) {
+            Some((index, expires)) => timers.push((ArbiterEvent::QuarantineBackoffExpired(index), expires)),
+            None => {}
+        };
+        timers.into_iter().min_by_key(|(_, due)| *due)
+    }
+
+    // a chain's priority only matters to the queue if it has a block waiting or being mined
+    fn has_block_in_play(&self, index: u8) -> bool {
+        match self.get_chain_state(index) {
+            ChainState::Queued(_) | ChainState::Mining(_) => true,
+            _ => false,
+        }
+    }
+
+    // returns (chain index, previous state, new state) for every chain whose state changed
+    fn update_chain_states(&mut self) -> Vec<(u8, ChainState, ChainState)> {
+        let archon = &*self.archon;
+        let mut transitions = Vec::new();
+        for inner in &archon.conf.poc_chains {
+            for chain in inner {
+                if chain.enabled.unwrap_or(true) {
+                    let index = super::get_chain_index(archon, &*chain.url, &*chain.name);
+                    let state = get_chain_state(archon, index);
+                    let previous = self.get_chain_state(index);
+                    if previous != state {
+                        debug!("STATE - {}: {:?} => {:?}", &*chain.name, previous, state);
+                        self.chain_states.insert(index, state);
+                        transitions.push((index, previous, state));
+                    }
+                }
+            }
+        }
+        return transitions;
+    }
+}
+
+pub fn send_arbiter_event(archon: &Archon, event: ArbiterEvent) {
+    let sender = archon.state.arbiter_event_channel.0.lock().unwrap().clone();
+    match sender.send(event) {
+        Ok(_) => {}
+        Err(_) => {}
+    };
+}
+
+fn create_chain_nonce_submission_client(archon: &Archon, chain_index: u8) {
     // get current chain
-    let chain = super::get_chain_from_index(chain_index).unwrap();
+    let chain = super::get_chain_from_index(archon, chain_index).unwrap();
     use reqwest::header;
     let mut default_headers = header::HeaderMap::new();
     // if this chain is for hpool, add a default header to this client with the user's account key
@@ -39,7 +348,7 @@ fn create_chain_nonce_submission_client(chain_index: u8) {
         };
         default_headers.insert("X-Account", account_key_header_value);
     }
-    let mut chain_nonce_submission_clients = crate::CHAIN_NONCE_SUBMISSION_CLIENTS.lock().unwrap();
+    let mut chain_nonce_submission_clients = archon.upstream.nonce_submission_clients.lock().unwrap();
     chain_nonce_submission_clients.insert(
         chain_index, 
         reqwest::Client::builder()
@@ -51,21 +360,25 @@ fn create_chain_nonce_submission_client(chain_index: u8) {
     drop(chain_nonce_submission_clients);
 }
 
-pub fn thread_arbitrate() {
+pub fn thread_arbitrate(archon_context: Arc<Archon>) {
+    let archon = &*archon_context;
     let new_mining_info_found = Arc::new(AtomicBool::new(false));
-    let (mining_info_sender, mining_info_receiver) = mpsc::channel();
+    let mining_info_sender = archon.state.arbiter_event_channel.0.lock().unwrap().clone();
     // start polling for mining info for each chain
-    for inner in &crate::CONF.poc_chains {
+    for inner in &archon.conf.poc_chains {
         for chain in inner {
             if chain.enabled.unwrap_or(true) {
                 let new_sender = mining_info_sender.clone();
                 let new_mining_info_found = new_mining_info_found.clone();
-                let index = super::get_chain_index(&*chain.url, &*chain.name);
-                create_chain_nonce_submission_client(index);
+                let index = super::get_chain_index(archon, &*chain.url, &*chain.name);
+                create_chain_nonce_submission_client(archon, index);
+                let chain = chain.clone();
+                let poller_archon = archon_context.clone();
                 thread::spawn(move || {
                     thread_get_mining_info(
-                        reqwest::Client::new(),
-                        chain.clone(),
+                        &poller_archon,
+                        poller_archon.upstream.mining_info_client.clone(),
+                        chain,
                         new_sender,
                         new_mining_info_found,
                     );
@@ -74,31 +387,48 @@ pub fn thread_arbitrate() {
         }
     }
 
+    let event_receiver = archon.state.arbiter_event_channel.1.lock().unwrap();
+    let mut arbiter = Arbiter::new(archon_context.clone());
     loop {
-        match mining_info_receiver.recv() {
-            Ok(_mining_info_polling_result) => {
-                info!("NEW BLOCK - {}: {:?}", &*_mining_info_polling_result.chain.name, _mining_info_polling_result.mining_info);
-                update_chain_info(&_mining_info_polling_result);
-                process_new_block(&_mining_info_polling_result);
+        // wait for the next event, or until the next timer is due
+        let now = archon.now();
+        let event = match arbiter.next_timer(now) {
+            Some((timer_event, due)) => {
+                let wait = (due - now).to_std().unwrap_or(Duration::from_millis(0));
+                match event_receiver.recv_timeout(wait) {
+                    Ok(event) => event,
+                    Err(mpsc::RecvTimeoutError::Timeout) => timer_event,
+                    Err(mpsc::RecvTimeoutError::Disconnected) => return,
+                }
             }
-            Err(_) => {}
-        }
+            None => match event_receiver.recv() {
+                Ok(event) => event,
+                Err(_) => return,
+            },
+        };
+        arbiter.handle_event(event, archon.now());
     }
 }
 
 fn thread_get_mining_info(
+    archon: &Archon,
     client: reqwest::Client,
     chain: PocChain,
-    sender: mpsc::Sender<MiningInfoPollingResult>,
+    sender: mpsc::Sender<ArbiterEvent>,
     new_mining_info_found: Arc<AtomicBool>,
 ) {
+    let index = super::get_chain_index(archon, &*chain.url, &*chain.name);
     let mut url = String::from(&*chain.url);
     url.push_str("/burst?requestType=getMiningInfo");
     let mut last_block_height = 0 as u32;
+    let mut last_generation_signature = String::from("");
+    let mut last_new_block_time = archon.now();
+    let mut stale = false;
     let mut request_failure = false;
-    let mut last_request_success: DateTime<Local> = Local::now();
-    let mut last_outage_reminder_sent: DateTime<Local> = Local::now();
+    let mut last_request_success: DateTime<Local> = archon.now();
+    let mut last_outage_reminder_sent: DateTime<Local> = archon.now();
     loop {
+        let request_start = Instant::now();
         match client
             .get(url.as_str())
             .header(
@@ -114,33 +444,57 @@ fn thread_get_mining_info(
             Ok(mut resp) => {
                 match &resp.text() {
                     Ok(text) => {
+                        archon.state.metrics.observe_upstream_latency(index, "getMiningInfo", get_seconds_since(request_start));
                         match MiningInfo::from_json(text) {
                             (true, _mining_info) => {
                                 if request_failure {
                                     request_failure = false;
-                                    let outage_duration = Local::now() - last_request_success;
+                                    let outage_duration = archon.now() - last_request_success;
                                     let outage_duration_str = super::format_timespan(
+                                        archon,
                                         outage_duration.num_seconds() as u64,
                                     );
                                     println!("  {} {} {}",
-                                        super::get_time().white(),
+                                        super::get_time(archon).white(),
                                         format!("{}", &*chain.name).color(&*chain.color),
                                         format!("Outage over, total time unavailable: {}.", outage_duration_str).green()
                                     );
                                     info!("{} - Outage over, total time unavailable: {}.", &*chain.name, outage_duration_str);
+                                    match sender.send(ArbiterEvent::ChainRecovered(index)) {
+                                        Ok(_) => {}
+                                        Err(_) => {}
+                                    }
                                 }
-                                last_request_success = Local::now();
-                                if (chain.allow_lower_block_heights.unwrap_or_default()
+                                last_request_success = archon.now();
+                                let is_new_block = (chain.allow_lower_block_heights.unwrap_or_default()
                                     && _mining_info.height != last_block_height)
-                                    || _mining_info.height > last_block_height
-                                {
+                                    || _mining_info.height > last_block_height;
+                                // a different block at the same or a lower height means the upstream switched forks
+                                let is_fork = !is_new_block
+                                    && last_block_height > 0
+                                    && _mining_info.generation_signature != last_generation_signature;
+                                if is_new_block || is_fork {
                                     last_block_height = _mining_info.height;
+                                    last_generation_signature = _mining_info.generation_signature.clone();
+                                    last_new_block_time = archon.now();
+                                    stale = false;
                                     let _mining_info_polling_result = MiningInfoPollingResult {
                                         mining_info: _mining_info.clone(),
                                         chain: chain.clone(),
                                     };
                                     new_mining_info_found.store(true, Ordering::Relaxed);
-                                    match sender.send(_mining_info_polling_result) {
+                                    let event = match is_fork {
+                                        true => ArbiterEvent::Fork(_mining_info_polling_result),
+                                        false => ArbiterEvent::NewBlock(_mining_info_polling_result),
+                                    };
+                                    match sender.send(event) {
+                                        Ok(_) => {}
+                                        Err(_) => {}
+                                    }
+                                } else if !stale && quarantine::is_chain_stale(&chain, last_new_block_time, archon.now()) {
+                                    stale = true;
+                                    info!("{} - Stale: No new block since #{} for {}s (Max: {}s)", &*chain.name, last_block_height, (archon.now() - last_new_block_time).num_seconds(), quarantine::get_max_block_interval(&chain));
+                                    match sender.send(ArbiterEvent::ChainStale(index)) {
                                         Ok(_) => {}
                                         Err(_) => {}
                                     }
@@ -158,24 +512,28 @@ fn thread_get_mining_info(
             Err(why) => {
                 if !request_failure {
                     request_failure = true;
-                    last_outage_reminder_sent = Local::now();
+                    last_outage_reminder_sent = archon.now();
                     println!("  {} {} {}",
-                        super::get_time().white(),
+                        super::get_time(archon).white(),
                         format!("{}", &*chain.name).color(&*chain.color),
                         "Could not retrieve mining info!".red()
                     );
                     info!("{} ({}) - Error getting mining info! Outage started: {}", &*chain.name, &*chain.url, why);
+                    match sender.send(ArbiterEvent::ChainOutage(index)) {
+                        Ok(_) => {}
+                        Err(_) => {}
+                    }
                 } else {
-                    let outage_duration = Local::now() - last_request_success;
-                    let last_reminder = Local::now() - last_outage_reminder_sent;
+                    let outage_duration = archon.now() - last_request_success;
+                    let last_reminder = archon.now() - last_outage_reminder_sent;
                     if last_reminder.num_seconds()
-                        >= crate::CONF.outage_status_update_interval.unwrap_or(300u16) as i64
+                        >= archon.conf.outage_status_update_interval.unwrap_or(300u16) as i64
                     {
-                        last_outage_reminder_sent = Local::now();
+                        last_outage_reminder_sent = archon.now();
                         let outage_duration_str =
-                            super::format_timespan(outage_duration.num_seconds() as u64);
+                            super::format_timespan(archon, outage_duration.num_seconds() as u64);
                         println!("  {} {} {}",
-                            super::get_time().white(),
+                            super::get_time(archon).white(),
                             format!("{} - Last: {}", &*chain.name, last_block_height).color(&*chain.color),
                             format!("Outage continues, time unavailable so far: {}.", outage_duration_str).red()
                         );
@@ -192,81 +550,299 @@ fn thread_get_mining_info(
     }
 }
 
-fn update_chain_info(mining_info_polling_result: &MiningInfoPollingResult) {
+fn update_chain_info(archon: &Archon, mining_info_polling_result: &MiningInfoPollingResult, now: DateTime<Local>) {
     // insert the new mining info into the mining infos map with the current time
     let index = super::get_chain_index(
+        archon,
         &*mining_info_polling_result.chain.url,
         &*mining_info_polling_result.chain.name,
     );
-    let mut chain_info_map = crate::CHAIN_MINING_INFOS.lock().unwrap();
-    chain_info_map.insert(
+    let block_time = now;
+    let mut chain_info_map = archon.state.chain_mining_infos.lock().unwrap();
+    let previous = chain_info_map.insert(
         index,
-        (mining_info_polling_result.mining_info.clone(), Local::now()),
+        (mining_info_polling_result.mining_info.clone(), block_time),
+    );
+    drop(chain_info_map);
+    match previous {
+        // blocks replaced by a fork don't count towards the chain's block intervals
+        Some((previous_mining_info, previous_block_time))
+            if mining_info_polling_result.mining_info.height > previous_mining_info.height =>
+        {
+            prediction::record_block_arrival(archon, index, previous_block_time, block_time);
+        }
+        _ => {}
+    };
+}
+
+/// Discards everything recorded for the blocks abandoned by a fork, and rolls the chain's queue status back
+/// so the replacement block is mined as a new round.
+fn process_fork(archon: &Archon, mining_info_polling_result: &MiningInfoPollingResult) {
+    let index = super::get_chain_index(
+        archon,
+        &*mining_info_polling_result.chain.url,
+        &*mining_info_polling_result.chain.name,
+    );
+    let abandoned_mining_info = match get_current_chain_mining_info(archon, index) {
+        Some((mining_info, _)) => mining_info,
+        None => return,
+    };
+    let new_mining_info = &mining_info_polling_result.mining_info;
+    info!("FORK - {} - Block #{} => #{} | Old Signature: {} | New Signature: {}",
+        &*mining_info_polling_result.chain.name,
+        abandoned_mining_info.height,
+        new_mining_info.height,
+        &*abandoned_mining_info.generation_signature,
+        &*new_mining_info.generation_signature
+    );
+    events::publish(archon, ArchonEvent::Fork {
+        chain_index: index,
+        chain_name: mining_info_polling_result.chain.name.clone(),
+        old_height: abandoned_mining_info.height,
+        new_height: new_mining_info.height,
+    });
+    super::print_fork(
+        archon,
+        &*mining_info_polling_result.chain.name,
+        &*mining_info_polling_result.chain.color,
+        abandoned_mining_info.height,
+        new_mining_info.height,
+        &*abandoned_mining_info.generation_signature,
+        &*new_mining_info.generation_signature,
     );
+    // deadlines submitted for this chain's abandoned blocks are no longer valid, a drop deeper than a real fork
+    // (eg: the upstream being swapped for one which is still syncing) only invalidates the most recent blocks
+    let fork_depth = abandoned_mining_info.height.saturating_sub(new_mining_info.height);
+    if fork_depth > MAX_FORK_DEPTH {
+        warn!("FORK - {} - Dropped {} blocks, only invalidating the last {}", &*mining_info_polling_result.chain.name, fork_depth, MAX_FORK_DEPTH);
+    }
+    let oldest_abandoned_height = abandoned_mining_info.height - fork_depth.min(MAX_FORK_DEPTH);
+    let mut best_deadlines_map = archon.state.best_deadlines.lock().unwrap();
+    for height in oldest_abandoned_height..=abandoned_mining_info.height {
+        if best_deadlines_map.remove(&(index, height)).is_some() {
+            debug!("INVALIDATE BestDLs - Chain #{} Block #{}", index, height);
+        }
+    }
+    drop(best_deadlines_map);
+    let mut best_confirmed_deadlines_map = archon.state.best_confirmed_deadlines.lock().unwrap();
+    match best_confirmed_deadlines_map.get(&index) {
+        Some((height, _)) if *height >= new_mining_info.height => {
+            best_confirmed_deadlines_map.remove(&index);
+        }
+        _ => {}
+    };
+    drop(best_confirmed_deadlines_map);
+    // the cached mining info is keyed by height, so it would still hold the abandoned block's signature
+    archon.state.mining_info_cache.lock().unwrap().remove(&index);
+    let (queued_height, queued_time) = get_queued_chain_info(archon, index);
+    if queued_height > 0 && queued_height >= new_mining_info.height {
+        let replacement_height = new_mining_info.height.saturating_sub(1);
+        let mut chain_queue_status_map = archon.state.chain_queue_status.lock().unwrap();
+        chain_queue_status_map.insert(index, (replacement_height, queued_time));
+        debug!("SET START - Chain #{} Block #{} ==> #{}", index, queued_height, replacement_height);
+        let mut block_start_printed_map = archon.state.block_start_printed.lock().unwrap();
+        block_start_printed_map.insert(index, replacement_height);
+    }
 }
 
 // wrapper function to safely retrieve the current chain index from the mutex without holding a lock
-pub fn get_current_chain_index() -> u8 {
-    return *crate::CURRENT_CHAIN_INDEX.lock().unwrap();
+pub fn get_current_chain_index(archon: &Archon) -> u8 {
+    return *archon.state.current_chain_index.lock().unwrap();
+}
+
+pub fn is_fair_share_mode(archon: &Archon) -> bool {
+    archon.conf.fair_share_mode.unwrap_or_default()
 }
 
-fn process_new_block(mining_info_polling_result: &MiningInfoPollingResult) {
+// fair share mode takes precedence over priority mode
+pub fn is_priority_mode(archon: &Archon) -> bool {
+    archon.conf.priority_mode.unwrap_or(true) && !is_fair_share_mode(archon)
+}
+
+/// Returns the configured share of scan time for a chain as a fraction of 1.
+/// Chains without a miningShare split whatever percentage the other chains leave unclaimed.
+pub fn get_target_mining_share(archon: &Archon, chain: &PocChain) -> f64 {
+    let mut configured_total = 0f64;
+    let mut num_unconfigured = 0u8;
+    for inner in &archon.conf.poc_chains {
+        for other_chain in inner {
+            if other_chain.enabled.unwrap_or(true) {
+                match other_chain.mining_share {
+                    Some(share) if share > 0f64 => configured_total += share,
+                    _ => num_unconfigured += 1,
+                }
+            }
+        }
+    }
+    let unclaimed = if configured_total < 100f64 { 100f64 - configured_total } else { 0f64 };
+    let share = match chain.mining_share {
+        Some(share) if share > 0f64 => share,
+        _ => {
+            if num_unconfigured > 0 {
+                unclaimed / num_unconfigured as f64
+            } else {
+                0f64
+            }
+        }
+    };
+    let total = configured_total + if num_unconfigured > 0 { unclaimed } else { 0f64 };
+    if total > 0f64 {
+        return share / total;
+    }
+    return 0f64;
+}
+
+/// Returns (actual share, target share) of scan time for a chain, both as fractions of 1.
+pub fn get_mining_share(archon: &Archon, index: u8) -> (f64, f64) {
+    let target_share = match super::get_chain_from_index(archon, index) {
+        Some(chain) => get_target_mining_share(archon, &chain),
+        None => 0f64,
+    };
+    let chain_mining_times_map = archon.state.chain_mining_times.lock().unwrap();
+    let total_time: i64 = chain_mining_times_map.values().sum();
+    if total_time <= 0 {
+        return (0f64, target_share);
+    }
+    let chain_time = *chain_mining_times_map.get(&index).unwrap_or(&0);
+    return (chain_time as f64 / total_time as f64, target_share);
+}
+
+pub fn get_mining_shares(archon: &Archon) -> Vec<(String, f64, f64)> {
+    let mut mining_shares = Vec::new();
+    for inner in &archon.conf.poc_chains {
+        for chain in inner {
+            if chain.enabled.unwrap_or(true) {
+                let index = super::get_chain_index(archon, &*chain.url, &*chain.name);
+                let (actual_share, target_share) = get_mining_share(archon, index);
+                mining_shares.push((chain.name.clone(), actual_share, target_share));
+            }
+        }
+    }
+    return mining_shares;
+}
+
+// credit the current round's scan time to its chain before a new block is started
+// a round can't be scanned for longer than the grace period, any time beyond that is idle time
+fn record_current_round_mining_time(archon: &Archon, now: DateTime<Local>) {
+    let current_chain_index = get_current_chain_index(archon);
+    let start_time = match archon.state.chain_queue_status.lock().unwrap().get(&current_chain_index) {
+        Some((_, start_time)) => *start_time,
+        None => return,
+    };
+    let mut scan_time = (now - start_time).num_milliseconds();
+    let grace_period = archon.conf.grace_period as i64 * 1000;
+    if scan_time > grace_period {
+        scan_time = grace_period;
+    }
+    if scan_time > 0 {
+        let mut chain_mining_times_map = archon.state.chain_mining_times.lock().unwrap();
+        *chain_mining_times_map.entry(current_chain_index).or_insert(0) += scan_time;
+    }
+}
+
+fn process_new_block(archon: &Archon, mining_info_polling_result: &MiningInfoPollingResult, now: DateTime<Local>) {
     let index = super::get_chain_index(
+        archon,
         &*mining_info_polling_result.chain.url,
         &*mining_info_polling_result.chain.name,
     );
-    let current_chain_index = get_current_chain_index();
-    let current_chain = super::get_chain_from_index(current_chain_index).unwrap();
-    if crate::CONF.priority_mode.unwrap_or(true) {
-        if mining_info_polling_result.chain.priority <= current_chain.priority {
+    let new_block_chain = super::get_chain_from_index(archon, index).unwrap();
+    let current_chain_index = get_current_chain_index(archon);
+    let current_chain = super::get_chain_from_index(archon, current_chain_index).unwrap();
+    if schedule::is_chain_scheduled_off(archon, index) {
+        info!("QUEUE BLOCK - {} #{} (Switched off by schedule)", &*new_block_chain.name, mining_info_polling_result.mining_info.height);
+        return;
+    }
+    if control::is_chain_paused(archon, index) {
+        info!("QUEUE BLOCK - {} #{} (Paused)", &*new_block_chain.name, mining_info_polling_result.mining_info.height);
+        return;
+    }
+    if is_fair_share_mode(archon) {
+        if is_current_round_over(archon, now) {
+            match any_blocks_queued(archon, now) {
+                (true, _, next_index) => {
+                    start_mining_chain(archon, next_index, now);
+                    return;
+                }
+                (false, _, _) => {}
+            };
+        } else if index == current_chain_index {
+            // the current round is outdated, start the new block unless another chain is waiting for its turn
+            match any_blocks_queued(archon, now) {
+                (true, _, next_index) if next_index != index => {}
+                (_, _, _) => {
+                    start_mining_chain(archon, index, now);
+                    return;
+                }
+            };
+        } // else queue new block
+    } else if is_priority_mode(archon) {
+        if new_block_chain.priority < current_chain.priority
+            || index == current_chain_index
+        {
             // higher priority is LOWER in actual value
             if index != current_chain_index {
-                if !has_grace_period_elapsed() {
-                    if crate::CONF.interrupt_lower_priority_blocks.unwrap_or(true) {
-                        requeue_current_block(
-                            current_chain.requeue_interrupted_blocks.unwrap_or(true),
-                            index,
-                            Some(mining_info_polling_result.clone())
-                        );
-                        start_mining_chain(index);
-                        return;
+                if !is_current_round_over(archon, now) {
+                    if archon.conf.interrupt_lower_priority_blocks.unwrap_or(true) {
+                        match get_current_round_protection(archon, index) {
+                            Some(protection) => print_current_round_decision(archon, &*protection),
+                            None => {
+                                requeue_current_block(
+                                    archon,
+                                    current_chain.requeue_interrupted_blocks.unwrap_or(true),
+                                    index,
+                                    Some(mining_info_polling_result.clone()),
+                                    format!("{} has a higher priority ({} vs {})",
+                                        &*new_block_chain.name, new_block_chain.priority, current_chain.priority).as_str(),
+                                    now
+                                );
+                                start_mining_chain(archon, index, now);
+                                return;
+                            }
+                        };
                     } // else queue new block
                 } else {
                     // if grace period has elapsed
-                    start_mining_chain(index);
+                    start_queued_block(archon, index, now);
                     return;
                 }
             } else {
-                match any_blocks_queued() {
+                match any_blocks_queued(archon, now) {
                     (true, 0...1, _) => {
                         // queue new block
                     }
                     (_, _, _) => {
-                        start_mining_chain(index);
+                        start_mining_chain(archon, index, now);
                     }
                 }
                 return;
             }
-        } else if has_grace_period_elapsed() {
-            start_mining_chain(index);
-            return;
+        } else if is_current_round_over(archon, now) {
+            // same or lower priority tier, start whichever queued block is next in line
+            match any_blocks_queued(archon, now) {
+                (true, _, next_index) => {
+                    start_queued_block(archon, next_index, now);
+                    return;
+                }
+                (false, _, _) => {}
+            };
         } // else queue new block
     } else {
         // running in FIFO mode
         if index != current_chain_index {
-            if has_grace_period_elapsed() {
-                match any_blocks_queued() {
+            if is_current_round_over(archon, now) {
+                match any_blocks_queued(archon, now) {
                     (true, _, _) => {
-                        start_mining_chain(index);
+                        start_mining_chain(archon, index, now);
                         return;
                     }
                     (false, _, _) => {}
                 }; // else queue new block
             } // else queue new block
         } else {
-            match any_blocks_queued() {
+            match any_blocks_queued(archon, now) {
                 (false, _, _) => {
-                    start_mining_chain(index);
+                    start_mining_chain(archon, index, now);
                     return;
                 }
                 (true, _, _) => {}
@@ -282,10 +858,10 @@ fn process_new_block(mining_info_polling_result: &MiningInfoPollingResult) {
     );*/
 }
 
-fn requeue_current_block(do_requeue: bool, interrupted_by_index: u8, mining_info_polling_result: Option<MiningInfoPollingResult>) {
-    let current_chain_index = get_current_chain_index();
-    let current_chain = super::get_chain_from_index(current_chain_index).unwrap();
-    let (requeued_height, requeued_time) = get_queued_chain_info(current_chain_index);
+fn requeue_current_block(archon: &Archon, do_requeue: bool, interrupted_by_index: u8, mining_info_polling_result: Option<MiningInfoPollingResult>, reason: &str, now: DateTime<Local>) {
+    let current_chain_index = get_current_chain_index(archon);
+    let current_chain = super::get_chain_from_index(archon, current_chain_index).unwrap();
+    let (requeued_height, requeued_time) = get_queued_chain_info(archon, current_chain_index);
     let interrupted_by_name;
     let interrupted_by_height;
     match mining_info_polling_result {
@@ -294,7 +870,7 @@ fn requeue_current_block(do_requeue: bool, interrupted_by_index: u8, mining_info
             interrupted_by_height = mining_info_polling_result.mining_info.clone().height;
         },
         None => {
-            match (super::get_chain_from_index(interrupted_by_index), get_current_chain_mining_info(interrupted_by_index)) {
+            match (super::get_chain_from_index(archon, interrupted_by_index), get_current_chain_mining_info(archon, interrupted_by_index)) {
                 (Some(interrupted_by_chain), Some(interrupted_by_mining_info)) => {
                     interrupted_by_name = interrupted_by_chain.clone().name;
                     interrupted_by_height = interrupted_by_mining_info.0.clone().height;
@@ -307,18 +883,48 @@ fn requeue_current_block(do_requeue: bool, interrupted_by_index: u8, mining_info
         }
     }
     if do_requeue {
-        info!("INTERRUPT & REQUEUE BLOCK - {} #{} => {} #{}", &*current_chain.name, requeued_height, &*interrupted_by_name, interrupted_by_height);
+        info!("INTERRUPT & REQUEUE BLOCK - {} #{} => {} #{} | {}", &*current_chain.name, requeued_height, &*interrupted_by_name, interrupted_by_height, reason);
         // set the queue status for this chain back by 1, thereby "requeuing" it
-        let mut chain_queue_status_map = crate::CHAIN_QUEUE_STATUS.lock().unwrap();
+        let mut chain_queue_status_map = archon.state.chain_queue_status.lock().unwrap();
         chain_queue_status_map.insert(current_chain_index, (requeued_height - 1, requeued_time));
         debug!("SET START - Chain #{} Block #{} ==> #{}", current_chain_index, requeued_height, requeued_height - 1);
-        let mut block_start_printed_map = crate::BLOCK_START_PRINTED.lock().unwrap();
+        let mut block_start_printed_map = archon.state.block_start_printed.lock().unwrap();
         block_start_printed_map.insert(current_chain_index, requeued_height - 1);
     } else {
-        info!("INTERRUPT BLOCK - {} #{} => {} #{}", &*current_chain.name, requeued_height, &*interrupted_by_name, interrupted_by_height);
+        info!("INTERRUPT BLOCK - {} #{} => {} #{} | {}", &*current_chain.name, requeued_height, &*interrupted_by_name, interrupted_by_height, reason);
     }
+    super::print_arbiter_decision(
+        archon,
+        &*current_chain.name,
+        &*current_chain.color,
+        requeued_height,
+        format!("{} #{} for {} #{}: {}.",
+            if do_requeue { "Interrupted & requeued" } else { "Interrupted" },
+            requeued_height,
+            &*interrupted_by_name,
+            interrupted_by_height,
+            reason).as_str(),
+    );
+    rounds::record_round_interrupted(archon, do_requeue, now);
+    let interrupted_by = format!("{} #{}", &*interrupted_by_name, interrupted_by_height);
+    events::publish(archon, if do_requeue {
+        ArchonEvent::BlockRequeued {
+            chain_index: current_chain_index,
+            chain_name: current_chain.name.clone(),
+            height: requeued_height,
+            interrupted_by,
+        }
+    } else {
+        ArchonEvent::BlockInterrupted {
+            chain_index: current_chain_index,
+            chain_name: current_chain.name.clone(),
+            height: requeued_height,
+            interrupted_by,
+        }
+    });
     // print
     super::print_block_requeued_or_interrupted(
+        archon,
         &*current_chain.name,
         &*current_chain.color,
         requeued_height,
@@ -326,14 +932,128 @@ fn requeue_current_block(do_requeue: bool, interrupted_by_index: u8, mining_info
     );
 }
 
-fn has_grace_period_elapsed() -> bool {
-    let grace_period = time::Duration::seconds(crate::CONF.grace_period as i64);
-    let current_chain_index = get_current_chain_index();
-    let chain_queue_status_map = crate::CHAIN_QUEUE_STATUS.lock().unwrap();
+// returns the lowest deadline submitted by any account for a chain's block, if any
+pub fn get_best_deadline_for_round(archon: &Archon, chain_index: u8, block_height: u32) -> Option<u64> {
+    match get_best_deadlines_for_block(archon, chain_index, block_height) {
+        Some(best_deadlines) => best_deadlines.iter().map(|(_, deadline)| *deadline).min(),
+        None => None,
+    }
+}
+
+// a deadline threshold is never more lenient than the chain's own target deadline
+fn get_deadline_threshold_for_chain(archon: &Archon, index: u8, threshold: u64) -> u64 {
+    let chain = super::get_chain_from_index(archon, index).unwrap();
+    let base_target = match get_current_chain_mining_info(archon, index) {
+        Some((mining_info, _)) => mining_info.base_target,
+        _ => 0,
+    };
+    let (target_deadline, _) = get_target_deadline(archon, 0, base_target, index, chain.target_deadline, None);
+    if target_deadline < threshold {
+        return target_deadline;
+    }
+    return threshold;
+}
+
+/// Returns why the current round shouldn't be interrupted by the given chain's block, if it's protected:
+/// it was force-started, or already has a deadline good enough to keep.
+fn get_current_round_protection(archon: &Archon, interrupted_by_index: u8) -> Option<String> {
+    let current_chain_index = get_current_chain_index(archon);
+    let current_chain = super::get_chain_from_index(archon, current_chain_index).unwrap();
+    let (height, _) = get_queued_chain_info(archon, current_chain_index);
+    let interrupted_by_name = match super::get_chain_from_index(archon, interrupted_by_index) {
+        Some(chain) => chain.name,
+        None => String::from("Unknown"),
+    };
+    if control::is_round_force_started(archon, current_chain_index, height) {
+        return Some(format!("Protected #{} from interruption by {}: It was started by an admin.", height, interrupted_by_name));
+    }
+    let protect_deadline = match current_chain.protect_deadline {
+        Some(protect_deadline) => get_deadline_threshold_for_chain(archon, current_chain_index, protect_deadline),
+        None => return None,
+    };
+    match get_best_deadline_for_round(archon, current_chain_index, height) {
+        Some(best_deadline) if best_deadline <= protect_deadline => Some(format!(
+            "Protected #{} from interruption by {}: Best deadline {} is within the protect deadline ({}).",
+            height, interrupted_by_name, best_deadline, protect_deadline
+        )),
+        _ => None,
+    }
+}
+
+/// Returns why the current round can stop waiting for its grace period, if a deadline confirmed for it is good enough.
+fn get_current_round_early_release(archon: &Archon) -> Option<String> {
+    let current_chain_index = get_current_chain_index(archon);
+    let current_chain = super::get_chain_from_index(archon, current_chain_index).unwrap();
+    let early_release_deadline = match current_chain.early_release_deadline {
+        Some(early_release_deadline) => get_deadline_threshold_for_chain(archon, current_chain_index, early_release_deadline),
+        None => return None,
+    };
+    let (height, _) = get_queued_chain_info(archon, current_chain_index);
+    let best_confirmed_deadline = match archon.state.best_confirmed_deadlines.lock().unwrap().get(&current_chain_index) {
+        Some((confirmed_height, deadline)) if *confirmed_height == height => *deadline,
+        _ => return None,
+    };
+    if best_confirmed_deadline <= early_release_deadline {
+        return Some(format!("Released #{} early: Confirmed deadline {} is within the early release deadline ({}).",
+            height, best_confirmed_deadline, early_release_deadline));
+    }
+    return None;
+}
+
+// prints a decision about the round being mined
+fn print_current_round_decision(archon: &Archon, decision: &str) {
+    let current_chain_index = get_current_chain_index(archon);
+    let current_chain = super::get_chain_from_index(archon, current_chain_index).unwrap();
+    let (height, _) = get_queued_chain_info(archon, current_chain_index);
+    super::print_arbiter_decision(archon, &*current_chain.name, &*current_chain.color, height, decision);
+}
+
+/// Like has_grace_period_elapsed, for callers about to move on from the current round: if it was released early,
+/// prints why.
+fn is_current_round_over(archon: &Archon, now: DateTime<Local>) -> bool {
+    if !has_grace_period_elapsed(archon, now) {
+        return false;
+    }
+    if !has_full_grace_period_elapsed(archon, now) {
+        match get_current_round_early_release(archon) {
+            Some(early_release) => print_current_round_decision(archon, &*early_release),
+            None => {}
+        };
+    }
+    return true;
+}
+
+fn update_best_confirmed_deadline(archon: &Archon, chain_index: u8, block_height: u32, deadline: u64) {
+    let mut best_confirmed_deadlines_map = archon.state.best_confirmed_deadlines.lock().unwrap();
+    match best_confirmed_deadlines_map.get(&chain_index) {
+        Some((height, best_deadline)) if *height == block_height && *best_deadline <= deadline => {}
+        _ => {
+            best_confirmed_deadlines_map.insert(chain_index, (block_height, deadline));
+        }
+    };
+}
+
+fn has_grace_period_elapsed(archon: &Archon, now: DateTime<Local>) -> bool {
+    if has_full_grace_period_elapsed(archon, now) {
+        return true;
+    }
+    let current_chain_index = get_current_chain_index(archon);
+    match archon.state.chain_queue_status.lock().unwrap().get(&current_chain_index) {
+        Some(_) => {}
+        None => return false,
+    };
+    return get_current_round_early_release(archon).is_some();
+}
+
+// checks the grace period has run its course, without regard for early release
+fn has_full_grace_period_elapsed(archon: &Archon, now: DateTime<Local>) -> bool {
+    let grace_period = time::Duration::seconds(archon.conf.grace_period as i64);
+    let current_chain_index = get_current_chain_index(archon);
+    let chain_queue_status_map = archon.state.chain_queue_status.lock().unwrap();
     if chain_queue_status_map.len() > 0 {
         match chain_queue_status_map.get(&current_chain_index) {
             Some((_, start_time)) => {
-                return (Local::now() - *start_time) >= grace_period;
+                return (now - *start_time) >= grace_period;
             }
             None => {
                 return false;
@@ -344,13 +1064,13 @@ fn has_grace_period_elapsed() -> bool {
     }
 }
 
-pub fn get_time_since_block_start(height: u32) -> Option<u64> {
-    let current_chain_index = get_chain_index_from_height(height);
-    let chain_queue_status_map = crate::CHAIN_QUEUE_STATUS.lock().unwrap();
+pub fn get_time_since_block_start(archon: &Archon, height: u32) -> Option<u64> {
+    let current_chain_index = get_chain_index_from_height(archon, height);
+    let chain_queue_status_map = archon.state.chain_queue_status.lock().unwrap();
     if chain_queue_status_map.len() > 0 {
         match chain_queue_status_map.get(&current_chain_index) {
             Some((_, start_time)) => {
-                return Some((Local::now() - *start_time).num_seconds() as u64);
+                return Some((archon.now() - *start_time).num_seconds() as u64);
             }
             None => {}
         };
@@ -358,32 +1078,32 @@ pub fn get_time_since_block_start(height: u32) -> Option<u64> {
     return None;
 }
 
-fn get_queued_chain_info(index: u8) -> (u32, DateTime<Local>) {
-    let chain_queue_status_map = crate::CHAIN_QUEUE_STATUS.lock().unwrap();
+fn get_queued_chain_info(archon: &Archon, index: u8) -> (u32, DateTime<Local>) {
+    let chain_queue_status_map = archon.state.chain_queue_status.lock().unwrap();
     match chain_queue_status_map.get(&index) {
         Some((block_height, block_time)) => {
             return (*block_height, *block_time);
         }
         None => {
-            return (0u32, Local::now());
+            return (0u32, archon.now());
         }
     };
 }
 
-pub fn get_latest_chain_info(index: u8) -> (u32, DateTime<Local>) {
-    let chain_mining_infos_map = crate::CHAIN_MINING_INFOS.lock().unwrap();
+pub fn get_latest_chain_info(archon: &Archon, index: u8) -> (u32, DateTime<Local>) {
+    let chain_mining_infos_map = archon.state.chain_mining_infos.lock().unwrap();
     match chain_mining_infos_map.get(&index) {
         Some((mining_info, block_time)) => {
             return (mining_info.height, *block_time);
         }
         None => {
-            return (0u32, Local::now());
+            return (0u32, archon.now());
         }
     };
 }
 
-fn get_current_chain_mining_info(index: u8) -> Option<(MiningInfo, DateTime<Local>)> {
-    let chain_mining_infos_map = crate::CHAIN_MINING_INFOS.lock().unwrap();
+pub fn get_current_chain_mining_info(archon: &Archon, index: u8) -> Option<(MiningInfo, DateTime<Local>)> {
+    let chain_mining_infos_map = archon.state.chain_mining_infos.lock().unwrap();
     match chain_mining_infos_map.get(&index) {
         Some((mining_info, block_time)) => {
             return Some((mining_info.clone(), *block_time));
@@ -394,40 +1114,54 @@ fn get_current_chain_mining_info(index: u8) -> Option<(MiningInfo, DateTime<Loca
     }
 }
 
-pub fn get_chain_index_from_height(height: u32) -> u8 {
-    for inner in &crate::CONF.poc_chains {
+pub fn get_chain_index_from_height(archon: &Archon, height: u32) -> u8 {
+    for inner in &archon.conf.poc_chains {
         for chain in inner {
             if chain.enabled.unwrap_or(true) {
-                let index = super::get_chain_index(&*chain.url, &*chain.name);
-                let (current_height, _) = get_latest_chain_info(index);
-                if current_height == height || current_height == height - 1 {
+                let index = super::get_chain_index(archon, &*chain.url, &*chain.name);
+                let (current_height, _) = get_latest_chain_info(archon, index);
+                if current_height == height || current_height + 1 == height {
                     return index;
                 }
             }
         }
     }
-    return get_current_chain_index();
+    return get_current_chain_index(archon);
+}
+
+/// Returns the chain whose queued block is next in line, if any blocks are waiting.
+pub fn get_next_queued_chain_index(archon: &Archon, now: DateTime<Local>) -> Option<u8> {
+    match any_blocks_queued(archon, now) {
+        (true, _, index) => Some(index),
+        (false, _, _) => None,
+    }
 }
 
 // indicates state of queue
 // returns highest priority block if running in priority mode, or oldest block if in FIFO mode
 // (success, relative priority to current (1 = higher, 0 = same, -1 = lower), index)
-fn any_blocks_queued() -> (bool, i8, u8) {
+fn any_blocks_queued(archon: &Archon, now: DateTime<Local>) -> (bool, i8, u8) {
     let mut chain_indexes_with_queued_blocks: Vec<(u8, u32, u8, DateTime<Local>)> = Vec::new();
-    let current_chain_index = get_current_chain_index();
+    let current_chain_index = get_current_chain_index(archon);
     let mut current_chain_height = 0u32;
     // go through chains, check if each one has a higher blockheight queued, if so, store the index, and priority
-    for inner in &crate::CONF.poc_chains {
+    for inner in &archon.conf.poc_chains {
         for chain in inner {
             if chain.enabled.unwrap_or(true) {
-                let index = super::get_chain_index(&*chain.url, &*chain.name);
-                let (current_height, current_time) = get_latest_chain_info(index);
-                let (queued_height, _) = get_queued_chain_info(index);
-                if queued_height < current_height {
+                let index = super::get_chain_index(archon, &*chain.url, &*chain.name);
+                let (current_height, current_time) = get_latest_chain_info(archon, index);
+                let (queued_height, _) = get_queued_chain_info(archon, index);
+                // blocks from chains switched off by their schedule, quarantined, paused or unreachable stay queued until the chain is back
+                if queued_height < current_height
+                    && !schedule::is_chain_scheduled_off(archon, index)
+                    && !quarantine::is_chain_quarantined(archon, index)
+                    && !control::is_chain_paused(archon, index)
+                    && !is_chain_in_outage(archon, index)
+                {
                     chain_indexes_with_queued_blocks.push((
                         index,
                         queued_height,
-                        chain.priority,
+                        super::get_chain_from_index(archon, index).unwrap().priority,
                         current_time,
                     ));
                 }
@@ -438,16 +1172,33 @@ fn any_blocks_queued() -> (bool, i8, u8) {
         }
     }
     if chain_indexes_with_queued_blocks.len() > 0 {
-        let current_chain = super::get_chain_from_index(current_chain_index).unwrap();
-        if crate::CONF.priority_mode.unwrap_or(true) {
-            let mut highest_priority_chain_index = 0u8;
+        let current_chain = super::get_chain_from_index(archon, current_chain_index).unwrap();
+        if is_fair_share_mode(archon) {
+            // pick the chain which is furthest below its target share of scan time
+            let mut neediest_chain_index = 0u8;
+            let mut largest_deficit = std::f64::MIN;
+            for (index, _, _, _) in chain_indexes_with_queued_blocks.iter() {
+                let (actual_share, target_share) = get_mining_share(archon, *index);
+                if target_share - actual_share > largest_deficit {
+                    largest_deficit = target_share - actual_share;
+                    neediest_chain_index = *index;
+                }
+            }
+            return (true, 0, neediest_chain_index);
+        } else if is_priority_mode(archon) {
             let mut highest_priority = u8::max_value();
-            for (index, _, priority, _) in chain_indexes_with_queued_blocks.iter() {
+            for (_, _, priority, _) in chain_indexes_with_queued_blocks.iter() {
                 if *priority < highest_priority {
                     highest_priority = *priority;
-                    highest_priority_chain_index = *index;
                 }
             }
+            // multiple chains can share a priority tier, break the tie according to the configured policy
+            let tier: Vec<(u8, DateTime<Local>)> = chain_indexes_with_queued_blocks
+                .iter()
+                .filter(|(_, _, priority, _)| *priority == highest_priority)
+                .map(|(index, _, _, time)| (*index, *time))
+                .collect();
+            let highest_priority_chain_index = break_priority_tie(archon, &tier, now);
             if highest_priority < current_chain.priority {
                 return (true, 1, highest_priority_chain_index);
             } else if highest_priority == current_chain.priority {
@@ -458,7 +1209,7 @@ fn any_blocks_queued() -> (bool, i8, u8) {
         } else {
             // FIFO mode
             let mut oldest_queued_chain_index = 0u8;
-            let mut oldest_queued_chain_time = Local::now();
+            let mut oldest_queued_chain_time = now;
             for (index, height, _, time) in chain_indexes_with_queued_blocks.iter() {
                 if *time < oldest_queued_chain_time
                     && (*index != current_chain_index
@@ -475,69 +1226,372 @@ fn any_blocks_queued() -> (bool, i8, u8) {
     }
 }
 
-pub fn thread_arbitrate_queue() {
-    loop {
-        match any_blocks_queued() {
-            (true, priority, index) => {
-                if crate::CONF.priority_mode.unwrap_or(true) {
-                    match priority {
-                        1 => {
-                            // 1 = higher priority than current block
-                            if has_grace_period_elapsed() {
-                                start_mining_chain(index);
-                            } else if crate::CONF.interrupt_lower_priority_blocks.unwrap_or(true) {
-                                let current_chain_index = get_current_chain_index();
-                                let current_chain =
-                                    super::get_chain_from_index(current_chain_index).unwrap();
-                                requeue_current_block(
-                                    current_chain.requeue_interrupted_blocks.unwrap_or(true),
-                                    index,
-                                    None
-                                );
-                                start_mining_chain(index);
-                            } // else do nothing
-                        }
-                        0 => {
-                            // 0 = same priority as current block
-                            start_mining_chain(index);
-                        }
-                        _ => {
-                            // -1 = lower priority than current block
-                            if has_grace_period_elapsed() {
-                                start_mining_chain(index);
-                            } // else do nothing
+/// How the next chain is picked when several chains in the same priority tier have queued blocks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TieBreakPolicy {
+    // the chain which was started least recently
+    RoundRobin,
+    // the block which has been waiting the longest
+    Fifo,
+    // the block with the best odds of winning, weighted by the chain's block reward
+    ExpectedValue,
+}
+
+impl TieBreakPolicy {
+    pub fn parse(policy: &str) -> Option<TieBreakPolicy> {
+        match policy.to_lowercase().as_str() {
+            "roundrobin" => Some(TieBreakPolicy::RoundRobin),
+            "fifo" => Some(TieBreakPolicy::Fifo),
+            "expectedvalue" => Some(TieBreakPolicy::ExpectedValue),
+            _ => None,
+        }
+    }
+
+    pub fn describe(&self) -> &'static str {
+        match self {
+            TieBreakPolicy::RoundRobin => "Round Robin",
+            TieBreakPolicy::Fifo => "First In, First Out",
+            TieBreakPolicy::ExpectedValue => "Expected Value",
+        }
+    }
+}
+
+/// The configured tieBreakPolicy. Defaults to round robin, which is also used for an invalid value (warned about at startup).
+pub fn get_tie_break_policy(archon: &Archon) -> TieBreakPolicy {
+    archon.conf.tie_break_policy
+        .as_ref()
+        .and_then(|policy| TieBreakPolicy::parse(&*policy))
+        .unwrap_or(TieBreakPolicy::RoundRobin)
+}
+
+/// Picks the next chain to mine from chains with queued blocks in the same priority tier.
+/// Each entry is (chain index, time the queued block was found).
+fn break_priority_tie(archon: &Archon, tier: &[(u8, DateTime<Local>)], now: DateTime<Local>) -> u8 {
+    if tier.len() == 1 {
+        return tier[0].0;
+    }
+    let tie_break_policy = get_tie_break_policy(archon);
+    let mut picked_index = tier[0].0;
+    match tie_break_policy {
+        TieBreakPolicy::Fifo => {
+            // the block which has been waiting the longest
+            let mut oldest_time = tier[0].1;
+            for (index, time) in tier.iter() {
+                if *time < oldest_time {
+                    oldest_time = *time;
+                    picked_index = *index;
+                }
+            }
+        }
+        TieBreakPolicy::ExpectedValue => {
+            // the block with the best odds of winning, weighted by the chain's block reward
+            let mut best_expected_value = std::f64::MIN;
+            for (index, _) in tier.iter() {
+                let expected_value = get_expected_block_value(archon, *index);
+                if expected_value > best_expected_value {
+                    best_expected_value = expected_value;
+                    picked_index = *index;
+                }
+            }
+        }
+        TieBreakPolicy::RoundRobin => {
+            // the chain which was started least recently, chains which were never started go first
+            let chain_queue_status_map = archon.state.chain_queue_status.lock().unwrap();
+            let mut least_recent_start = now;
+            for (index, _) in tier.iter() {
+                match chain_queue_status_map.get(index) {
+                    Some((_, start_time)) => {
+                        if *start_time < least_recent_start {
+                            least_recent_start = *start_time;
+                            picked_index = *index;
                         }
-                    };
-                } else {
-                    // FIFO mode
-                    if has_grace_period_elapsed() {
-                        start_mining_chain(index);
-                    } // else do nothing
+                    }
+                    None => {
+                        picked_index = *index;
+                        break;
+                    }
+                };
+            }
+        }
+    };
+    debug!("TIE BREAK - Policy={:?} | Tier={:?} => Chain #{}", tie_break_policy, tier.iter().map(|(index, _)| *index).collect::<Vec<u8>>(), picked_index);
+    return picked_index;
+}
+
+// expected value is proportional to the block reward divided by the network difficulty, since plot size is the same for every chain
+fn get_expected_block_value(archon: &Archon, index: u8) -> f64 {
+    let chain = match super::get_chain_from_index(archon, index) {
+        Some(chain) => chain,
+        None => return 0f64,
+    };
+    let base_target = match get_current_chain_mining_info(archon, index) {
+        Some((mining_info, _)) => mining_info.base_target,
+        _ => 0,
+    };
+    if base_target == 0 {
+        return 0f64;
+    }
+    let net_diff = super::get_network_difficulty_for_block(base_target, prediction::get_block_time(&chain) as u16);
+    if net_diff == 0 {
+        return std::f64::MAX;
+    }
+    return chain.block_reward.unwrap_or(1f64) / net_diff as f64;
+}
+
+/// Look-ahead: checks if a higher priority chain is likely to find a new block before this chain's block
+/// could finish being scanned, in which case starting it now would just get it interrupted.
+fn is_block_held_back(archon: &Archon, index: u8, now: DateTime<Local>) -> bool {
+    let look_ahead_probability = match archon.conf.look_ahead_probability {
+        Some(probability) if probability > 0f64 => probability,
+        _ => return false,
+    };
+    let chain = match super::get_chain_from_index(archon, index) {
+        Some(chain) => chain,
+        None => return false,
+    };
+    let round_time = archon.conf.grace_period as i64;
+    for (other_index, other_chain) in get_look_ahead_chains(archon, &chain) {
+        let arrival_probability = prediction::get_block_arrival_probability(archon, other_index, round_time, now);
+        if arrival_probability >= look_ahead_probability {
+            let (height, _) = get_latest_chain_info(archon, index);
+            let skip = archon.conf.look_ahead_action.clone().unwrap_or(String::from("hold")).to_lowercase() == "skip";
+            debug!("LOOK-AHEAD - {} #{} - {} has a {:.0}% chance of a new block within {} seconds",
+                &*chain.name, height, &*other_chain.name, arrival_probability * 100f64, round_time);
+            // the odds change while a block is held back, so they're left out of the decision to only print it once
+            super::print_arbiter_decision(
+                archon,
+                &*chain.name,
+                &*chain.color,
+                height,
+                format!("{} #{}: {} is likely to find a new block within {} seconds (Look-ahead: {:.0}%).",
+                    if skip { "Skipped" } else { "Holding back" },
+                    height,
+                    &*other_chain.name,
+                    round_time,
+                    look_ahead_probability * 100f64).as_str(),
+            );
+            if skip {
+                // mark the block as done without mining it
+                let mut chain_queue_status_map = archon.state.chain_queue_status.lock().unwrap();
+                let start_time = match chain_queue_status_map.get(&index) {
+                    Some((_, start_time)) => *start_time,
+                    None => now,
+                };
+                chain_queue_status_map.insert(index, (height, start_time));
+            }
+            return true;
+        }
+    }
+    return false;
+}
+
+/// The chains whose next block can hold back a chain's block: enabled, higher priority chains which can be mined right now.
+fn get_look_ahead_chains(archon: &Archon, chain: &PocChain) -> Vec<(u8, PocChain)> {
+    let mut look_ahead_chains = Vec::new();
+    for inner in &archon.conf.poc_chains {
+        for other_chain in inner {
+            if other_chain.enabled.unwrap_or(true) {
+                let other_index = super::get_chain_index(archon, &*other_chain.url, &*other_chain.name);
+                let other_chain = super::get_chain_from_index(archon, other_index).unwrap();
+                if other_chain.priority >= chain.priority
+                    || schedule::is_chain_scheduled_off(archon, other_index)
+                    || quarantine::is_chain_quarantined(archon, other_index)
+                    || control::is_chain_paused(archon, other_index)
+                    || is_chain_in_outage(archon, other_index)
+                {
+                    continue;
                 }
+                look_ahead_chains.push((other_index, other_chain));
+            }
+        }
+    }
+    return look_ahead_chains;
+}
+
+/// When the look-ahead rule should next reconsider holding back a chain's block: the soonest any higher priority
+/// chain's odds of a new block within the grace period change. None if the rule doesn't apply, or the odds don't
+/// change with time alone, in which case only another chain's state changing can release the block.
+fn get_look_ahead_recheck_time(archon: &Archon, index: u8, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    match archon.conf.look_ahead_probability {
+        Some(probability) if probability > 0f64 && is_priority_mode(archon) => {}
+        _ => return None,
+    };
+    let chain = super::get_chain_from_index(archon, index)?;
+    let round_time = archon.conf.grace_period as i64;
+    let mut recheck_time = None;
+    for (other_index, _) in get_look_ahead_chains(archon, &chain) {
+        match prediction::get_next_arrival_probability_change(archon, other_index, round_time, now) {
+            Some(change) if recheck_time.map_or(true, |recheck_time| change < recheck_time) => {
+                recheck_time = Some(change);
             }
-            (false, _, _) => {} // nothing queued, nothing to do...
+            _ => {}
         };
+    }
+    return recheck_time;
+}
+
+/// Starts a queued block once it's the queue's turn, unless the look-ahead rule holds it back.
+fn start_queued_block(archon: &Archon, index: u8, now: DateTime<Local>) {
+    if is_block_held_back(archon, index, now) {
+        return;
+    }
+    start_mining_chain(archon, index, now);
+}
+
+pub fn is_chain_in_outage(archon: &Archon, index: u8) -> bool {
+    archon.state.chain_outages.lock().unwrap().contains_key(&index)
+}
+
+/// Treats an outage on the current chain's upstream, the chain being paused or switched off by its schedule, as an interruption: the round
+/// shouldn't carry on, so the next queued block is started instead, and the round is requeued or dropped per
+/// requeueInterruptedBlocks. The reason is printed with the decision.
+fn skip_current_round(archon: &Archon, index: u8, reason: &str, now: DateTime<Local>) {
+    let current_chain_index = get_current_chain_index(archon);
+    if index != current_chain_index || has_grace_period_elapsed(archon, now) {
+        return;
+    }
+    let current_chain = super::get_chain_from_index(archon, current_chain_index).unwrap();
+    let (height, _) = get_queued_chain_info(archon, current_chain_index);
+    match any_blocks_queued(archon, now) {
+        (true, _, next_index) if next_index != current_chain_index => {
+            requeue_current_block(
+                archon,
+                current_chain.requeue_interrupted_blocks.unwrap_or(true),
+                next_index,
+                None,
+                reason,
+                now
+            );
+            start_mining_chain(archon, next_index, now);
+        }
+        (_, _, _) => {
+            super::print_arbiter_decision(
+                archon,
+                &*current_chain.name,
+                &*current_chain.color,
+                height,
+                format!("{} during #{}, but there are no other blocks to mine.", reason, height).as_str(),
+            );
+        }
+    };
+}
 
-        thread::sleep(std::time::Duration::from_secs(1));
+/// Works out a chain's state from the shared queue, outage, quarantine and schedule status.
+pub fn get_chain_state(archon: &Archon, index: u8) -> ChainState {
+    let (latest_height, _) = get_latest_chain_info(archon, index);
+    let (queued_height, _) = get_queued_chain_info(archon, index);
+    if is_chain_in_outage(archon, index) {
+        ChainState::Outage
+    } else if quarantine::is_chain_quarantined(archon, index) {
+        ChainState::Quarantined
+    } else if control::is_chain_paused(archon, index) {
+        ChainState::Paused
+    } else if schedule::is_chain_scheduled_off(archon, index) {
+        ChainState::ScheduledOff
+    } else if queued_height < latest_height {
+        ChainState::Queued(latest_height)
+    } else if index == get_current_chain_index(archon) && queued_height > 0 {
+        ChainState::Mining(queued_height)
+    } else {
+        ChainState::Idle
     }
 }
 
-fn start_mining_chain(index: u8) {
+// checks if the round being mined is the given chain's block
+fn is_current_round(archon: &Archon, index: u8, height: u32) -> bool {
+    if index != get_current_chain_index(archon) {
+        return false;
+    }
+    let (queued_height, _) = get_queued_chain_info(archon, index);
+    return queued_height == height;
+}
+
+// the current round is outdated if its chain has found a newer block since it started
+fn is_current_round_outdated(archon: &Archon) -> bool {
+    let current_chain_index = get_current_chain_index(archon);
+    let (queued_height, _) = get_queued_chain_info(archon, current_chain_index);
+    let (latest_height, _) = get_latest_chain_info(archon, current_chain_index);
+    return latest_height != queued_height;
+}
+
+// starts, interrupts or holds back whichever queued block is next in line
+fn arbitrate_queue(archon: &Archon, now: DateTime<Local>) {
+    match any_blocks_queued(archon, now) {
+        (true, priority, index) => {
+            if is_priority_mode(archon) {
+                match priority {
+                    1 => {
+                        // 1 = higher priority than current block
+                        if is_current_round_over(archon, now) {
+                            start_queued_block(archon, index, now);
+                        } else if archon.conf.interrupt_lower_priority_blocks.unwrap_or(true) {
+                            match get_current_round_protection(archon, index) {
+                                Some(protection) => print_current_round_decision(archon, &*protection),
+                                None => {
+                                    let current_chain_index = get_current_chain_index(archon);
+                                    let current_chain =
+                                        super::get_chain_from_index(archon, current_chain_index).unwrap();
+                                    let queued_chain = super::get_chain_from_index(archon, index).unwrap();
+                                    requeue_current_block(
+                                        archon,
+                                        current_chain.requeue_interrupted_blocks.unwrap_or(true),
+                                        index,
+                                        None,
+                                        format!("{} has a higher priority ({} vs {})",
+                                            &*queued_chain.name, queued_chain.priority, current_chain.priority).as_str(),
+                                        now
+                                    );
+                                    start_mining_chain(archon, index, now);
+                                }
+                            };
+                        } // else do nothing
+                    }
+                    0 => {
+                        // 0 = same priority tier as current block, only cut the current round short if it's outdated
+                        if index == get_current_chain_index(archon)
+                            || is_current_round_outdated(archon)
+                            || is_current_round_over(archon, now)
+                        {
+                            start_queued_block(archon, index, now);
+                        } // else do nothing
+                    }
+                    _ => {
+                        // -1 = lower priority than current block
+                        if is_current_round_over(archon, now) {
+                            start_queued_block(archon, index, now);
+                        } // else do nothing
+                    }
+                };
+            } else {
+                // FIFO & fair share modes
+                if is_current_round_over(archon, now) {
+                    start_mining_chain(archon, index, now);
+                } // else do nothing
+            }
+        }
+        (false, _, _) => {} // nothing queued, nothing to do...
+    };
+}
+
+fn start_mining_chain(archon: &Archon, index: u8, now: DateTime<Local>) {
     // get chain
-    match super::get_chain_from_index(index) {
+    match super::get_chain_from_index(archon, index) {
         Some(chain) => {
             // get access to chain mining infos
-            match get_current_chain_mining_info(index) {
+            match get_current_chain_mining_info(archon, index) {
                 Some((mining_info, _)) => {
                     if mining_info.base_target > 0 {
+                        // credit the outgoing round's scan time before the share is printed
+                        record_current_round_mining_time(archon, now);
                         // get currently mining block height before we change it
-                        let current_block_height = match super::get_current_mining_info() {
+                        let current_block_height = match super::get_current_mining_info(archon) {
                             Some(mi) => mi.height,
                             _ => 0,
                         };
-                        let last_block_time = get_time_since_block_start(current_block_height);
+                        let last_block_time = get_time_since_block_start(archon, current_block_height);
                         // print block info
                         super::print_block_started(
+                            archon,
                             index,
                             mining_info.height,
                             mining_info.base_target,
@@ -547,12 +1601,25 @@ fn start_mining_chain(index: u8) {
                         );
                         info!("START BLOCK - Chain #{} - Block #{} - Priority {} | {} | {}", index, mining_info.height, chain.priority, &*chain.name, &*chain.url);
                         // set last mining info
-                        *crate::LAST_MINING_INFO.lock().unwrap() = super::get_current_mining_info_json();
+                        *archon.state.last_mining_info.lock().unwrap() = super::get_current_mining_info_json(archon);
                         // set current chain index
-                        *crate::CURRENT_CHAIN_INDEX.lock().unwrap() = index;
+                        *archon.state.current_chain_index.lock().unwrap() = index;
                         // update the queue status for this chain
-                        let mut chain_queue_status_map = crate::CHAIN_QUEUE_STATUS.lock().unwrap();
-                        chain_queue_status_map.insert(index, (mining_info.height, Local::now()));
+                        let mut chain_queue_status_map = archon.state.chain_queue_status.lock().unwrap();
+                        chain_queue_status_map.insert(index, (mining_info.height, now));
+                        drop(chain_queue_status_map);
+                        rounds::record_round_started(archon, index, &mining_info, now);
+                        miners::record_round_started(archon, index, mining_info.height, now);
+                        watchdog::check_finished_round(archon, now);
+                        capacity::check_capacity_discrepancy(archon, index, now);
+                        events::publish(archon, ArchonEvent::BlockStarted {
+                            chain_index: index,
+                            chain_name: chain.name.clone(),
+                            height: mining_info.height,
+                            base_target: mining_info.base_target,
+                            generation_signature: mining_info.generation_signature.clone(),
+                            target_deadline: mining_info.target_deadline,
+                        });
                     }
                 }
                 _ => {}
@@ -562,13 +1629,54 @@ fn start_mining_chain(index: u8) {
     };
 }
 
-pub fn get_best_deadlines() -> HashMap<u32, Vec<(u64, u64)>> {
-    return crate::BEST_DEADLINES.lock().unwrap().clone();
+/// Starts a chain's latest block straight away, for the admin API. If another chain's round is still in its grace
+/// period it's interrupted, and the new round is protected from being interrupted in turn.
+fn force_start_chain(archon: &Archon, index: u8, requested_by: &str, now: DateTime<Local>) {
+    let (height, _) = get_latest_chain_info(archon, index);
+    if height == 0 {
+        return;
+    }
+    let current_chain_index = get_current_chain_index(archon);
+    let (current_height, _) = get_queued_chain_info(archon, current_chain_index);
+    if index != current_chain_index && current_height > 0 && !has_grace_period_elapsed(archon, now) {
+        let current_chain = super::get_chain_from_index(archon, current_chain_index).unwrap();
+        requeue_current_block(
+            archon,
+            current_chain.requeue_interrupted_blocks.unwrap_or(true),
+            index,
+            None,
+            format!("Force-started by {}", requested_by).as_str(),
+            now
+        );
+    }
+    control::record_force_started_round(archon, index, height);
+    control::record_admin_action(archon, index, "forceStart", format!("Force-started #{}.", height).as_str(), requested_by);
+    start_mining_chain(archon, index, now);
+}
+
+/// Marks a chain's queued block as done without mining it, for the admin API.
+fn drop_queued_block(archon: &Archon, index: u8, requested_by: &str, now: DateTime<Local>) {
+    let (latest_height, _) = get_latest_chain_info(archon, index);
+    let mut chain_queue_status_map = archon.state.chain_queue_status.lock().unwrap();
+    let (queued_height, start_time) = match chain_queue_status_map.get(&index) {
+        Some((queued_height, start_time)) => (*queued_height, *start_time),
+        None => (0u32, now),
+    };
+    if queued_height >= latest_height {
+        return;
+    }
+    chain_queue_status_map.insert(index, (latest_height, start_time));
+    drop(chain_queue_status_map);
+    control::record_admin_action(archon, index, "dropQueuedBlock", format!("Dropped queued block #{}.", latest_height).as_str(), requested_by);
+}
+
+pub fn get_best_deadlines(archon: &Archon) -> HashMap<(u8, u32), Vec<(u64, u64)>> {
+    return archon.state.best_deadlines.lock().unwrap().clone();
 }
 
-pub fn get_best_deadlines_for_block(block_height: u32) -> Option<Vec<(u64, u64)>> {
-    let best_deadlines_map = crate::BEST_DEADLINES.lock().unwrap();
-    match best_deadlines_map.get(&block_height) {
+pub fn get_best_deadlines_for_block(archon: &Archon, chain_index: u8, block_height: u32) -> Option<Vec<(u64, u64)>> {
+    let best_deadlines_map = archon.state.best_deadlines.lock().unwrap();
+    match best_deadlines_map.get(&(chain_index, block_height)) {
         Some(best_deadlines) => {
             return Some(best_deadlines.to_vec());
         }
@@ -576,8 +1684,8 @@ pub fn get_best_deadlines_for_block(block_height: u32) -> Option<Vec<(u64, u64)>
     };
 }
 
-pub fn get_best_deadline(block_height: u32, account_id: u64) -> u64 {
-    match get_best_deadlines_for_block(block_height) {
+pub fn get_best_deadline(archon: &Archon, chain_index: u8, block_height: u32, account_id: u64) -> u64 {
+    match get_best_deadlines_for_block(archon, chain_index, block_height) {
         Some(best_deadlines) => {
             for best_deadline_tuple_ref in best_deadlines {
                 let (id, deadline) = best_deadline_tuple_ref;
@@ -593,9 +1701,9 @@ pub fn get_best_deadline(block_height: u32, account_id: u64) -> u64 {
     return u64::max_value();
 }
 
-fn update_best_deadline(block_height: u32, account_id: u64, deadline: u64) {
-    debug!("NEW BestDL - Height={}, ID={}, DL={}", block_height, account_id, deadline);
-    match get_best_deadlines_for_block(block_height) {
+fn update_best_deadline(archon: &Archon, chain_index: u8, block_height: u32, account_id: u64, deadline: u64) {
+    debug!("NEW BestDL - Chain #{} Height={}, ID={}, DL={}", chain_index, block_height, account_id, deadline);
+    match get_best_deadlines_for_block(archon, chain_index, block_height) {
         Some(mut best_deadlines) => {
             // check if account id has a deadline in the vec
             let mut existing = (0u64, 0u64);
@@ -612,19 +1720,20 @@ fn update_best_deadline(block_height: u32, account_id: u64, deadline: u64) {
                 &best_deadlines.remove_item(&existing);
             }
             best_deadlines.push((account_id, deadline));
-            let mut best_deadlines_map = crate::BEST_DEADLINES.lock().unwrap();
-            best_deadlines_map.insert(block_height, best_deadlines);
+            let mut best_deadlines_map = archon.state.best_deadlines.lock().unwrap();
+            best_deadlines_map.insert((chain_index, block_height), best_deadlines);
         }
         None => {
-            let mut best_deadlines_map = crate::BEST_DEADLINES.lock().unwrap();
+            let mut best_deadlines_map = archon.state.best_deadlines.lock().unwrap();
             let mut best_deadlines: Vec<(u64, u64)> = Vec::new();
             best_deadlines.push((account_id, deadline));
-            best_deadlines_map.insert(block_height, best_deadlines);
+            best_deadlines_map.insert((chain_index, block_height), best_deadlines);
         }
     };
 }
 
-fn get_target_deadline(
+pub fn get_target_deadline(
+    archon: &Archon,
     account_id: u64,
     base_target: u32,
     chain_index: u8,
@@ -633,7 +1742,7 @@ fn get_target_deadline(
 ) -> (u64, bool) {
     // get max deadline from upstream if present
     let upstream_target_deadline;
-    let mut target_deadline = match get_current_chain_mining_info(chain_index) {
+    let mut target_deadline = match get_current_chain_mining_info(archon, chain_index) {
         Some((mining_info, _)) => mining_info.target_deadline,
         _ => u64::max_value(),
     };
@@ -654,8 +1763,8 @@ fn get_target_deadline(
         _ => target_deadline,
     };
 
-    // calculate the dynamic deadline
-    target_deadline = match super::get_dynamic_deadline_for_block(base_target) {
+    // calculate the dynamic deadline, from the account's own plot capacity if it has one
+    target_deadline = match super::get_dynamic_deadline_for_account(archon, chain_index, account_id, base_target) {
         (true, _, _, dynamic_target_deadline) => {
             if dynamic_target_deadline < target_deadline {
                 dynamic_target_deadline
@@ -683,10 +1792,16 @@ fn get_target_deadline(
     return (target_deadline, id_override);
 }
 
-fn forward_nonce_submission(chain_index: u8, url: &str, user_agent_header: &str) -> Option<String> {
-    let chain_nonce_submission_clients = crate::CHAIN_NONCE_SUBMISSION_CLIENTS.lock().unwrap();
+fn get_seconds_since(start: Instant) -> f64 {
+    let elapsed = start.elapsed();
+    elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1_000_000_000f64
+}
+
+fn forward_nonce_submission(archon: &Archon, chain_index: u8, url: &str, user_agent_header: &str) -> Option<String> {
+    let chain_nonce_submission_clients = archon.upstream.nonce_submission_clients.lock().unwrap();
     match chain_nonce_submission_clients.get(&chain_index) {
         Some(client) => {
+            let request_start = Instant::now();
             match client
                 .post(url)
                 .header(
@@ -701,7 +1816,10 @@ fn forward_nonce_submission(chain_index: u8, url: &str, user_agent_header: &str)
                 .send()
             {
                 Ok(mut response) => match &response.text() {
-                    Ok(text) => Some(text.to_string()),
+                    Ok(text) => {
+                        archon.state.metrics.observe_upstream_latency(chain_index, "submitNonce", get_seconds_since(request_start));
+                        Some(text.to_string())
+                    }
                     Err(why) => {
                         warn!("Forward Nonce Submission(chInd={}, url={}, software={}) - Couldn't retrieve response data: {:?}", chain_index, url, user_agent_header, why);
                         None
@@ -721,6 +1839,7 @@ fn forward_nonce_submission(chain_index: u8, url: &str, user_agent_header: &str)
 }
 
 pub fn process_nonce_submission(
+    archon: &Archon,
     block_height: u32,
     account_id: u64,
     nonce: u64,
@@ -732,20 +1851,20 @@ pub fn process_nonce_submission(
     debug!("Received DL: Height={}, ID={}, Nonce={}, DL={:?}, Software={}, Adjusted={}, Address={}", block_height, account_id, nonce, deadline, user_agent_header, adjusted, remote_addr);
     // validate data
     // get mining info for chain
-    let chain_index = get_chain_index_from_height(block_height); // defaults to the chain being currently mined if it cannot find a height match
-    let current_chain = super::get_chain_from_index(chain_index).unwrap();
-    let base_target = match get_current_chain_mining_info(chain_index) {
+    let chain_index = get_chain_index_from_height(archon, block_height); // defaults to the chain being currently mined if it cannot find a height match
+    let current_chain = super::get_chain_from_index(archon, chain_index).unwrap();
+    let base_target = match get_current_chain_mining_info(archon, chain_index) {
         Some((mining_info, _)) => mining_info.base_target,
         _ => 0,
     };
     if base_target > 0 {
         let mut height = block_height;
         if height == 0 {
-            height = match get_latest_chain_info(chain_index) {
+            height = match get_latest_chain_info(archon, chain_index) {
                 (height, _) => height,
             };
         }
-        let start_time = Local::now();
+        let start_time = archon.now();
         let mut send_deadline = true;
         let mut print_deadline = true;
         let mut _deadline_sent = false;
@@ -762,6 +1881,7 @@ pub fn process_nonce_submission(
                     adjusted_deadline = dl;
                 }
                 let (target_deadline, id_override) = get_target_deadline(
+                    archon,
                     account_id,
                     base_target,
                     chain_index,
@@ -775,15 +1895,17 @@ pub fn process_nonce_submission(
                     print_deadline = false;
                 }
                 // check that this deadline is better than the best one submitted for this block and this account id
-                let best_deadline = get_best_deadline(height, account_id);
+                let best_deadline = get_best_deadline(archon, chain_index, height, account_id);
                 if best_deadline < adjusted_deadline {
                     send_deadline = false;
                     deadline_over_best = true;
                     print_deadline = false;
                 }
+                watchdog::record_submission(archon, chain_index, height, account_id, &*remote_addr, user_agent_header, archon.now());
                 let mut failure_message = String::from("");
                 if print_deadline {
                     super::print_nonce_submission(
+                        archon,
                         chain_index,
                         height,
                         account_id,
@@ -795,8 +1917,17 @@ pub fn process_nonce_submission(
                     );
                 }
                 if !deadline_over_best {
-                    update_best_deadline(height, account_id, adjusted_deadline);
+                    update_best_deadline(archon, chain_index, height, account_id, adjusted_deadline);
                 }
+                events::publish(archon, ArchonEvent::NonceSubmitted {
+                    chain_index,
+                    chain_name: current_chain.name.clone(),
+                    height,
+                    account_id: super::censor_account_id(archon, account_id),
+                    deadline: adjusted_deadline,
+                    miner_software: String::from(user_agent_header),
+                    forwarded: send_deadline,
+                });
                 let mut passphrase_str = String::from("");
                 // if solo mining burst, look for a passphrase from config for this account id
                 if !current_chain.is_hpool.unwrap_or_default()
@@ -847,7 +1978,7 @@ pub fn process_nonce_submission(
                     while attempts < 5 && !deadline_accepted && !deadline_rejected {
                         _deadline_sent = true;
                         info!("DL Send - #{} | ID={} | DL={} (Unadjusted={}) - Attempt #{}/5", block_height, account_id, adjusted_deadline, unadjusted_deadline, attempts + 1);
-                        match forward_nonce_submission(chain_index, url.as_str(), user_agent_header)
+                        match forward_nonce_submission(archon, chain_index, url.as_str(), user_agent_header)
                         {
                             Some(text) => {
                                 debug!("DL Submit Response: {}", text);
@@ -867,11 +1998,36 @@ pub fn process_nonce_submission(
                         thread::sleep(std::time::Duration::from_secs(1));
                     }
                 }
+                if _deadline_sent {
+                    // rejections still mean the upstream is answering, only count submissions which never got through
+                    let failed = !deadline_accepted && !deadline_rejected;
+                    if quarantine::record_submission_result(archon, chain_index, failed) {
+                        send_arbiter_event(archon, ArbiterEvent::SubmissionsFailing(chain_index));
+                    } else if !failed && quarantine::get_quarantine(archon, chain_index).map(|(reason, _)| reason) == Some(QuarantineReason::SubmissionsFailing) {
+                        send_arbiter_event(archon, ArbiterEvent::SubmissionsRecovered(chain_index));
+                    }
+                }
                 if deadline_accepted {
-                    let confirm_time = (Local::now() - start_time).num_milliseconds();
+                    let confirm_time = (archon.now() - start_time).num_milliseconds();
                     info!("DL Confirmed - #{} | ID={} | DL={} (Unadjusted={}) | {}ms", block_height, account_id, adjusted_deadline, unadjusted_deadline, confirm_time);
+                    update_best_confirmed_deadline(archon, chain_index, height, adjusted_deadline);
+                    send_arbiter_event(archon, ArbiterEvent::SubmissionReceived {
+                        chain_index,
+                        height,
+                        deadline: adjusted_deadline,
+                        confirmed: true,
+                    });
+                    events::publish(archon, ArchonEvent::NonceAccepted {
+                        chain_index,
+                        chain_name: current_chain.name.clone(),
+                        height,
+                        account_id: super::censor_account_id(archon, account_id),
+                        deadline: adjusted_deadline,
+                        confirmation_time_ms: confirm_time,
+                    });
                     // print nonce confirmation
                     super::print_nonce_accepted(
+                        archon,
                         chain_index,
                         height,
                         adjusted_deadline,
@@ -885,10 +2041,18 @@ pub fn process_nonce_submission(
                     };
                     return resp.to_json();
                 } else if deadline_rejected {
-                    let reject_time = (Local::now() - start_time).num_milliseconds();
+                    let reject_time = (archon.now() - start_time).num_milliseconds();
                     info!("DL Rejected - #{} | ID={} | DL={} (Unadjusted={}) | {}ms - Response: {}", block_height, account_id, adjusted_deadline, unadjusted_deadline, reject_time, failure_message);
+                    events::publish(archon, ArchonEvent::NonceRejected {
+                        chain_index,
+                        chain_name: current_chain.name.clone(),
+                        height,
+                        account_id: super::censor_account_id(archon, account_id),
+                        deadline: adjusted_deadline,
+                        rejection_time_ms: reject_time,
+                    });
                     // print confirmation failure
-                    super::print_nonce_rejected(chain_index, height, adjusted_deadline, reject_time);
+                    super::print_nonce_rejected(archon, chain_index, height, adjusted_deadline, reject_time);
                     let (ds_success, response) = SubmitNonceResponse::from_json(failure_message.as_str());
                     if ds_success {
                         return response.to_json();
@@ -952,4 +2116,522 @@ pub fn process_nonce_submission(
         )),
     };
     return resp.to_json();
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{Clock, FakeClock};
+    use crate::events::PublishedEvent;
+    use chrono::TimeZone;
+
+    const CONFIG: &str = "
+gracePeriod: 30
+priorityMode: true
+webServerBindAddress: 127.0.0.1
+webServerPort: 1337
+pocChains:
+  - name: High
+    priority: 0
+    url: http://127.0.0.1:1
+    color: cyan
+  - name: Low
+    priority: 1
+    url: http://127.0.0.1:2
+    color: green
+";
+    const HIGH: u8 = 0;
+    const LOW: u8 = 1;
+
+    fn create_arbiter(config: &str) -> (Arbiter, FakeClock) {
+        let clock = FakeClock::new(Local.with_ymd_and_hms(2020, 1, 1, 12, 0, 0).unwrap());
+        let conf = serde_yaml::from_str(config).unwrap();
+        let archon = Arc::new(Archon::with_clock(conf, Box::new(clock.clone())));
+        (Arbiter::new(archon), clock)
+    }
+
+    fn create_polling_result(arbiter: &Arbiter, index: u8, height: u32, generation_signature: String) -> MiningInfoPollingResult {
+        MiningInfoPollingResult {
+            mining_info: MiningInfo {
+                height,
+                base_target: 70000,
+                generation_signature,
+                request_processing_time: None,
+                target_deadline: u64::max_value(),
+            },
+            chain: crate::get_configured_chain_from_index(&*arbiter.archon, index).unwrap(),
+        }
+    }
+
+    fn new_block(arbiter: &Arbiter, index: u8, height: u32) -> ArbiterEvent {
+        ArbiterEvent::NewBlock(create_polling_result(arbiter, index, height, format!("{:064}", height)))
+    }
+
+    fn fork(arbiter: &Arbiter, index: u8, height: u32) -> ArbiterEvent {
+        ArbiterEvent::Fork(create_polling_result(arbiter, index, height, format!("{:064}", height + 1000000)))
+    }
+
+    #[test]
+    fn lower_priority_block_waits_for_grace_period() {
+        let (mut arbiter, clock) = create_arbiter(CONFIG);
+        let start = clock.now();
+        arbiter.handle_event(new_block(&arbiter, HIGH, 100), start);
+        let now = clock.advance(5);
+        arbiter.handle_event(new_block(&arbiter, LOW, 500), now);
+        assert_eq!(arbiter.get_chain_state(HIGH), ChainState::Mining(100));
+        assert_eq!(arbiter.get_chain_state(LOW), ChainState::Queued(500));
+        let (event, due) = arbiter.next_timer(now).unwrap();
+        assert_eq!(due, start + chrono::Duration::seconds(30));
+        match event {
+            ArbiterEvent::GracePeriodExpired(HIGH, 100) => {}
+            event => panic!("Unexpected timer: {:?}", event),
+        };
+        let now = clock.advance(25);
+        arbiter.handle_event(event, now);
+        assert_eq!(arbiter.get_chain_state(LOW), ChainState::Mining(500));
+    }
+
+    #[test]
+    fn grace_period_timer_of_replaced_round_is_ignored() {
+        let (mut arbiter, clock) = create_arbiter(CONFIG);
+        arbiter.handle_event(new_block(&arbiter, HIGH, 100), clock.now());
+        let (stale_timer, _) = arbiter.next_timer(clock.now()).unwrap();
+        let now = clock.advance(5);
+        arbiter.handle_event(new_block(&arbiter, LOW, 500), now);
+        let now = clock.advance(5);
+        arbiter.handle_event(new_block(&arbiter, HIGH, 101), now);
+        let now = clock.advance(25);
+        arbiter.handle_event(stale_timer, now);
+        assert_eq!(arbiter.get_chain_state(HIGH), ChainState::Mining(101));
+        assert_eq!(arbiter.get_chain_state(LOW), ChainState::Queued(500));
+    }
+
+    #[test]
+    fn higher_priority_block_interrupts_and_requeues() {
+        let (mut arbiter, clock) = create_arbiter(CONFIG);
+        arbiter.handle_event(new_block(&arbiter, LOW, 500), clock.now());
+        assert_eq!(arbiter.get_chain_state(LOW), ChainState::Mining(500));
+        let now = clock.advance(10);
+        arbiter.handle_event(new_block(&arbiter, HIGH, 100), now);
+        assert_eq!(arbiter.get_chain_state(HIGH), ChainState::Mining(100));
+        assert_eq!(arbiter.get_chain_state(LOW), ChainState::Queued(500));
+    }
+
+    fn get_printed_decisions(arbiter: &Arbiter, chain_name: &str) -> Vec<String> {
+        arbiter.archon.state.printed_arbiter_decisions
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(name, _, _)| name == chain_name)
+            .map(|(_, _, decision)| decision.clone())
+            .collect()
+    }
+
+    #[test]
+    fn protected_round_is_not_interrupted() {
+        let (mut arbiter, clock) = create_arbiter(&*CONFIG.replace("    color: green\n", "    color: green\n    protectDeadline: 1000\n"));
+        arbiter.handle_event(new_block(&arbiter, LOW, 500), clock.now());
+        update_best_deadline(&*arbiter.archon, LOW, 500, 1, 10);
+        assert!(get_current_round_protection(&*arbiter.archon, HIGH).is_some());
+        // asking doesn't print anything, only acting on it does
+        assert!(get_printed_decisions(&arbiter, "Low").is_empty());
+        arbiter.handle_event(new_block(&arbiter, HIGH, 100), clock.advance(5));
+        assert_eq!(arbiter.get_chain_state(LOW), ChainState::Mining(500));
+        assert_eq!(arbiter.get_chain_state(HIGH), ChainState::Queued(100));
+        let decisions = get_printed_decisions(&arbiter, "Low");
+        assert_eq!(decisions.len(), 1);
+        assert!(decisions[0].starts_with("Protected #500 from interruption by High"));
+    }
+
+    #[test]
+    fn early_release_is_only_printed_when_the_round_is_released() {
+        let (mut arbiter, clock) = create_arbiter(&*CONFIG.replace("    color: cyan\n", "    color: cyan\n    earlyReleaseDeadline: 1000\n"));
+        arbiter.handle_event(new_block(&arbiter, HIGH, 100), clock.now());
+        arbiter.handle_event(new_block(&arbiter, LOW, 500), clock.advance(5));
+        update_best_confirmed_deadline(&*arbiter.archon, HIGH, 100, 10);
+        assert!(has_grace_period_elapsed(&*arbiter.archon, clock.now()));
+        assert!(get_printed_decisions(&arbiter, "High").is_empty());
+        let confirmed = ArbiterEvent::SubmissionReceived { chain_index: HIGH, height: 100, deadline: 10, confirmed: true };
+        arbiter.handle_event(confirmed, clock.advance(1));
+        assert_eq!(arbiter.get_chain_state(LOW), ChainState::Mining(500));
+        let decisions = get_printed_decisions(&arbiter, "High");
+        assert_eq!(decisions.len(), 1);
+        assert!(decisions[0].starts_with("Released #100 early"));
+    }
+
+    #[test]
+    fn pausing_current_chain_moves_on_to_queued_block() {
+        let (mut arbiter, clock) = create_arbiter(CONFIG);
+        arbiter.handle_event(new_block(&arbiter, HIGH, 100), clock.now());
+        let now = clock.advance(5);
+        arbiter.handle_event(new_block(&arbiter, LOW, 500), now);
+        let now = clock.advance(5);
+        control::pause_chain(&*arbiter.archon, HIGH, "test", now);
+        arbiter.handle_event(ArbiterEvent::ChainPaused(HIGH), now);
+        assert_eq!(arbiter.get_chain_state(HIGH), ChainState::Paused);
+        assert_eq!(arbiter.get_chain_state(LOW), ChainState::Mining(500));
+    }
+
+    #[test]
+    fn resuming_chain_starts_its_queued_block() {
+        let (mut arbiter, clock) = create_arbiter(CONFIG);
+        control::pause_chain(&*arbiter.archon, HIGH, "test", clock.now());
+        arbiter.handle_event(ArbiterEvent::ChainPaused(HIGH), clock.now());
+        arbiter.handle_event(new_block(&arbiter, LOW, 500), clock.now());
+        let now = clock.advance(5);
+        arbiter.handle_event(new_block(&arbiter, HIGH, 100), now);
+        assert_eq!(arbiter.get_chain_state(HIGH), ChainState::Paused);
+        assert_eq!(arbiter.get_chain_state(LOW), ChainState::Mining(500));
+        let now = clock.advance(5);
+        control::resume_chain(&*arbiter.archon, HIGH, "test", now);
+        arbiter.handle_event(ArbiterEvent::ChainResumed(HIGH), now);
+        assert_eq!(arbiter.get_chain_state(HIGH), ChainState::Mining(100));
+        assert_eq!(arbiter.get_chain_state(LOW), ChainState::Queued(500));
+    }
+
+    #[test]
+    fn held_back_block_does_not_poll() {
+        let config = format!("{}lookAheadProbability: 0.5\n", CONFIG.replace("gracePeriod: 30", "gracePeriod: 240"));
+        let (mut arbiter, clock) = create_arbiter(&*config);
+        arbiter.handle_event(new_block(&arbiter, HIGH, 100), clock.now());
+        let now = clock.advance(240);
+        arbiter.handle_event(new_block(&arbiter, LOW, 500), now);
+        // with no block history the odds of a new high priority block don't change with time, so nothing is due
+        assert_eq!(arbiter.get_chain_state(LOW), ChainState::Queued(500));
+        assert!(arbiter.next_timer(now).is_none());
+    }
+
+    #[test]
+    fn held_back_block_is_rechecked_when_odds_change() {
+        let config = format!("{}lookAheadProbability: 0.5\n", CONFIG);
+        let (mut arbiter, clock) = create_arbiter(&*config);
+        // every high priority block so far came 60 seconds after the previous one
+        let mut now = clock.now();
+        for height in 100..112 {
+            arbiter.handle_event(new_block(&arbiter, HIGH, height), now);
+            now = clock.advance(60);
+        }
+        let now = clock.advance(-30);
+        arbiter.handle_event(new_block(&arbiter, LOW, 500), now);
+        assert_eq!(arbiter.get_chain_state(LOW), ChainState::Queued(500));
+        // 30 seconds in, the next high priority block is due within the 30 second grace period, until 60 seconds in
+        let (event, due) = arbiter.next_timer(now).unwrap();
+        assert_eq!(due, now + chrono::Duration::seconds(30));
+        match event {
+            ArbiterEvent::LookAheadRecheck(LOW) => {}
+            event => panic!("Unexpected timer: {:?}", event),
+        };
+        let now = clock.advance(30);
+        arbiter.handle_event(event, now);
+        assert_eq!(arbiter.get_chain_state(LOW), ChainState::Mining(500));
+    }
+
+    #[test]
+    fn paused_chain_does_not_set_look_ahead_timers() {
+        let config = format!(
+            "{}  - name: Mid\n    priority: 1\n    url: http://127.0.0.1:3\n    color: yellow\nlookAheadProbability: 0.1\n",
+            CONFIG.replace("priority: 1", "priority: 2")
+        );
+        let (mut arbiter, clock) = create_arbiter(&*config);
+        let mid = 2;
+        let mut now = clock.now();
+        for height in 100..112 {
+            arbiter.handle_event(new_block(&arbiter, HIGH, height), now);
+            now = clock.advance(120);
+        }
+        // the high priority chain's odds change 90 seconds after its last block, but it's paused
+        let now = clock.advance(-80);
+        control::pause_chain(&*arbiter.archon, HIGH, "test", now);
+        arbiter.handle_event(ArbiterEvent::ChainPaused(HIGH), now);
+        arbiter.handle_event(new_block(&arbiter, mid, 700), now);
+        let now = clock.advance(30);
+        arbiter.handle_event(new_block(&arbiter, LOW, 500), now);
+        // the mid priority chain has no block history, so its odds only change with a new block
+        assert_eq!(arbiter.get_chain_state(LOW), ChainState::Queued(500));
+        assert!(arbiter.next_timer(now).is_none());
+    }
+
+    #[test]
+    fn held_back_block_is_printed_once_as_odds_change() {
+        let config = format!("{}lookAheadProbability: 0.3\n", CONFIG);
+        let (mut arbiter, clock) = create_arbiter(&*config);
+        // high priority blocks came 60, 90 and 120 seconds after the previous one
+        arbiter.handle_event(new_block(&arbiter, HIGH, 100), clock.now());
+        for height in 101..113 {
+            let now = clock.advance([60, 90, 120][height as usize % 3]);
+            arbiter.handle_event(new_block(&arbiter, HIGH, height), now);
+        }
+        // 30 seconds in a third of the blocks still to come arrive within the grace period, 60 seconds in half of them
+        let now = clock.advance(30);
+        arbiter.handle_event(new_block(&arbiter, LOW, 500), now);
+        assert_eq!(arbiter.get_chain_state(LOW), ChainState::Queued(500));
+        let (event, due) = arbiter.next_timer(now).unwrap();
+        assert_eq!(due, now + chrono::Duration::seconds(30));
+        arbiter.handle_event(event, clock.advance(30));
+        assert_eq!(arbiter.get_chain_state(LOW), ChainState::Queued(500));
+        let held_back_decisions = arbiter.archon.state.printed_arbiter_decisions
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(name, height, decision)| name == "Low" && *height == 500 && decision.starts_with("Holding back"))
+            .count();
+        assert_eq!(held_back_decisions, 1);
+    }
+
+    #[test]
+    fn fork_only_invalidates_its_own_chains_deadlines() {
+        let (mut arbiter, clock) = create_arbiter(CONFIG);
+        let archon = arbiter.archon.clone();
+        arbiter.handle_event(new_block(&arbiter, HIGH, 100), clock.now());
+        arbiter.handle_event(new_block(&arbiter, LOW, 100), clock.now());
+        update_best_deadline(&*archon, HIGH, 100, 1, 500);
+        update_best_deadline(&*archon, LOW, 100, 1, 600);
+        arbiter.handle_event(fork(&arbiter, HIGH, 100), clock.advance(5));
+        assert_eq!(get_best_deadline_for_round(&*archon, HIGH, 100), None);
+        assert_eq!(get_best_deadline_for_round(&*archon, LOW, 100), Some(600));
+    }
+
+    #[test]
+    fn fork_depth_is_capped() {
+        let (mut arbiter, clock) = create_arbiter(CONFIG);
+        let archon = arbiter.archon.clone();
+        arbiter.handle_event(new_block(&arbiter, HIGH, 100), clock.now());
+        update_best_deadline(&*archon, HIGH, 100, 1, 500);
+        update_best_deadline(&*archon, HIGH, 60, 1, 400);
+        arbiter.handle_event(fork(&arbiter, HIGH, 50), clock.advance(5));
+        assert_eq!(get_best_deadline_for_round(&*archon, HIGH, 100), None);
+        assert_eq!(get_best_deadline_for_round(&*archon, HIGH, 60), Some(400));
+    }
+
+    #[test]
+    fn new_block_only_releases_stale_quarantine() {
+        let (mut arbiter, clock) = create_arbiter(CONFIG);
+        arbiter.handle_event(new_block(&arbiter, HIGH, 100), clock.now());
+        arbiter.handle_event(ArbiterEvent::ChainStale(HIGH), clock.advance(5));
+        assert_eq!(arbiter.get_chain_state(HIGH), ChainState::Quarantined);
+        arbiter.handle_event(new_block(&arbiter, HIGH, 101), clock.advance(5));
+        assert_eq!(arbiter.get_chain_state(HIGH), ChainState::Mining(101));
+        arbiter.handle_event(ArbiterEvent::SubmissionsFailing(HIGH), clock.advance(5));
+        arbiter.handle_event(new_block(&arbiter, HIGH, 102), clock.advance(5));
+        assert_eq!(arbiter.get_chain_state(HIGH), ChainState::Quarantined);
+    }
+
+    #[test]
+    fn quarantining_current_chain_moves_on_to_queued_block() {
+        let (mut arbiter, clock) = create_arbiter(CONFIG);
+        arbiter.handle_event(new_block(&arbiter, HIGH, 100), clock.now());
+        arbiter.handle_event(new_block(&arbiter, LOW, 500), clock.advance(5));
+        assert_eq!(arbiter.get_chain_state(LOW), ChainState::Queued(500));
+        arbiter.handle_event(ArbiterEvent::SubmissionsFailing(HIGH), clock.advance(5));
+        assert_eq!(arbiter.get_chain_state(HIGH), ChainState::Quarantined);
+        assert_eq!(arbiter.get_chain_state(LOW), ChainState::Mining(500));
+    }
+
+    #[test]
+    fn failing_submissions_quarantine_backs_off() {
+        let (mut arbiter, clock) = create_arbiter(CONFIG);
+        let quarantined = clock.now();
+        arbiter.handle_event(ArbiterEvent::SubmissionsFailing(HIGH), quarantined);
+        let (event, due) = arbiter.next_timer(quarantined).unwrap();
+        assert_eq!(due, quarantined + chrono::Duration::seconds(300));
+        match event {
+            ArbiterEvent::QuarantineBackoffExpired(HIGH) => {}
+            event => panic!("Unexpected timer: {:?}", event),
+        };
+        arbiter.handle_event(event, clock.advance(300));
+        assert!(!quarantine::is_chain_quarantined(&*arbiter.archon, HIGH));
+        // quarantined again before a submission got through, so it waits twice as long
+        let quarantined = clock.advance(60);
+        arbiter.handle_event(ArbiterEvent::SubmissionsFailing(HIGH), quarantined);
+        let (_, due) = arbiter.next_timer(quarantined).unwrap();
+        assert_eq!(due, quarantined + chrono::Duration::seconds(600));
+        arbiter.handle_event(ArbiterEvent::SubmissionsRecovered(HIGH), clock.advance(60));
+        assert!(!quarantine::is_chain_quarantined(&*arbiter.archon, HIGH));
+    }
+
+    #[test]
+    fn nonce_submission_is_timed_by_archon_clock() {
+        // an upstream which takes two seconds of fake time to confirm a deadline
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let upstream_url = format!("http://127.0.0.1:{}", listener.local_addr().unwrap().port());
+        let config = CONFIG
+            .replace("http://127.0.0.1:1", &*upstream_url)
+            .replace("    color: cyan", "    color: cyan\n    isPool: true");
+        let (mut arbiter, clock) = create_arbiter(&*config);
+        let archon = arbiter.archon.clone();
+        arbiter.handle_event(new_block(&arbiter, HIGH, 100), clock.now());
+        let upstream_clock = clock.clone();
+        thread::spawn(move || {
+            use std::io::{Read, Write};
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 4096];
+            let _ = stream.read(&mut request);
+            upstream_clock.advance(2);
+            let body = "{\"result\":\"success\",\"deadline\":1000}";
+            write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body).unwrap();
+        });
+        create_chain_nonce_submission_client(&*archon, HIGH);
+        let response = process_nonce_submission(&*archon, 100, 1, 1, Some(1000), "test", true, String::from("127.0.0.1:1234"));
+        assert!(response.contains("success"), "Unexpected response: {}", response);
+        // the deadline was confirmed, so its event has already been published
+        let confirmation_time_ms = futures::Stream::wait(events::subscribe(&*archon, Some(0)))
+            .filter_map(|published_event| match published_event {
+                Ok(PublishedEvent { event: ArchonEvent::NonceAccepted { confirmation_time_ms, .. }, .. }) => Some(confirmation_time_ms),
+                _ => None,
+            })
+            .next();
+        assert_eq!(confirmation_time_ms, Some(2000));
+    }
+
+    const FAIR_SHARE_CONFIG: &str = "
+gracePeriod: 30
+fairShareMode: true
+webServerBindAddress: 127.0.0.1
+webServerPort: 1337
+pocChains:
+  - name: High
+    priority: 0
+    url: http://127.0.0.1:1
+    color: cyan
+    miningShare: 50
+  - name: Low
+    priority: 1
+    url: http://127.0.0.1:2
+    color: green
+    miningShare: 30
+  - name: Rest
+    priority: 2
+    url: http://127.0.0.1:3
+    color: yellow
+";
+    const REST: u8 = 2;
+
+    fn get_target_shares(arbiter: &Arbiter) -> Vec<f64> {
+        [HIGH, LOW, REST]
+            .iter()
+            .map(|index| get_target_mining_share(&*arbiter.archon, &crate::get_configured_chain_from_index(&*arbiter.archon, *index).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn chains_without_a_mining_share_split_the_rest() {
+        let (arbiter, _) = create_arbiter(FAIR_SHARE_CONFIG);
+        assert_eq!(get_target_shares(&arbiter), vec![0.5, 0.3, 0.2]);
+        let (arbiter, _) = create_arbiter(&*FAIR_SHARE_CONFIG.replace("miningShare: 30", "miningShare: 10"));
+        assert_eq!(get_target_shares(&arbiter), vec![0.5, 0.1, 0.4]);
+    }
+
+    #[test]
+    fn mining_shares_over_100_are_scaled_down() {
+        let config = FAIR_SHARE_CONFIG.replace("miningShare: 50", "miningShare: 150").replace("miningShare: 30", "miningShare: 50");
+        let (arbiter, _) = create_arbiter(&*config);
+        // nothing is left over for the chain without a share
+        assert_eq!(get_target_shares(&arbiter), vec![0.75, 0.25, 0.0]);
+    }
+
+    #[test]
+    fn mining_share_is_measured_in_scan_time_up_to_the_grace_period() {
+        let (mut arbiter, clock) = create_arbiter(FAIR_SHARE_CONFIG);
+        arbiter.handle_event(new_block(&arbiter, HIGH, 100), clock.now());
+        // a round left running for longer than the grace period is only credited with the grace period
+        arbiter.handle_event(new_block(&arbiter, LOW, 500), clock.advance(90));
+        assert_eq!(get_mining_share(&*arbiter.archon, HIGH), (1.0, 0.5));
+        arbiter.handle_event(new_block(&arbiter, HIGH, 101), clock.advance(10));
+        arbiter.handle_event(ArbiterEvent::GracePeriodExpired(LOW, 500), clock.advance(20));
+        assert_eq!(arbiter.get_chain_state(HIGH), ChainState::Mining(101));
+        assert_eq!(get_mining_share(&*arbiter.archon, HIGH), (0.5, 0.5));
+        assert_eq!(get_mining_share(&*arbiter.archon, LOW), (0.5, 0.3));
+        assert_eq!(get_mining_share(&*arbiter.archon, REST), (0.0, 0.2));
+    }
+
+    #[test]
+    fn fair_share_mines_the_chain_furthest_below_its_share() {
+        let (mut arbiter, clock) = create_arbiter(FAIR_SHARE_CONFIG);
+        arbiter.archon.state.chain_mining_times.lock().unwrap().insert(HIGH, 60000);
+        arbiter.archon.state.chain_mining_times.lock().unwrap().insert(REST, 10000);
+        arbiter.handle_event(new_block(&arbiter, HIGH, 100), clock.now());
+        arbiter.handle_event(new_block(&arbiter, REST, 700), clock.advance(5));
+        arbiter.handle_event(new_block(&arbiter, LOW, 500), clock.advance(5));
+        // higher priority doesn't matter, High is over its share and Low is the furthest under its share
+        arbiter.handle_event(new_block(&arbiter, HIGH, 101), clock.advance(5));
+        assert_eq!(get_current_chain_index(&*arbiter.archon), HIGH);
+        assert_eq!(arbiter.get_chain_state(HIGH), ChainState::Queued(101));
+        arbiter.handle_event(ArbiterEvent::GracePeriodExpired(HIGH, 100), clock.advance(15));
+        assert_eq!(arbiter.get_chain_state(LOW), ChainState::Mining(500));
+        assert_eq!(arbiter.get_chain_state(HIGH), ChainState::Queued(101));
+        assert_eq!(arbiter.get_chain_state(REST), ChainState::Queued(700));
+    }
+
+    fn create_tied_arbiter(tie_break_policy: &str) -> (Arbiter, FakeClock) {
+        let config = CONFIG.replace("priority: 1", "priority: 0") + &*format!("tieBreakPolicy: {}\n", tie_break_policy);
+        create_arbiter(&*config)
+    }
+
+    fn set_base_target(arbiter: &Arbiter, index: u8, base_target: u32, now: DateTime<Local>) {
+        let mut mining_info = create_polling_result(arbiter, index, 100, format!("{:064}", 100)).mining_info;
+        mining_info.base_target = base_target;
+        arbiter.archon.state.chain_mining_infos.lock().unwrap().insert(index, (mining_info, now));
+    }
+
+    #[test]
+    fn tie_break_policy_is_parsed_case_insensitively() {
+        assert_eq!(TieBreakPolicy::parse("roundRobin"), Some(TieBreakPolicy::RoundRobin));
+        assert_eq!(TieBreakPolicy::parse("FIFO"), Some(TieBreakPolicy::Fifo));
+        assert_eq!(TieBreakPolicy::parse("expectedValue"), Some(TieBreakPolicy::ExpectedValue));
+        assert_eq!(TieBreakPolicy::parse("round robin"), None);
+        assert_eq!(TieBreakPolicy::parse(""), None);
+    }
+
+    #[test]
+    fn tie_break_policy_defaults_to_round_robin() {
+        let (arbiter, _) = create_arbiter(CONFIG);
+        assert_eq!(get_tie_break_policy(&*arbiter.archon), TieBreakPolicy::RoundRobin);
+        let (arbiter, _) = create_tied_arbiter("random");
+        assert_eq!(get_tie_break_policy(&*arbiter.archon), TieBreakPolicy::RoundRobin);
+        let (arbiter, _) = create_tied_arbiter("fifo");
+        assert_eq!(get_tie_break_policy(&*arbiter.archon), TieBreakPolicy::Fifo);
+    }
+
+    #[test]
+    fn fifo_picks_the_block_waiting_the_longest() {
+        let (arbiter, clock) = create_tied_arbiter("fifo");
+        let now = clock.now();
+        let tier = [(HIGH, now - chrono::Duration::seconds(5)), (LOW, now - chrono::Duration::seconds(10))];
+        assert_eq!(break_priority_tie(&*arbiter.archon, &tier, now), LOW);
+        let tier = [(HIGH, now - chrono::Duration::seconds(10)), (LOW, now - chrono::Duration::seconds(5))];
+        assert_eq!(break_priority_tie(&*arbiter.archon, &tier, now), HIGH);
+    }
+
+    #[test]
+    fn round_robin_picks_the_chain_started_least_recently() {
+        let (arbiter, clock) = create_tied_arbiter("roundRobin");
+        let now = clock.now();
+        let tier = [(HIGH, now), (LOW, now)];
+        // a chain which was never started goes first
+        arbiter.archon.state.chain_queue_status.lock().unwrap().insert(HIGH, (100, now - chrono::Duration::seconds(60)));
+        assert_eq!(break_priority_tie(&*arbiter.archon, &tier, now), LOW);
+        arbiter.archon.state.chain_queue_status.lock().unwrap().insert(LOW, (500, now - chrono::Duration::seconds(30)));
+        assert_eq!(break_priority_tie(&*arbiter.archon, &tier, now), HIGH);
+        arbiter.archon.state.chain_queue_status.lock().unwrap().insert(HIGH, (101, now - chrono::Duration::seconds(10)));
+        assert_eq!(break_priority_tie(&*arbiter.archon, &tier, now), LOW);
+    }
+
+    #[test]
+    fn expected_value_weighs_block_reward_against_difficulty() {
+        let (arbiter, clock) = create_tied_arbiter("expectedValue");
+        let now = clock.now();
+        let tier = [(HIGH, now), (LOW, now)];
+        // without a base target there is nothing to compare, so the first chain is kept
+        assert_eq!(break_priority_tie(&*arbiter.archon, &tier, now), HIGH);
+        // the same reward on a chain with half the difficulty
+        set_base_target(&arbiter, HIGH, 70000, now);
+        set_base_target(&arbiter, LOW, 140000, now);
+        assert_eq!(break_priority_tie(&*arbiter.archon, &tier, now), LOW);
+        // more than double the reward outweighs double the difficulty
+        let config = CONFIG.replace("priority: 1", "priority: 0").replace("color: cyan", "color: cyan\n    blockReward: 3") + "tieBreakPolicy: expectedValue\n";
+        let (arbiter, _) = create_arbiter(&*config);
+        set_base_target(&arbiter, HIGH, 70000, now);
+        set_base_target(&arbiter, LOW, 140000, now);
+        assert_eq!(break_priority_tie(&*arbiter.archon, &tier, now), HIGH);
+    }
+}
@@ -0,0 +1,257 @@
+use actix_web::http::{header, StatusCode};
+use actix_web::HttpRequest;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use crate::context::Archon;
+
+/// What an API token is allowed to do. Admin tokens can also do everything read tokens can.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum ApiScope {
+    Read,
+    Admin,
+}
+
+impl ApiScope {
+    pub fn parse(scope: &str) -> Option<ApiScope> {
+        match scope.to_lowercase().as_str() {
+            "read" => Some(ApiScope::Read),
+            "admin" => Some(ApiScope::Admin),
+            _ => None,
+        }
+    }
+
+    pub fn describe(&self) -> &'static str {
+        match self {
+            ApiScope::Read => "read",
+            ApiScope::Admin => "admin",
+        }
+    }
+}
+
+/// Checks the request came from this machine, going by the connection's real address rather than any headers.
+pub fn is_loopback(req: &HttpRequest<Arc<Archon>>) -> bool {
+    match req.peer_addr() {
+        Some(addr) => is_loopback_address(addr.ip()),
+        None => false,
+    }
+}
+
+fn is_loopback_address(address: IpAddr) -> bool {
+    match address {
+        IpAddr::V4(ip) => ip.is_loopback(),
+        // IPv4 clients of an IPv6 socket show up as ::ffff:127.0.0.1
+        IpAddr::V6(ip) => {
+            let segments = ip.segments();
+            ip.is_loopback() || (segments[..6] == [0, 0, 0, 0, 0, 0xffff] && segments[6] >> 8 == 127)
+        }
+    }
+}
+
+// the token from an "Authorization: Bearer" header, or a token query string parameter for clients which can't set headers
+fn get_request_token(req: &HttpRequest<Arc<Archon>>) -> Option<String> {
+    match req.headers().get(header::AUTHORIZATION).and_then(|value| value.to_str().ok()) {
+        Some(value) if value.len() > 7 && value[..7].eq_ignore_ascii_case("bearer ") => {
+            return Some(String::from(value[7..].trim()));
+        }
+        _ => {}
+    };
+    req.query().get("token").cloned()
+}
+
+// compares every byte so the time taken doesn't give away how much of a guess was right
+pub fn tokens_match(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |difference, (x, y)| difference | (x ^ y)) == 0
+}
+
+/// Describes who made a request for the audit log: the name of the API token it used, if any, and its address.
+pub fn get_requester(req: &HttpRequest<Arc<Archon>>) -> String {
+    let remote_address = req.connection_info().remote().unwrap_or("Unknown").to_string();
+    let token_name = match (&req.state().conf.api_tokens, get_request_token(req)) {
+        (Some(api_tokens), Some(request_token)) => api_tokens
+            .iter()
+            .find(|api_token| tokens_match(&*api_token.token, &*request_token))
+            .map(|api_token| api_token.name.clone().unwrap_or(String::from("Unnamed token"))),
+        _ => None,
+    };
+    match token_name {
+        Some(token_name) => format!("{} [{}]", token_name, remote_address),
+        None => format!("[{}]", remote_address),
+    }
+}
+
+/// Checks the request is allowed to use an API route which needs the given scope.
+/// Returns the HTTP status and reason to send back if it isn't.
+pub fn authorize(req: &HttpRequest<Arc<Archon>>, required_scope: ApiScope) -> Result<(), (StatusCode, String)> {
+    let remote_address = req.connection_info().remote().unwrap_or("Unknown").to_string();
+    check_access(req.state(), is_loopback(req), get_request_token(req), required_scope, &*remote_address, req.path())
+}
+
+// the checks behind authorize, given whether the request came from this machine and the token it sent
+fn check_access(
+    archon: &Archon,
+    loopback: bool,
+    request_token: Option<String>,
+    required_scope: ApiScope,
+    remote_address: &str,
+    path: &str,
+) -> Result<(), (StatusCode, String)> {
+    if archon.conf.api_loopback_only.unwrap_or_default() && !loopback {
+        warn!("API - Refused request from [{}] to {}: Not from the host machine", remote_address, path);
+        return Err((StatusCode::FORBIDDEN, String::from("This request can only be made from the host machine.")));
+    }
+    let api_tokens = match &archon.conf.api_tokens {
+        Some(api_tokens) if api_tokens.len() > 0 => api_tokens,
+        _ => {
+            // without tokens, admin requests are only trusted from the host machine
+            if required_scope == ApiScope::Admin && !loopback {
+                warn!("API - Refused request from [{}] to {}: Admin requests without tokens are only allowed from the host machine", remote_address, path);
+                return Err((StatusCode::FORBIDDEN, String::from("This request can only be made from the host machine.")));
+            }
+            return Ok(());
+        }
+    };
+    let request_token = match request_token {
+        Some(request_token) => request_token,
+        None => return Err((StatusCode::UNAUTHORIZED, String::from("An API token is required."))),
+    };
+    match api_tokens.iter().find(|api_token| tokens_match(&*api_token.token, &*request_token)) {
+        Some(api_token) => {
+            let scope = ApiScope::parse(&*api_token.scope.clone().unwrap_or(String::from("read"))).unwrap_or(ApiScope::Read);
+            if scope >= required_scope {
+                Ok(())
+            } else {
+                warn!("API - Refused request from [{}] to {}: Token \"{}\" doesn't have the {} scope", remote_address, path, api_token.name.clone().unwrap_or_default(), required_scope.describe());
+                Err((StatusCode::FORBIDDEN, format!("This API token doesn't have the {} scope.", required_scope.describe())))
+            }
+        }
+        None => {
+            warn!("API - Refused request from [{}] to {}: Invalid token", remote_address, path);
+            Err((StatusCode::UNAUTHORIZED, String::from("The API token is not valid.")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    const CONFIG: &str = "
+gracePeriod: 30
+webServerBindAddress: 127.0.0.1
+webServerPort: 1337
+apiTokens:
+  - name: dashboard
+    token: read-token
+    scope: read
+  - name: ops
+    token: admin-token
+    scope: admin
+  - name: unscoped
+    token: unscoped-token
+";
+    const NO_TOKENS_CONFIG: &str = "gracePeriod: 30\nwebServerBindAddress: 127.0.0.1\nwebServerPort: 1337\n";
+
+    fn create_archon(config: &str) -> Archon {
+        Archon::new(serde_yaml::from_str(config).unwrap())
+    }
+
+    fn check(archon: &Archon, loopback: bool, token: Option<&str>, required_scope: ApiScope) -> Result<(), StatusCode> {
+        check_access(archon, loopback, token.map(String::from), required_scope, "test", "/api/v1/test").map_err(|(status, _)| status)
+    }
+
+    #[test]
+    fn matches_tokens() {
+        assert!(tokens_match("admin-token", "admin-token"));
+        assert!(!tokens_match("admin-token", "admin-tokem"));
+        assert!(!tokens_match("admin-token", "admin-token2"));
+        assert!(!tokens_match("admin-token", ""));
+    }
+
+    #[test]
+    fn recognises_loopback_addresses() {
+        assert!(is_loopback_address("127.0.0.1".parse().unwrap()));
+        assert!(is_loopback_address("127.1.2.3".parse().unwrap()));
+        assert!(is_loopback_address("::1".parse().unwrap()));
+        assert!(is_loopback_address("::ffff:127.0.0.1".parse().unwrap()));
+        assert!(!is_loopback_address("192.168.1.1".parse().unwrap()));
+        assert!(!is_loopback_address("::ffff:192.168.1.1".parse().unwrap()));
+        assert!(!is_loopback_address("fe80::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn read_token_can_only_read() {
+        let archon = create_archon(CONFIG);
+        assert_eq!(check(&archon, false, Some("read-token"), ApiScope::Read), Ok(()));
+        assert_eq!(check(&archon, false, Some("read-token"), ApiScope::Admin), Err(StatusCode::FORBIDDEN));
+        // a token without a scope is a read token
+        assert_eq!(check(&archon, false, Some("unscoped-token"), ApiScope::Read), Ok(()));
+        assert_eq!(check(&archon, true, Some("unscoped-token"), ApiScope::Admin), Err(StatusCode::FORBIDDEN));
+    }
+
+    #[test]
+    fn admin_token_can_read_and_administer() {
+        let archon = create_archon(CONFIG);
+        assert_eq!(check(&archon, false, Some("admin-token"), ApiScope::Read), Ok(()));
+        assert_eq!(check(&archon, false, Some("admin-token"), ApiScope::Admin), Ok(()));
+    }
+
+    #[test]
+    fn missing_or_unknown_tokens_are_refused() {
+        let archon = create_archon(CONFIG);
+        // once tokens are configured, even the host machine needs one
+        assert_eq!(check(&archon, true, None, ApiScope::Read), Err(StatusCode::UNAUTHORIZED));
+        assert_eq!(check(&archon, false, Some("admin-tokem"), ApiScope::Read), Err(StatusCode::UNAUTHORIZED));
+    }
+
+    #[test]
+    fn without_tokens_admin_requests_are_loopback_only() {
+        let archon = create_archon(NO_TOKENS_CONFIG);
+        assert_eq!(check(&archon, false, None, ApiScope::Read), Ok(()));
+        assert_eq!(check(&archon, false, None, ApiScope::Admin), Err(StatusCode::FORBIDDEN));
+        assert_eq!(check(&archon, true, None, ApiScope::Admin), Ok(()));
+    }
+
+    #[test]
+    fn loopback_only_refuses_other_machines() {
+        let archon = create_archon(&*format!("{}apiLoopbackOnly: true\n", CONFIG));
+        assert_eq!(check(&archon, false, Some("admin-token"), ApiScope::Read), Err(StatusCode::FORBIDDEN));
+        assert_eq!(check(&archon, true, Some("admin-token"), ApiScope::Admin), Ok(()));
+        assert_eq!(check(&archon, true, Some("read-token"), ApiScope::Admin), Err(StatusCode::FORBIDDEN));
+        let archon = create_archon(&*format!("{}apiLoopbackOnly: true\n", NO_TOKENS_CONFIG));
+        assert_eq!(check(&archon, false, None, ApiScope::Read), Err(StatusCode::FORBIDDEN));
+        assert_eq!(check(&archon, true, None, ApiScope::Admin), Ok(()));
+    }
+
+    #[test]
+    fn token_is_taken_from_the_header_or_the_query_string() {
+        let archon = Arc::new(create_archon(CONFIG));
+        let req = TestRequest::with_state(archon.clone()).header(header::AUTHORIZATION, "Bearer admin-token").finish();
+        assert_eq!(get_request_token(&req), Some(String::from("admin-token")));
+        let req = TestRequest::with_state(archon.clone()).uri("/api/v1/status?token=read-token").finish();
+        assert_eq!(get_request_token(&req), Some(String::from("read-token")));
+        // the header wins over the query string
+        let req = TestRequest::with_state(archon.clone())
+            .uri("/api/v1/status?token=read-token")
+            .header(header::AUTHORIZATION, "bearer admin-token")
+            .finish();
+        assert_eq!(get_request_token(&req), Some(String::from("admin-token")));
+        let req = TestRequest::with_state(archon.clone()).header(header::AUTHORIZATION, "Basic YWRtaW4=").finish();
+        assert_eq!(get_request_token(&req), None);
+    }
+
+    #[test]
+    fn query_string_token_authorizes_requests_from_other_machines() {
+        let archon = Arc::new(create_archon(CONFIG));
+        // test requests have no peer address, so they count as coming from another machine
+        let req = TestRequest::with_state(archon.clone()).uri("/api/v1/admin/chains/0/pause?token=admin-token").finish();
+        assert!(authorize(&req, ApiScope::Admin).is_ok());
+        let req = TestRequest::with_state(archon.clone()).uri("/api/v1/admin/chains/0/pause?token=read-token").finish();
+        assert_eq!(authorize(&req, ApiScope::Admin).map_err(|(status, _)| status), Err(StatusCode::FORBIDDEN));
+        let archon = Arc::new(create_archon(NO_TOKENS_CONFIG));
+        let req = TestRequest::with_state(archon).uri("/api/v1/admin/chains/0/pause").finish();
+        assert_eq!(authorize(&req, ApiScope::Admin).map_err(|(status, _)| status), Err(StatusCode::FORBIDDEN));
+    }
+}
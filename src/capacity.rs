@@ -0,0 +1,230 @@
+use chrono::{DateTime, Local};
+use colored::Colorize;
+
+use crate::config::PocChain;
+use crate::context::Archon;
+use crate::miners;
+use crate::plots;
+
+// reported capacity further than this fraction away from the configured total plots size is warned about
+const DISCREPANCY_WARNING_RATIO: f64 = 0.2;
+// how long before a chain's capacity discrepancy is warned about again, in seconds
+const DISCREPANCY_WARNING_INTERVAL: i64 = 3600;
+
+/// Where the plot capacity used for a chain's dynamic deadlines comes from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CapacitySource {
+    // the totalPlotsSizeIn* options
+    Config,
+    // the X-Capacity headers sent by connected miners
+    Miners,
+    // the plot files found in the plotDirectories
+    Plots,
+}
+
+impl CapacitySource {
+    pub fn parse(source: &str) -> Option<CapacitySource> {
+        match source.to_lowercase().as_str() {
+            "config" => Some(CapacitySource::Config),
+            "miners" => Some(CapacitySource::Miners),
+            "plots" => Some(CapacitySource::Plots),
+            _ => None,
+        }
+    }
+
+    pub fn describe(&self) -> &'static str {
+        match self {
+            CapacitySource::Config => "config",
+            CapacitySource::Miners => "miners",
+            CapacitySource::Plots => "plots",
+        }
+    }
+}
+
+/// The chain's plotCapacitySource if it has one, otherwise the global plotCapacitySource. Defaults to the config.
+pub fn get_capacity_source(archon: &Archon, chain: &PocChain) -> CapacitySource {
+    chain.plot_capacity_source
+        .as_ref()
+        .or(archon.conf.plot_capacity_source.as_ref())
+        .and_then(|source| CapacitySource::parse(&*source))
+        .unwrap_or(CapacitySource::Config)
+}
+
+/// The total capacity connected miners report in their X-Capacity headers, in TiB.
+/// Only miners which have been asking for the chain's blocks are counted, unless none of them have yet.
+pub fn get_reported_capacity(archon: &Archon, chain_index: u8, now: DateTime<Local>) -> Option<f64> {
+    let reporting_miners: Vec<miners::Miner> = miners::get_miners(archon)
+        .into_iter()
+        .filter(|miner| miner.is_connected(now) && miner.capacity.is_some())
+        .collect();
+    if reporting_miners.len() == 0 {
+        return None;
+    }
+    let chain_miners: Vec<&miners::Miner> = reporting_miners
+        .iter()
+        .filter(|miner| miner.rounds.iter().any(|round| round.chain_index == chain_index && round.active))
+        .collect();
+    let capacity_gibibytes: u64 = if chain_miners.len() > 0 {
+        chain_miners.iter().map(|miner| miner.capacity.unwrap_or_default()).sum()
+    } else {
+        reporting_miners.iter().map(|miner| miner.capacity.unwrap_or_default()).sum()
+    };
+    Some(capacity_gibibytes as f64 / 1024f64)
+}
+
+/// The plot capacity to calculate a chain's dynamic deadlines with, in TiB, and where it came from.
+/// Reported and scanned capacity fall back to the configured total plots size while there isn't any.
+pub fn get_plot_capacity(archon: &Archon, chain_index: u8) -> (f64, CapacitySource) {
+    let configured_capacity = super::get_total_plots_size_in_tebibytes(archon);
+    let chain = match super::get_chain_from_index(archon, chain_index) {
+        Some(chain) => chain,
+        None => return (configured_capacity, CapacitySource::Config),
+    };
+    match get_capacity_source(archon, &chain) {
+        CapacitySource::Miners => match get_reported_capacity(archon, chain_index, archon.now()) {
+            Some(reported_capacity) if reported_capacity > 0f64 => (reported_capacity, CapacitySource::Miners),
+            _ => (configured_capacity, CapacitySource::Config),
+        },
+        CapacitySource::Plots => match plots::get_scanned_capacity(archon) {
+            Some(scanned_capacity) if scanned_capacity > 0f64 => (scanned_capacity, CapacitySource::Plots),
+            _ => (configured_capacity, CapacitySource::Config),
+        },
+        CapacitySource::Config => (configured_capacity, CapacitySource::Config),
+    }
+}
+
+/// An account's own plot capacity in TiB, from numericIdToPlotsSizeInTebibytes, or else its plot files in the plot directories.
+/// Accounts without one share the farm's plot capacity.
+pub fn get_account_capacity(archon: &Archon, account_id: u64) -> Option<(f64, CapacitySource)> {
+    match archon.conf.numeric_id_to_plots_size_in_tebibytes.as_ref().and_then(|capacities| capacities.get(&account_id)) {
+        Some(configured_capacity) if *configured_capacity > 0f64 => return Some((*configured_capacity, CapacitySource::Config)),
+        _ => {}
+    };
+    match plots::get_scanned_account_capacity(archon, account_id) {
+        Some(scanned_capacity) if scanned_capacity > 0f64 => Some((scanned_capacity, CapacitySource::Plots)),
+        _ => None,
+    }
+}
+
+/// Warns when the capacity miners report for a chain using dynamic deadlines is far from the configured total plots size.
+/// Each chain is warned about at most once an hour.
+pub fn check_capacity_discrepancy(archon: &Archon, chain_index: u8, now: DateTime<Local>) {
+    let chain = match super::get_chain_from_index(archon, chain_index) {
+        Some(chain) if chain.use_dynamic_deadlines.unwrap_or_default() => chain,
+        _ => return,
+    };
+    let configured_capacity = super::get_total_plots_size_in_tebibytes(archon);
+    let reported_capacity = match get_reported_capacity(archon, chain_index, now) {
+        Some(reported_capacity) => reported_capacity,
+        None => return,
+    };
+    if configured_capacity <= 0f64 || (reported_capacity - configured_capacity).abs() / configured_capacity <= DISCREPANCY_WARNING_RATIO {
+        return;
+    }
+    let mut capacity_warnings = archon.state.capacity_warnings.lock().unwrap();
+    match capacity_warnings.get(&chain_index) {
+        Some(last_warned) if (now - *last_warned).num_seconds() < DISCREPANCY_WARNING_INTERVAL => return,
+        _ => capacity_warnings.insert(chain_index, now),
+    };
+    drop(capacity_warnings);
+    let used = match get_capacity_source(archon, &chain) {
+        CapacitySource::Miners => "the reported capacity",
+        CapacitySource::Plots => "the scanned plot files",
+        CapacitySource::Config => "the configured size",
+    };
+    warn!("CAPACITY - {} - Miners report {:.2} TiB, but the total plots size is configured as {:.2} TiB", &*chain.name, reported_capacity, configured_capacity);
+    println!("  {} {} {}",
+        super::get_time(archon).white(),
+        format!("{}", &*chain.name).color(super::get_color(archon, &*chain.color)),
+        format!("Miners report {:.2} TiB of plots, but the total plots size is configured as {:.2} TiB. Dynamic deadlines are using {}.", reported_capacity, configured_capacity, used).yellow()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rounds;
+    use crate::upstream::MiningInfo;
+    use chrono::{Duration, TimeZone};
+
+    const CONFIG: &str = "
+gracePeriod: 30
+webServerBindAddress: 127.0.0.1
+webServerPort: 1337
+pocChains:
+  - name: Alpha
+    priority: 0
+    url: http://127.0.0.1:1
+    color: cyan
+  - name: Beta
+    priority: 1
+    url: http://127.0.0.1:2
+    color: green
+";
+    const ALPHA: u8 = 0;
+    const BETA: u8 = 1;
+
+    fn create_archon() -> Archon {
+        Archon::new(serde_yaml::from_str(CONFIG).unwrap())
+    }
+
+    fn get_time() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2020, 1, 1, 12, 0, 0).unwrap()
+    }
+
+    fn start_round(archon: &Archon, chain_index: u8, height: u32) {
+        let mining_info = MiningInfo {
+            height,
+            ..MiningInfo::empty()
+        };
+        rounds::record_round_started(archon, chain_index, &mining_info, get_time());
+    }
+
+    // capacity in GiB, as sent in the X-Capacity header
+    fn poll(archon: &Archon, remote_addr: &str, capacity: Option<u64>, now: DateTime<Local>) {
+        miners::record_mining_info_request(archon, remote_addr, "scavenger", capacity, now);
+    }
+
+    #[test]
+    fn nothing_is_reported_without_miners_sending_their_capacity() {
+        let archon = create_archon();
+        assert_eq!(get_reported_capacity(&archon, ALPHA, get_time()), None);
+        start_round(&archon, ALPHA, 100);
+        poll(&archon, "192.168.1.10:50000", None, get_time());
+        assert_eq!(get_reported_capacity(&archon, ALPHA, get_time()), None);
+    }
+
+    #[test]
+    fn only_miners_asking_for_the_chain_are_counted() {
+        let archon = create_archon();
+        start_round(&archon, ALPHA, 100);
+        poll(&archon, "192.168.1.10:50000", Some(2048), get_time());
+        poll(&archon, "192.168.1.11:50000", Some(1024), get_time());
+        start_round(&archon, BETA, 500);
+        poll(&archon, "192.168.1.11:50000", Some(1024), get_time());
+        poll(&archon, "192.168.1.12:50000", Some(512), get_time());
+        assert_eq!(get_reported_capacity(&archon, ALPHA, get_time()), Some(3.0));
+        assert_eq!(get_reported_capacity(&archon, BETA, get_time()), Some(1.5));
+    }
+
+    #[test]
+    fn every_miner_is_counted_until_one_asks_for_the_chain() {
+        let archon = create_archon();
+        start_round(&archon, ALPHA, 100);
+        poll(&archon, "192.168.1.10:50000", Some(2048), get_time());
+        poll(&archon, "192.168.1.11:50000", Some(1024), get_time());
+        poll(&archon, "192.168.1.12:50000", None, get_time());
+        assert_eq!(get_reported_capacity(&archon, BETA, get_time()), Some(3.0));
+    }
+
+    #[test]
+    fn disconnected_miners_are_not_counted() {
+        let archon = create_archon();
+        start_round(&archon, ALPHA, 100);
+        poll(&archon, "192.168.1.10:50000", Some(2048), get_time());
+        poll(&archon, "192.168.1.11:50000", Some(1024), get_time() + Duration::seconds(200));
+        assert_eq!(get_reported_capacity(&archon, ALPHA, get_time() + Duration::seconds(250)), Some(3.0));
+        assert_eq!(get_reported_capacity(&archon, ALPHA, get_time() + Duration::seconds(350)), Some(1.0));
+        assert_eq!(get_reported_capacity(&archon, ALPHA, get_time() + Duration::seconds(550)), None);
+    }
+}
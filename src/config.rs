@@ -1,3 +1,4 @@
+use chrono::{DateTime, Datelike, Local, Timelike};
 use colored::Colorize;
 use std::collections::HashMap;
 use std::fs::File;
@@ -57,6 +58,135 @@ pub struct PocChain {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub requeue_interrupted_blocks: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mining_share: Option<f64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub protect_deadline: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub early_release_deadline: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_reward: Option<f64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<Vec<ChainSchedule>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_block_interval: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_failed_submissions: Option<u8>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plot_capacity_source: Option<String>,
+}
+
+/// A day/time window during which a chain is enabled/disabled, or has its priority or target deadline overridden.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainSchedule {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub days: Option<String>,
+
+    pub start: String,
+    pub end: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<u8>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_deadline: Option<u64>,
+}
+
+impl ChainSchedule {
+    pub fn validate(&self) -> Result<(), ArchonError> {
+        self.parse().map(|_| ())
+    }
+
+    /// Checks if this window covers the given time. Windows ending before they start wrap past midnight,
+    /// in which case the part after midnight belongs to the day the window started on.
+    pub fn is_active(&self, now: DateTime<Local>) -> bool {
+        let (start, end, days) = match self.parse() {
+            Ok(window) => window,
+            Err(_) => return false,
+        };
+        let minute_of_day = now.hour() * 60 + now.minute();
+        let today = now.weekday().num_days_from_monday() as usize;
+        let yesterday = (today + 6) % 7;
+        if start <= end {
+            return days[today] && minute_of_day >= start && minute_of_day < end;
+        }
+        (days[today] && minute_of_day >= start) || (days[yesterday] && minute_of_day < end)
+    }
+
+    pub fn describe(&self) -> String {
+        format!("{} {}-{}", self.days.clone().unwrap_or(String::from("*")), self.start, self.end)
+    }
+
+    // => (start minute, end minute, days), windows which could never be active are rejected
+    fn parse(&self) -> Result<(u32, u32, [bool; 7]), ArchonError> {
+        let start = ChainSchedule::parse_time(&*self.start)?;
+        let end = ChainSchedule::parse_time(&*self.end)?;
+        if start == 24 * 60 {
+            return Err(ArchonError::new("A schedule window can't start at 24:00, use 00:00 instead"));
+        }
+        if start == end {
+            return Err(ArchonError::new("A schedule window can't start and end at the same time, use 00:00-24:00 for the whole day"));
+        }
+        let days = ChainSchedule::parse_days(self.days.clone().unwrap_or(String::from("*")).as_str())?;
+        Ok((start, end, days))
+    }
+
+    // "HH:MM" => minutes since midnight, "24:00" is allowed as the end of the day
+    fn parse_time(time: &str) -> Result<u32, ArchonError> {
+        let parts: Vec<&str> = time.trim().split(':').collect();
+        if parts.len() == 2 {
+            match (parts[0].parse::<u32>(), parts[1].parse::<u32>()) {
+                (Ok(hours), Ok(minutes)) if (hours < 24 && minutes < 60) || (hours == 24 && minutes == 0) => {
+                    return Ok(hours * 60 + minutes);
+                }
+                _ => {}
+            };
+        }
+        Err(ArchonError::new(&format!("Invalid schedule time \"{}\", expected HH:MM (eg: 07:30)", time)))
+    }
+
+    // "*" | "mon-fri" | "sat,sun" | "mon,wed-fri" => which days of the week (monday first) are included
+    fn parse_days(days: &str) -> Result<[bool; 7], ArchonError> {
+        let names = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+        let mut included = [false; 7];
+        let days = days.trim().to_lowercase();
+        if days == "*" || days == "daily" {
+            return Ok([true; 7]);
+        }
+        for part in days.split(',') {
+            let range: Vec<&str> = part.trim().split('-').collect();
+            let first = range.first().and_then(|day| names.iter().position(|name| day.trim().starts_with(name)));
+            let last = range.last().and_then(|day| names.iter().position(|name| day.trim().starts_with(name)));
+            match (first, last, range.len()) {
+                (Some(first), Some(last), 1..=2) => {
+                    let mut day = first;
+                    loop {
+                        included[day] = true;
+                        if day == last {
+                            break;
+                        }
+                        day = (day + 1) % 7;
+                    }
+                }
+                _ => {
+                    return Err(ArchonError::new(&format!("Invalid schedule days \"{}\", expected eg: mon-fri or sat,sun", part)));
+                }
+            };
+        }
+        Ok(included)
+    }
 }
 
 impl PartialEq for PocChain {
@@ -105,6 +235,18 @@ pub struct Config {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub interrupt_lower_priority_blocks: Option<bool>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fair_share_mode: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tie_break_policy: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub look_ahead_probability: Option<f64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub look_ahead_action: Option<String>,
+
     pub web_server_bind_address: String,
     pub web_server_port: u16,
 
@@ -129,6 +271,15 @@ pub struct Config {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub total_plots_size_in_gigabytes: Option<f64>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plot_capacity_source: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plot_directories: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub numeric_id_to_plots_size_in_tebibytes: Option<HashMap<u64, f64>>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub show_human_readable_deadlines: Option<bool>,
 
@@ -146,6 +297,62 @@ pub struct Config {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub show_miner_addresses: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_tokens: Option<Vec<ApiToken>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_loopback_only: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls_certificate_file: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls_private_key_file: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls_client_ca_file: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub miner_groups: Option<Vec<MinerGroup>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub miner_submission_rate_limit: Option<u32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub miner_watchdog_rounds: Option<u32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub miner_watchdog_poll_timeout: Option<u64>,
+}
+
+/// A token which grants access to the API.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiToken {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    pub token: String,
+
+    // read or admin, defaults to read
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+}
+
+/// A group of miners allowed to use /burst, by address and/or a shared secret.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MinerGroup {
+    pub name: String,
+
+    // IP addresses or CIDR ranges, any address is allowed if there are none
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub addresses: Option<Vec<String>>,
+
+    // sent by the group's miners in an X-Archon-Secret header
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
 }
 
 impl Config {
@@ -175,6 +382,18 @@ priorityMode: true
 #  FALSE: Blocks will not be interrupted unless outdated by a new block from the same chain.
 interruptLowerPriorityBlocks: true
 
+# Tie Break Policy: Optional. Default: roundRobin. Only used in priority mode, when multiple chains share a priority.
+#   roundRobin: Take turns, the chain which was started least recently goes next.
+#         fifo: The block which has been queued the longest goes next.
+#   expectedValue: The block with the best odds of winning (weighted by the chain's blockReward) goes next.
+tieBreakPolicy: roundRobin
+
+# Fair Share Mode: Optional. Default: False.
+#   TRUE: Blocks will be started so that the time spent scanning each chain converges on each chain's miningShare.
+#         Blocks are never interrupted in this mode, the grace period is always respected. Overrides priorityMode.
+#  FALSE: Blocks will be queued according to priorityMode.
+fairShareMode: false
+
 # Web Server Bind Address: Which interface to listen for requests from your miners and/or web requests.
 
 # Localhost only - will only listen for requests made from the same machine
@@ -205,6 +424,25 @@ totalPlotsSizeInTebibytes: 10    # 10 TiB
 #totalPlotsSizeInGibibytes: 1024  # 1024 GiB (1 TiB)
 #totalPlotsSizeInGigabytes: 8000  # 8000 GB (7.27 TiB)
 
+# Plot Capacity Source: Optional. Default: config. Where dynamic deadlines get your plot capacity from.
+#   config: The total plots size above.
+#   miners: The capacity your miners report in their X-Capacity header, the total plots size above is used until they do.
+#   plots: The size of the plot files found in the plot directories below, the total plots size above is used until some are found.
+#plotCapacitySource: config
+
+# Plot Directories: Optional. Directories on this machine holding your plot files, which Archon reads the sizes and account IDs of.
+#   They are scanned again every 10 minutes. Run "archon plots" to print a report of what's in them.
+#plotDirectories:
+#  - "D:\\Plots"
+#  - "/mnt/plots1"
+
+# Numeric ID To Plots Size In Tebibytes: Optional. Each account's own plots size, for mining several accounts with one farm.
+#   Dynamic deadlines for these accounts are calculated from their own plots size instead of the total above.
+#   Accounts found in the plot directories use the size of their plot files, unless they're set here.
+#numericIdToPlotsSizeInTebibytes:
+#  12345678901234567890: 24.5
+#  10987654321098765432: 8
+
 # Show Human Readable Deadlines: Optional. If true, values displayed in seconds will be appended with a human readable value, for example: 3345951 (1m 8d 17:25:51)
 showHumanReadableDeadlines: true
 
@@ -228,6 +466,41 @@ loggingLevel: info
 #   Shows the IP Address of miner's which submit deadlines.
 showMinerAddresses: false
 
+# API Tokens: Optional. If none are set, the API can be read by anyone, and the config can only be read from this machine.
+#   Each token has a scope of either read (the default) or admin. Send it as an "Authorization: Bearer <token>" header, or as ?token=<token>
+#apiTokens:
+#  - name: grafana
+#    token: "change-me-to-something-long-and-random"
+#  - name: me
+#    token: "change-me-to-something-else-long-and-random"
+#    scope: admin
+
+# API Loopback Only: Optional. Default: false. Only answer API requests made from this machine. Miners can still connect as normal.
+#apiLoopbackOnly: false
+
+# TLS: Optional. Set both a certificate (PEM, including any intermediates) and its private key to serve https instead of http.
+#   The files are checked every 10 seconds, renewed certificates are picked up without a restart.
+#tlsCertificateFile: archon.crt
+#tlsPrivateKeyFile: archon.key
+# TLS Client CA File: Optional. Only miners with a client certificate signed by this CA can connect. The dashboard and API don't need one.
+#tlsClientCaFile: miners-ca.crt
+
+# Miner Groups: Optional. If none are set, any miner which can reach Archon can use it.
+#   Miners must match a group's addresses (IPs or CIDR ranges, any address if none), and send its secret in an X-Archon-Secret header if it has one.
+#minerGroups:
+#  - name: lan
+#    addresses: ["192.168.1.0/24"]
+#  - name: remote rigs
+#    secret: "change-me-to-something-long-and-random"
+
+# Miner Submission Rate Limit: Optional. Default: no limit. The most deadlines a single address can submit per minute.
+#minerSubmissionRateLimit: 600
+
+# Miner Watchdog: Optional. Raise an alert when a miner or account doesn't submit deadlines for this many rounds of a chain it normally mines,
+#   or when a miner doesn't ask for mining info for this many seconds. Default: 5 rounds and 120 seconds. Set either to 0 to disable it.
+#minerWatchdogRounds: 5
+#minerWatchdogPollTimeout: 120
+
 ######################################################################################################################
 # Define PoC Chains to mine here, Archon will exit if there are no chains configured/enabled, you need at least one! #
 ######################################################################################################################
@@ -287,3 +560,86 @@ pocChains:
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn create_schedule(days: Option<&str>, start: &str, end: &str) -> ChainSchedule {
+        ChainSchedule {
+            days: days.map(String::from),
+            start: String::from(start),
+            end: String::from(end),
+            enabled: None,
+            priority: None,
+            target_deadline: None,
+        }
+    }
+
+    // 2020-01-06 is a Monday
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2020, 1, 6 + day, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn parses_every_day() {
+        assert_eq!(ChainSchedule::parse_days("*").unwrap(), [true; 7]);
+        assert_eq!(ChainSchedule::parse_days("Daily").unwrap(), [true; 7]);
+    }
+
+    #[test]
+    fn parses_day_lists_and_ranges() {
+        assert_eq!(ChainSchedule::parse_days("mon-fri").unwrap(), [true, true, true, true, true, false, false]);
+        assert_eq!(ChainSchedule::parse_days("sat, sun").unwrap(), [false, false, false, false, false, true, true]);
+        assert_eq!(ChainSchedule::parse_days("mon,wed-fri").unwrap(), [true, false, true, true, true, false, false]);
+        assert_eq!(ChainSchedule::parse_days("Tuesday").unwrap(), [false, true, false, false, false, false, false]);
+    }
+
+    #[test]
+    fn parses_day_ranges_which_wrap_past_sunday() {
+        assert_eq!(ChainSchedule::parse_days("sat-mon").unwrap(), [true, false, false, false, false, true, true]);
+        assert_eq!(ChainSchedule::parse_days("fri-tue").unwrap(), [true, true, false, false, true, true, true]);
+    }
+
+    #[test]
+    fn rejects_invalid_days() {
+        assert!(ChainSchedule::parse_days("").is_err());
+        assert!(ChainSchedule::parse_days("weekdays").is_err());
+        assert!(ChainSchedule::parse_days("mon-wed-fri").is_err());
+        assert!(ChainSchedule::parse_days("mon,,fri").is_err());
+    }
+
+    #[test]
+    fn rejects_windows_which_are_never_active() {
+        assert!(create_schedule(None, "07:00", "23:00").validate().is_ok());
+        assert!(create_schedule(None, "00:00", "24:00").validate().is_ok());
+        assert!(create_schedule(None, "00:00", "00:00").validate().is_err());
+        assert!(create_schedule(None, "24:00", "07:00").validate().is_err());
+        assert!(create_schedule(None, "7:60", "23:00").validate().is_err());
+        assert!(create_schedule(Some("someday"), "07:00", "23:00").validate().is_err());
+    }
+
+    #[test]
+    fn window_is_active_within_its_times_and_days() {
+        let schedule = create_schedule(Some("mon-fri"), "07:00", "23:00");
+        assert!(!schedule.is_active(at(0, 6, 59)));
+        assert!(schedule.is_active(at(0, 7, 0)));
+        assert!(schedule.is_active(at(4, 22, 59)));
+        assert!(!schedule.is_active(at(4, 23, 0)));
+        assert!(!schedule.is_active(at(5, 12, 0)));
+        assert!(create_schedule(None, "00:00", "24:00").is_active(at(6, 23, 59)));
+    }
+
+    #[test]
+    fn window_across_midnight_belongs_to_the_day_it_started() {
+        let schedule = create_schedule(Some("fri"), "23:00", "07:00");
+        assert!(!schedule.is_active(at(4, 6, 0)));
+        assert!(schedule.is_active(at(4, 23, 0)));
+        assert!(schedule.is_active(at(5, 6, 59)));
+        assert!(!schedule.is_active(at(5, 7, 0)));
+        assert!(!schedule.is_active(at(5, 23, 30)));
+        // a sunday night window runs into monday morning
+        assert!(create_schedule(Some("sun"), "22:00", "02:00").is_active(at(7, 1, 0)));
+    }
+}
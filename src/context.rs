@@ -0,0 +1,191 @@
+use chrono::{DateTime, Local};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::mpsc;
+use std::sync::Mutex;
+
+use crate::access::AccessState;
+use crate::arbiter::ArbiterEvent;
+use crate::config::{ChainSchedule, Config};
+use crate::control::ChainOverride;
+use crate::events::EventBus;
+use crate::metrics::Metrics;
+use crate::miners::Miner;
+use crate::plots::PlotScan;
+use crate::quarantine::QuarantineReason;
+use crate::rounds::Round;
+use crate::tls::TlsState;
+use crate::upstream::MiningInfo;
+use crate::watchdog::WatchdogState;
+
+/// Source of the current time for the arbiter, printers and web server.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Local>;
+}
+
+/// The system's local time.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Local> {
+        Local::now()
+    }
+}
+
+/// A clock for tests which only moves when it's told to. Clones share the same time.
+#[cfg(test)]
+#[derive(Clone)]
+pub struct FakeClock {
+    now: std::sync::Arc<Mutex<DateTime<Local>>>,
+}
+
+#[cfg(test)]
+impl FakeClock {
+    pub fn new(now: DateTime<Local>) -> FakeClock {
+        FakeClock {
+            now: std::sync::Arc::new(Mutex::new(now)),
+        }
+    }
+
+    pub fn advance(&self, seconds: i64) -> DateTime<Local> {
+        let mut now = self.now.lock().unwrap();
+        *now = *now + chrono::Duration::seconds(seconds);
+        *now
+    }
+}
+
+#[cfg(test)]
+impl Clock for FakeClock {
+    fn now(&self) -> DateTime<Local> {
+        *self.now.lock().unwrap()
+    }
+}
+
+/// Everything Archon tracks while running.
+pub struct ArchonState {
+    pub chain_mining_infos: Mutex<HashMap<u8, (MiningInfo, DateTime<Local>)>>,
+    pub mining_info_cache: Mutex<HashMap<u8, (u32, String)>>,
+    pub block_start_printed: Mutex<HashMap<u8, u32>>,
+    // Key = tuple (chain index, block height), Value = tuple (account_id, best_deadline)
+    pub best_deadlines: Mutex<HashMap<(u8, u32), Vec<(u64, u64)>>>,
+    pub chain_queue_status: Mutex<HashMap<u8, (u32, DateTime<Local>)>>,
+    // Key = chain index, Value = total milliseconds spent scanning that chain's blocks
+    pub chain_mining_times: Mutex<HashMap<u8, i64>>,
+    // Key = chain index, Value = tuple (block_height, best_confirmed_deadline)
+    pub best_confirmed_deadlines: Mutex<HashMap<u8, (u32, u64)>>,
+    // Key = chain index, Value = the most recent intervals between blocks, in seconds
+    pub chain_block_intervals: Mutex<HashMap<u8, VecDeque<i64>>>,
+    // Key = chain index, Value = the schedule window currently in effect for that chain
+    pub chain_active_schedules: Mutex<HashMap<u8, ChainSchedule>>,
+    // Key = chain index, Value = tuple (reason, quarantined_since)
+    pub chain_quarantines: Mutex<HashMap<u8, (QuarantineReason, DateTime<Local>)>>,
+    // Key = chain index, Value = time the chain's upstream became unreachable
+    pub chain_outages: Mutex<HashMap<u8, DateTime<Local>>>,
+    // Key = chain index, Value = number of submissions in a row which couldn't be forwarded upstream
+    pub chain_submission_failures: Mutex<HashMap<u8, u8>>,
+    // Key = chain index, Value = number of times in a row the chain was quarantined for failing submissions
+    pub chain_quarantine_strikes: Mutex<HashMap<u8, u32>>,
+    // Key = chain index, Value = time the chain was paused through the admin API
+    pub chain_pauses: Mutex<HashMap<u8, DateTime<Local>>>,
+    // Key = chain index, Value = the temporary priority/target deadline override made through the admin API
+    pub chain_overrides: Mutex<HashMap<u8, ChainOverride>>,
+    // Key = chain index, Value = time a discrepancy between reported and configured plot capacity was last warned about
+    pub capacity_warnings: Mutex<HashMap<u8, DateTime<Local>>>,
+    // (chain index, block height) of the last round force-started through the admin API
+    pub force_started_round: Mutex<Option<(u8, u32)>>,
+    // the most recently started rounds, newest first
+    pub recent_rounds: Mutex<VecDeque<Round>>,
+    // Key = tuple (miner address, miner software), Value = everything known about the miner
+    pub miners: Mutex<HashMap<(String, String), Miner>>,
+    // the plot files found by the latest scan of the plot directories
+    pub plot_scan: Mutex<Option<PlotScan>>,
+    pub event_bus: EventBus,
+    pub metrics: Metrics,
+    pub tls: TlsState,
+    pub access: AccessState,
+    pub watchdog: WatchdogState,
+    pub arbiter_event_channel: (Mutex<mpsc::Sender<ArbiterEvent>>, Mutex<mpsc::Receiver<ArbiterEvent>>),
+    // (chain name, block height, decision) of the decisions already printed for each chain's latest block
+    pub printed_arbiter_decisions: Mutex<HashSet<(String, u32, String)>>,
+    pub current_chain_index: Mutex<u8>,
+    pub last_mining_info: Mutex<String>,
+}
+
+impl ArchonState {
+    pub fn new() -> ArchonState {
+        let (sender, receiver) = mpsc::channel();
+        ArchonState {
+            chain_mining_infos: Mutex::new(HashMap::new()),
+            mining_info_cache: Mutex::new(HashMap::new()),
+            block_start_printed: Mutex::new(HashMap::new()),
+            best_deadlines: Mutex::new(HashMap::new()),
+            chain_queue_status: Mutex::new(HashMap::new()),
+            chain_mining_times: Mutex::new(HashMap::new()),
+            best_confirmed_deadlines: Mutex::new(HashMap::new()),
+            chain_block_intervals: Mutex::new(HashMap::new()),
+            chain_active_schedules: Mutex::new(HashMap::new()),
+            chain_quarantines: Mutex::new(HashMap::new()),
+            chain_outages: Mutex::new(HashMap::new()),
+            chain_submission_failures: Mutex::new(HashMap::new()),
+            chain_quarantine_strikes: Mutex::new(HashMap::new()),
+            chain_pauses: Mutex::new(HashMap::new()),
+            chain_overrides: Mutex::new(HashMap::new()),
+            capacity_warnings: Mutex::new(HashMap::new()),
+            force_started_round: Mutex::new(None),
+            recent_rounds: Mutex::new(VecDeque::new()),
+            miners: Mutex::new(HashMap::new()),
+            plot_scan: Mutex::new(None),
+            event_bus: EventBus::new(),
+            metrics: Metrics::new(),
+            tls: TlsState::new(),
+            access: AccessState::new(),
+            watchdog: WatchdogState::new(),
+            arbiter_event_channel: (Mutex::new(sender), Mutex::new(receiver)),
+            printed_arbiter_decisions: Mutex::new(HashSet::new()),
+            current_chain_index: Mutex::new(0u8),
+            last_mining_info: Mutex::new(String::from("")),
+        }
+    }
+}
+
+/// The HTTP clients used to talk to each chain's upstream pool/wallet.
+pub struct UpstreamClients {
+    pub mining_info_client: reqwest::Client,
+    pub nonce_submission_clients: Mutex<HashMap<u8, reqwest::Client>>,
+}
+
+impl UpstreamClients {
+    pub fn new() -> UpstreamClients {
+        UpstreamClients {
+            mining_info_client: reqwest::Client::new(),
+            nonce_submission_clients: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+/// A single proxy instance: its config, state, clock and upstream clients.
+/// Everything which used to be a global is reached through this, so several instances can run side by side.
+pub struct Archon {
+    pub conf: Config,
+    pub state: ArchonState,
+    pub clock: Box<dyn Clock>,
+    pub upstream: UpstreamClients,
+}
+
+impl Archon {
+    pub fn new(conf: Config) -> Archon {
+        Archon::with_clock(conf, Box::new(SystemClock))
+    }
+
+    pub fn with_clock(conf: Config, clock: Box<dyn Clock>) -> Archon {
+        Archon {
+            conf,
+            state: ArchonState::new(),
+            clock,
+            upstream: UpstreamClients::new(),
+        }
+    }
+
+    pub fn now(&self) -> DateTime<Local> {
+        self.clock.now()
+    }
+}
@@ -0,0 +1,177 @@
+use chrono::{DateTime, Local};
+
+use crate::context::Archon;
+use crate::events::{self, ArchonEvent};
+
+// how long a priority or target deadline override lasts if no duration is given, in seconds
+pub const DEFAULT_OVERRIDE_DURATION: u64 = 3600;
+
+/// A temporary change to a chain's priority and/or target deadline, made through the admin API.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChainOverride {
+    pub priority: Option<u8>,
+    pub target_deadline: Option<u64>,
+    pub expires: DateTime<Local>,
+}
+
+impl ChainOverride {
+    pub fn describe(&self) -> String {
+        let mut changes = Vec::new();
+        match self.priority {
+            Some(priority) => changes.push(format!("Priority: {}", priority)),
+            None => {}
+        };
+        match self.target_deadline {
+            Some(target_deadline) => changes.push(format!("Target Deadline: {}", target_deadline)),
+            None => {}
+        };
+        changes.join(", ")
+    }
+}
+
+pub fn is_chain_paused(archon: &Archon, chain_index: u8) -> bool {
+    archon.state.chain_pauses.lock().unwrap().contains_key(&chain_index)
+}
+
+/// Takes a chain out of the queue until it's resumed. Returns false if it was already paused.
+pub fn pause_chain(archon: &Archon, chain_index: u8, requested_by: &str, now: DateTime<Local>) -> bool {
+    if archon.state.chain_pauses.lock().unwrap().insert(chain_index, now).is_some() {
+        return false;
+    }
+    record_admin_action(archon, chain_index, "pause", "Paused, its blocks won't be mined until it's resumed.", requested_by);
+    true
+}
+
+/// Puts a paused chain back in the queue. Returns false if it wasn't paused.
+pub fn resume_chain(archon: &Archon, chain_index: u8, requested_by: &str, now: DateTime<Local>) -> bool {
+    let paused_since = match archon.state.chain_pauses.lock().unwrap().remove(&chain_index) {
+        Some(paused_since) => paused_since,
+        None => return false,
+    };
+    let detail = format!("Resumed, total time paused: {} seconds.", (now - paused_since).num_seconds());
+    record_admin_action(archon, chain_index, "resume", &*detail, requested_by);
+    true
+}
+
+/// Returns the override in effect for a chain, ignoring one which has run out.
+pub fn get_chain_override(archon: &Archon, chain_index: u8, now: DateTime<Local>) -> Option<ChainOverride> {
+    match archon.state.chain_overrides.lock().unwrap().get(&chain_index) {
+        Some(chain_override) if chain_override.expires > now => Some(*chain_override),
+        _ => None,
+    }
+}
+
+/// Returns the chain whose override runs out first, and when.
+pub fn get_next_override_expiry(archon: &Archon) -> Option<(u8, DateTime<Local>)> {
+    archon.state.chain_overrides
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(index, chain_override)| (*index, chain_override.expires))
+        .min_by_key(|(_, expires)| *expires)
+}
+
+/// Overrides a chain's priority and/or target deadline for a number of seconds, replacing any earlier override.
+pub fn override_chain(
+    archon: &Archon,
+    chain_index: u8,
+    priority: Option<u8>,
+    target_deadline: Option<u64>,
+    duration: u64,
+    requested_by: &str,
+    now: DateTime<Local>,
+) {
+    let chain_override = ChainOverride {
+        priority,
+        target_deadline,
+        expires: now + chrono::Duration::seconds(duration as i64),
+    };
+    archon.state.chain_overrides.lock().unwrap().insert(chain_index, chain_override);
+    let detail = format!("Overridden for {} seconds: {}.", duration, chain_override.describe());
+    record_admin_action(archon, chain_index, "override", &*detail, requested_by);
+}
+
+/// Removes a chain's override before it runs out. Returns false if it didn't have one.
+pub fn clear_chain_override(archon: &Archon, chain_index: u8, requested_by: &str) -> bool {
+    let chain_override = match archon.state.chain_overrides.lock().unwrap().remove(&chain_index) {
+        Some(chain_override) => chain_override,
+        None => return false,
+    };
+    let detail = format!("Override cleared: {}.", chain_override.describe());
+    record_admin_action(archon, chain_index, "clearOverride", &*detail, requested_by);
+    true
+}
+
+/// Drops a chain's override once it has run out.
+pub fn expire_chain_override(archon: &Archon, chain_index: u8, now: DateTime<Local>) {
+    let mut chain_overrides_map = archon.state.chain_overrides.lock().unwrap();
+    let chain_override = match chain_overrides_map.get(&chain_index) {
+        Some(chain_override) if chain_override.expires <= now => *chain_override,
+        _ => return,
+    };
+    chain_overrides_map.remove(&chain_index);
+    drop(chain_overrides_map);
+    match super::get_configured_chain_from_index(archon, chain_index) {
+        Some(chain) => {
+            info!("OVERRIDE OVER - {} - {}", &*chain.name, chain_override.describe());
+            super::print_decision(
+                archon,
+                &*chain.name,
+                &*chain.color,
+                format!("Override ran out, back to its configured settings ({}).", chain_override.describe()).as_str(),
+            );
+        }
+        None => {}
+    };
+}
+
+/// Forgets every account's best deadline for a block, so the next deadline from each account is forwarded again.
+/// Returns false if there weren't any.
+pub fn clear_best_deadlines(archon: &Archon, chain_index: u8, height: u32, requested_by: &str) -> bool {
+    let best_deadlines = match archon.state.best_deadlines.lock().unwrap().remove(&(chain_index, height)) {
+        Some(best_deadlines) => best_deadlines,
+        None => return false,
+    };
+    let mut best_confirmed_deadlines_map = archon.state.best_confirmed_deadlines.lock().unwrap();
+    match best_confirmed_deadlines_map.get(&chain_index) {
+        Some((confirmed_height, _)) if *confirmed_height == height => {
+            best_confirmed_deadlines_map.remove(&chain_index);
+        }
+        _ => {}
+    };
+    drop(best_confirmed_deadlines_map);
+    let detail = format!("Cleared the best deadlines of {} account(s) for #{}.", best_deadlines.len(), height);
+    record_admin_action(archon, chain_index, "clearBestDeadlines", &*detail, requested_by);
+    true
+}
+
+/// Rounds started by an admin aren't interrupted by other chains' blocks.
+pub fn is_round_force_started(archon: &Archon, chain_index: u8, height: u32) -> bool {
+    *archon.state.force_started_round.lock().unwrap() == Some((chain_index, height))
+}
+
+pub fn record_force_started_round(archon: &Archon, chain_index: u8, height: u32) {
+    *archon.state.force_started_round.lock().unwrap() = Some((chain_index, height));
+}
+
+/// Logs and prints an admin action, and publishes it as an event so there's a record of who did what.
+pub fn record_admin_action(archon: &Archon, chain_index: u8, action: &str, detail: &str, requested_by: &str) {
+    let chain = match super::get_configured_chain_from_index(archon, chain_index) {
+        Some(chain) => chain,
+        None => return,
+    };
+    info!("ADMIN - {} - {} | {} | By: {}", &*chain.name, action, detail, requested_by);
+    events::publish(archon, ArchonEvent::AdminAction {
+        chain_index,
+        chain_name: chain.name.clone(),
+        action: String::from(action),
+        detail: String::from(detail),
+        requested_by: String::from(requested_by),
+    });
+    super::print_decision(
+        archon,
+        &*chain.name,
+        &*chain.color,
+        format!("{} (Admin: {})", detail, requested_by).as_str(),
+    );
+}
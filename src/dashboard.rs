@@ -0,0 +1,279 @@
+use chrono::{DateTime, Local};
+
+use crate::arbiter;
+use crate::capacity;
+use crate::context::Archon;
+use crate::control;
+use crate::events;
+use crate::miners;
+use crate::quarantine;
+use crate::rounds;
+
+/// Everything the web dashboard shows, gathered in one go so each refresh sees a consistent picture.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DashboardSnapshot {
+    pub version: String,
+    pub time: String,
+    pub queuing_mode: String,
+    pub grace_period: u16,
+    pub current_block: Option<CurrentBlock>,
+    pub chains: Vec<ChainStatus>,
+    pub recent_rounds: Vec<RoundSummary>,
+    pub miners: Vec<MinerSummary>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrentBlock {
+    pub chain_name: String,
+    pub color: String,
+    pub height: u32,
+    pub base_target: u32,
+    pub generation_signature: String,
+    pub target_deadline: u64,
+    pub started: String,
+    pub elapsed_seconds: i64,
+    pub best_deadline: Option<u64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainStatus {
+    pub index: u8,
+    pub name: String,
+    pub color: String,
+    pub priority: u8,
+    pub state: String,
+    pub latest_height: u32,
+    pub queued_height: u32,
+    pub seconds_since_last_block: Option<i64>,
+    // when a priority/target deadline override from the admin API runs out, if the chain has one
+    pub override_expires: Option<String>,
+    // the plot capacity used for the chain's dynamic deadlines, in TiB, and whether it came from the config or the miners
+    pub plot_capacity_tebibytes: f64,
+    pub plot_capacity_source: String,
+    pub upstream: UpstreamHealth,
+    pub best_deadlines: Vec<AccountDeadline>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpstreamHealth {
+    pub reachable: bool,
+    pub outage_seconds: Option<i64>,
+    pub quarantined: bool,
+    pub quarantine_reason: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountDeadline {
+    pub account_id: String,
+    pub deadline: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoundSummary {
+    pub chain_name: String,
+    pub color: String,
+    pub height: u32,
+    pub base_target: u32,
+    pub started: String,
+    pub duration_seconds: i64,
+    pub outcome: String,
+    pub best_deadline: Option<u64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MinerSummary {
+    pub address: String,
+    pub software: String,
+    pub connected: bool,
+    pub first_seen: String,
+    pub last_seen: String,
+    pub seconds_since_last_seen: i64,
+    pub poll_interval_seconds: Option<f64>,
+    pub capacity_tebibytes: Option<f64>,
+    pub accounts: Vec<String>,
+    pub total_submissions: u64,
+    pub silent_rounds: u32,
+    pub recent_rounds: Vec<MinerRoundSummary>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MinerRoundSummary {
+    pub chain_name: String,
+    pub height: u32,
+    pub submissions: u32,
+    pub active: bool,
+}
+
+pub fn get_dashboard_snapshot(archon: &Archon) -> DashboardSnapshot {
+    let now = archon.now();
+    DashboardSnapshot {
+        version: String::from(super::VERSION),
+        time: now.to_rfc3339(),
+        queuing_mode: String::from(get_queuing_mode(archon)),
+        grace_period: archon.conf.grace_period,
+        current_block: get_current_block(archon, now),
+        chains: get_chain_statuses(archon, now),
+        recent_rounds: get_round_summaries(archon, now),
+        miners: get_miner_summaries(archon, now),
+    }
+}
+
+pub fn get_queuing_mode(archon: &Archon) -> &'static str {
+    if arbiter::is_fair_share_mode(archon) {
+        "fairShare"
+    } else if arbiter::is_priority_mode(archon) {
+        "priority"
+    } else {
+        "fifo"
+    }
+}
+
+pub fn get_current_block(archon: &Archon, now: DateTime<Local>) -> Option<CurrentBlock> {
+    let index = arbiter::get_current_chain_index(archon);
+    let chain = super::get_chain_from_index(archon, index)?;
+    let mining_info = super::get_current_mining_info(archon)?;
+    let started = match archon.state.chain_queue_status.lock().unwrap().get(&index) {
+        Some((height, start_time)) if *height == mining_info.height => *start_time,
+        _ => return None,
+    };
+    Some(CurrentBlock {
+        chain_name: chain.name,
+        color: chain.color,
+        height: mining_info.height,
+        base_target: mining_info.base_target,
+        best_deadline: arbiter::get_best_deadline_for_round(archon, index, mining_info.height),
+        generation_signature: mining_info.generation_signature,
+        target_deadline: mining_info.target_deadline,
+        started: started.to_rfc3339(),
+        elapsed_seconds: seconds_between(started, now),
+    })
+}
+
+pub fn get_chain_statuses(archon: &Archon, now: DateTime<Local>) -> Vec<ChainStatus> {
+    let mut chain_statuses = Vec::new();
+    for inner in &archon.conf.poc_chains {
+        for chain in inner {
+            if chain.enabled.unwrap_or(true) {
+                let index = super::get_chain_index(archon, &*chain.url, &*chain.name);
+                chain_statuses.extend(get_chain_status(archon, index, now));
+            }
+        }
+    }
+    chain_statuses
+}
+
+pub fn get_chain_status(archon: &Archon, index: u8, now: DateTime<Local>) -> Option<ChainStatus> {
+    let chain = super::get_chain_from_index(archon, index)?;
+    let (latest_height, last_block_time) = arbiter::get_latest_chain_info(archon, index);
+    let queued_height = match archon.state.chain_queue_status.lock().unwrap().get(&index) {
+        Some((height, _)) => *height,
+        None => 0,
+    };
+    let outage_since = archon.state.chain_outages.lock().unwrap().get(&index).cloned();
+    let quarantine = quarantine::get_quarantine(archon, index);
+    let (plot_capacity_tebibytes, plot_capacity_source) = capacity::get_plot_capacity(archon, index);
+    Some(ChainStatus {
+        index,
+        name: chain.name,
+        color: chain.color,
+        priority: chain.priority,
+        state: String::from(arbiter::get_chain_state(archon, index).describe()),
+        latest_height,
+        queued_height,
+        seconds_since_last_block: if latest_height > 0 {
+            Some(seconds_between(last_block_time, now))
+        } else {
+            None
+        },
+        override_expires: control::get_chain_override(archon, index, now).map(|chain_override| chain_override.expires.to_rfc3339()),
+        plot_capacity_tebibytes,
+        plot_capacity_source: String::from(plot_capacity_source.describe()),
+        upstream: UpstreamHealth {
+            reachable: outage_since.is_none(),
+            outage_seconds: outage_since.map(|since| seconds_between(since, now)),
+            quarantined: quarantine.is_some(),
+            quarantine_reason: quarantine.map(|(reason, _)| String::from(reason.describe())),
+        },
+        best_deadlines: get_account_deadlines(archon, index, latest_height),
+    })
+}
+
+/// Returns each account's best deadline for a chain's block, best first.
+pub fn get_account_deadlines(archon: &Archon, chain_index: u8, height: u32) -> Vec<AccountDeadline> {
+    let mut account_deadlines: Vec<AccountDeadline> = arbiter::get_best_deadlines_for_block(archon, chain_index, height)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(account_id, deadline)| AccountDeadline {
+            account_id: super::censor_account_id(archon, account_id),
+            deadline,
+        })
+        .collect();
+    account_deadlines.sort_by_key(|account_deadline| account_deadline.deadline);
+    account_deadlines
+}
+
+pub fn get_round_summaries(archon: &Archon, now: DateTime<Local>) -> Vec<RoundSummary> {
+    rounds::get_recent_rounds(archon)
+        .into_iter()
+        .map(|round| {
+            let (chain_name, color) = match super::get_configured_chain_from_index(archon, round.chain_index) {
+                Some(chain) => (chain.name, chain.color),
+                None => (String::from("Unknown"), String::from("white")),
+            };
+            RoundSummary {
+                chain_name,
+                color,
+                height: round.height,
+                base_target: round.base_target,
+                started: round.started.to_rfc3339(),
+                duration_seconds: seconds_between(round.started, round.ended.unwrap_or(now)),
+                outcome: String::from(round.outcome.describe()),
+                best_deadline: arbiter::get_best_deadline_for_round(archon, round.chain_index, round.height),
+            }
+        })
+        .collect()
+}
+
+pub fn get_miner_summaries(archon: &Archon, now: DateTime<Local>) -> Vec<MinerSummary> {
+    miners::get_miners(archon)
+        .into_iter()
+        .map(|miner| MinerSummary {
+            connected: miner.is_connected(now),
+            poll_interval_seconds: miner.get_average_poll_interval(),
+            address: if archon.conf.show_miner_addresses.unwrap_or_default() {
+                miner.address
+            } else {
+                String::from("Hidden")
+            },
+            software: miner.software,
+            first_seen: miner.first_seen.to_rfc3339(),
+            last_seen: miner.last_seen.to_rfc3339(),
+            seconds_since_last_seen: seconds_between(miner.last_seen, now),
+            capacity_tebibytes: miner.capacity.map(|capacity| capacity as f64 / 1024f64),
+            accounts: miner.accounts.iter().map(|account_id| super::censor_account_id(archon, *account_id)).collect(),
+            total_submissions: miner.total_submissions,
+            silent_rounds: miner.silent_rounds,
+            recent_rounds: miner.rounds
+                .iter()
+                .map(|round| MinerRoundSummary {
+                    chain_name: events::get_chain_name(archon, round.chain_index),
+                    height: round.height,
+                    submissions: round.submissions,
+                    active: round.active,
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+pub fn seconds_between(from: DateTime<Local>, to: DateTime<Local>) -> i64 {
+    to.signed_duration_since(from).num_seconds()
+}
@@ -0,0 +1,196 @@
+use std::collections::HashSet;
+
+use crate::arbiter;
+use crate::context::Archon;
+use crate::rounds::{self, RoundOutcome};
+
+// a deadline is a hit divided by the base target, and hits are 64 bit numbers
+const HIT_RANGE: f64 = 18_446_744_073_709_551_616f64;
+// each nonce is one chance at a hit per round, and there are 2^22 nonces in a TiB
+pub const NONCES_PER_TEBIBYTE: f64 = 4_194_304f64;
+// fewer rounds with deadlines than this are too few to estimate a capacity from
+const MIN_ROUNDS_WITH_DEADLINES: usize = 3;
+// the z-score of the 95% confidence interval
+const CONFIDENCE_Z: f64 = 1.96;
+
+/// An account's best deadline for a finished round, if it submitted one.
+#[derive(Debug, Clone)]
+pub struct AccountRound {
+    pub chain_index: u8,
+    pub height: u32,
+    pub base_target: u32,
+    // the highest deadline the miners would have submitted, if there was a limit
+    pub target_deadline: Option<u64>,
+    pub deadline: Option<u64>,
+}
+
+impl AccountRound {
+    /// The deadline as a fraction of the hit range. For an account with N nonces, this is the lowest of N uniformly
+    /// distributed numbers, so it's exponentially distributed with a rate of N.
+    pub fn get_scaled_deadline(&self) -> Option<f64> {
+        self.deadline.map(|deadline| scale_deadline(deadline, self.base_target))
+    }
+
+    /// The target deadline as a fraction of the hit range, deadlines over it are never seen.
+    pub fn get_scaled_target_deadline(&self) -> Option<f64> {
+        self.target_deadline.map(|target_deadline| scale_deadline(target_deadline, self.base_target))
+    }
+}
+
+/// An account's plot capacity, estimated from its best deadlines.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CapacityEstimate {
+    pub capacity_tebibytes: f64,
+    // the 95% confidence interval of the estimate
+    pub low_tebibytes: f64,
+    pub high_tebibytes: f64,
+    pub rounds: usize,
+    pub rounds_with_deadlines: usize,
+}
+
+pub fn scale_deadline(deadline: u64, base_target: u32) -> f64 {
+    deadline as f64 * base_target as f64 / HIT_RANGE
+}
+
+/// The account's best deadline for each recently finished round of the chains it mines.
+/// Rounds cut short by another chain's block are left out, as the miners didn't get to finish scanning.
+/// A chain is taken to be mined by the account if it submitted a deadline for any of its recent rounds.
+pub fn get_account_rounds(archon: &Archon, account_id: u64) -> Vec<AccountRound> {
+    let finished_rounds: Vec<rounds::Round> = rounds::get_recent_rounds(archon)
+        .into_iter()
+        .filter(|round| round.outcome == RoundOutcome::Finished && round.base_target > 0)
+        .collect();
+    let account_rounds: Vec<AccountRound> = finished_rounds
+        .iter()
+        .map(|round| AccountRound {
+            chain_index: round.chain_index,
+            height: round.height,
+            base_target: round.base_target,
+            target_deadline: if round.target_deadline == 0 || round.target_deadline == u64::max_value() {
+                None
+            } else {
+                Some(round.target_deadline)
+            },
+            deadline: arbiter::get_best_deadlines_for_block(archon, round.chain_index, round.height)
+                .unwrap_or_default()
+                .into_iter()
+                .find(|(best_deadline_account_id, _)| *best_deadline_account_id == account_id)
+                .map(|(_, deadline)| deadline),
+        })
+        .collect();
+    let mined_chains: HashSet<u8> = account_rounds
+        .iter()
+        .filter(|account_round| account_round.deadline.is_some())
+        .map(|account_round| account_round.chain_index)
+        .collect();
+    account_rounds
+        .into_iter()
+        .filter(|account_round| mined_chains.contains(&account_round.chain_index))
+        .collect()
+}
+
+/// Returns (rounds, rounds with deadlines, total scaled deadlines). Rounds without a deadline count as
+/// deadlines somewhere over the target deadline, rounds without either tell us nothing and are left out.
+pub fn get_scaled_deadline_totals(account_rounds: &[AccountRound]) -> (usize, usize, f64) {
+    let mut rounds = 0;
+    let mut rounds_with_deadlines = 0;
+    let mut total_scaled_deadlines = 0f64;
+    for account_round in account_rounds {
+        match (account_round.get_scaled_deadline(), account_round.get_scaled_target_deadline()) {
+            (Some(scaled_deadline), _) => {
+                rounds_with_deadlines += 1;
+                total_scaled_deadlines += scaled_deadline;
+            }
+            (None, Some(scaled_target_deadline)) => total_scaled_deadlines += scaled_target_deadline,
+            (None, None) => continue,
+        };
+        rounds += 1;
+    }
+    (rounds, rounds_with_deadlines, total_scaled_deadlines)
+}
+
+/// Estimates a plot capacity from best deadlines by maximum likelihood.
+pub fn estimate_capacity(account_rounds: &[AccountRound]) -> Option<CapacityEstimate> {
+    let (rounds, rounds_with_deadlines, total_scaled_deadlines) = get_scaled_deadline_totals(account_rounds);
+    if rounds_with_deadlines < MIN_ROUNDS_WITH_DEADLINES || total_scaled_deadlines <= 0f64 {
+        return None;
+    }
+    let capacity_tebibytes = rounds_with_deadlines as f64 / total_scaled_deadlines / NONCES_PER_TEBIBYTE;
+    let margin = (CONFIDENCE_Z / (rounds_with_deadlines as f64).sqrt()).exp();
+    Some(CapacityEstimate {
+        capacity_tebibytes,
+        low_tebibytes: capacity_tebibytes / margin,
+        high_tebibytes: capacity_tebibytes * margin,
+        rounds,
+        rounds_with_deadlines,
+    })
+}
+
+/// An account's effective plot capacity, estimated from its best deadlines in recent rounds.
+pub fn get_effective_capacity(archon: &Archon, account_id: u64) -> Option<CapacityEstimate> {
+    estimate_capacity(&get_account_rounds(archon, account_id))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // with a base target of 1, a deadline of 2^40 is 1/4 of a TiB's share of the hit range
+    const QUARTER_TEBIBYTE_DEADLINE: u64 = 1 << 40;
+
+    pub(crate) fn create_account_round(height: u32, target_deadline: Option<u64>, deadline: Option<u64>) -> AccountRound {
+        AccountRound {
+            chain_index: 0,
+            height,
+            base_target: 1,
+            target_deadline,
+            deadline,
+        }
+    }
+
+    #[test]
+    fn scaled_deadline_totals_count_missing_deadlines_at_the_target_deadline() {
+        let account_rounds = vec![
+            create_account_round(1, None, Some(QUARTER_TEBIBYTE_DEADLINE)),
+            create_account_round(2, Some(2 * QUARTER_TEBIBYTE_DEADLINE), None),
+            create_account_round(3, None, None),
+        ];
+        let (rounds, rounds_with_deadlines, total_scaled_deadlines) = get_scaled_deadline_totals(&account_rounds);
+        assert_eq!(rounds, 2);
+        assert_eq!(rounds_with_deadlines, 1);
+        assert_eq!(total_scaled_deadlines, 3f64 / (4f64 * NONCES_PER_TEBIBYTE));
+    }
+
+    #[test]
+    fn estimates_capacity_from_deadlines() {
+        let account_rounds: Vec<AccountRound> = (0..10)
+            .map(|height| create_account_round(height, None, Some(QUARTER_TEBIBYTE_DEADLINE)))
+            .collect();
+        let estimate = estimate_capacity(&account_rounds).unwrap();
+        assert!((estimate.capacity_tebibytes - 4f64).abs() < 1e-9);
+        assert!(estimate.low_tebibytes < 4f64 && estimate.high_tebibytes > 4f64);
+        assert_eq!(estimate.rounds, 10);
+        assert_eq!(estimate.rounds_with_deadlines, 10);
+    }
+
+    #[test]
+    fn rounds_without_deadlines_lower_the_estimate() {
+        let mut account_rounds: Vec<AccountRound> = (0..4)
+            .map(|height| create_account_round(height, None, Some(QUARTER_TEBIBYTE_DEADLINE)))
+            .collect();
+        account_rounds.extend((4..8).map(|height| create_account_round(height, Some(QUARTER_TEBIBYTE_DEADLINE), None)));
+        let estimate = estimate_capacity(&account_rounds).unwrap();
+        assert!((estimate.capacity_tebibytes - 2f64).abs() < 1e-9);
+        assert_eq!(estimate.rounds, 8);
+        assert_eq!(estimate.rounds_with_deadlines, 4);
+    }
+
+    #[test]
+    fn too_few_deadlines_give_no_estimate() {
+        let account_rounds: Vec<AccountRound> = (0..MIN_ROUNDS_WITH_DEADLINES as u32 - 1)
+            .map(|height| create_account_round(height, None, Some(QUARTER_TEBIBYTE_DEADLINE)))
+            .collect();
+        assert!(estimate_capacity(&account_rounds).is_none());
+    }
+}
@@ -0,0 +1,259 @@
+use chrono::{DateTime, Local};
+use futures::sync::mpsc;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use crate::context::Archon;
+
+// how many events are kept for clients reconnecting with a Last-Event-ID
+const MAX_EVENT_HISTORY: usize = 100;
+// events a slow subscriber can fall behind by before it starts missing them
+const SUBSCRIBER_BUFFER_SIZE: usize = 256;
+
+/// Everything Archon publishes to dashboards and bots as it happens.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ArchonEvent {
+    // a chain's upstream has a new block, it may be mined now or queued
+    #[serde(rename_all = "camelCase")]
+    NewBlock {
+        chain_index: u8,
+        chain_name: String,
+        height: u32,
+        base_target: u32,
+    },
+    #[serde(rename_all = "camelCase")]
+    Fork {
+        chain_index: u8,
+        chain_name: String,
+        old_height: u32,
+        new_height: u32,
+    },
+    #[serde(rename_all = "camelCase")]
+    BlockStarted {
+        chain_index: u8,
+        chain_name: String,
+        height: u32,
+        base_target: u32,
+        generation_signature: String,
+        target_deadline: u64,
+    },
+    #[serde(rename_all = "camelCase")]
+    BlockInterrupted {
+        chain_index: u8,
+        chain_name: String,
+        height: u32,
+        interrupted_by: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    BlockRequeued {
+        chain_index: u8,
+        chain_name: String,
+        height: u32,
+        interrupted_by: String,
+    },
+    // forwarded is false if the deadline was confirmed to the miner without being sent upstream
+    #[serde(rename_all = "camelCase")]
+    NonceSubmitted {
+        chain_index: u8,
+        chain_name: String,
+        height: u32,
+        account_id: String,
+        deadline: u64,
+        miner_software: String,
+        forwarded: bool,
+    },
+    #[serde(rename_all = "camelCase")]
+    NonceAccepted {
+        chain_index: u8,
+        chain_name: String,
+        height: u32,
+        account_id: String,
+        deadline: u64,
+        confirmation_time_ms: i64,
+    },
+    #[serde(rename_all = "camelCase")]
+    NonceRejected {
+        chain_index: u8,
+        chain_name: String,
+        height: u32,
+        account_id: String,
+        deadline: u64,
+        rejection_time_ms: i64,
+    },
+    #[serde(rename_all = "camelCase")]
+    UpstreamOutage {
+        chain_index: u8,
+        chain_name: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    UpstreamRecovered {
+        chain_index: u8,
+        chain_name: String,
+        outage_seconds: i64,
+    },
+    #[serde(rename_all = "camelCase")]
+    ChainQuarantined {
+        chain_index: u8,
+        chain_name: String,
+        reason: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    ChainReleased {
+        chain_index: u8,
+        chain_name: String,
+    },
+    // something was changed through the admin API, requested_by is the API token's name and the request's address
+    #[serde(rename_all = "camelCase")]
+    AdminAction {
+        chain_index: u8,
+        chain_name: String,
+        action: String,
+        detail: String,
+        requested_by: String,
+    },
+    // a miner or account stopped polling or submitting, chain_index is only set for submissions
+    #[serde(rename_all = "camelCase")]
+    MinerAlert {
+        subject: String,
+        reason: String,
+        chain_index: Option<u8>,
+        chain_name: Option<String>,
+        detail: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    MinerRecovered {
+        subject: String,
+        reason: String,
+        chain_index: Option<u8>,
+        chain_name: Option<String>,
+        alert_seconds: i64,
+    },
+}
+
+impl ArchonEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ArchonEvent::NewBlock { .. } => "newBlock",
+            ArchonEvent::Fork { .. } => "fork",
+            ArchonEvent::BlockStarted { .. } => "blockStarted",
+            ArchonEvent::BlockInterrupted { .. } => "blockInterrupted",
+            ArchonEvent::BlockRequeued { .. } => "blockRequeued",
+            ArchonEvent::NonceSubmitted { .. } => "nonceSubmitted",
+            ArchonEvent::NonceAccepted { .. } => "nonceAccepted",
+            ArchonEvent::NonceRejected { .. } => "nonceRejected",
+            ArchonEvent::UpstreamOutage { .. } => "upstreamOutage",
+            ArchonEvent::UpstreamRecovered { .. } => "upstreamRecovered",
+            ArchonEvent::ChainQuarantined { .. } => "chainQuarantined",
+            ArchonEvent::ChainReleased { .. } => "chainReleased",
+            ArchonEvent::AdminAction { .. } => "adminAction",
+            ArchonEvent::MinerAlert { .. } => "minerAlert",
+            ArchonEvent::MinerRecovered { .. } => "minerRecovered",
+        }
+    }
+}
+
+/// An event as it was published, numbered in order.
+#[derive(Debug, Clone, Serialize)]
+pub struct PublishedEvent {
+    pub id: u64,
+    pub time: String,
+    #[serde(flatten)]
+    pub event: ArchonEvent,
+}
+
+impl PublishedEvent {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// Formats the event as a Server-Sent Events message.
+    pub fn to_sse(&self) -> String {
+        format!("id: {}\nevent: {}\ndata: {}\n\n", self.id, self.event.name(), self.to_json())
+    }
+}
+
+struct EventBusState {
+    next_id: u64,
+    history: VecDeque<PublishedEvent>,
+    subscribers: Vec<mpsc::Sender<PublishedEvent>>,
+}
+
+/// Hands each published event to every subscriber.
+pub struct EventBus {
+    state: Mutex<EventBusState>,
+}
+
+impl EventBus {
+    pub fn new() -> EventBus {
+        EventBus {
+            state: Mutex::new(EventBusState {
+                next_id: 1,
+                history: VecDeque::new(),
+                subscribers: Vec::new(),
+            }),
+        }
+    }
+
+    pub fn publish(&self, event: ArchonEvent, now: DateTime<Local>) {
+        let mut state = self.state.lock().unwrap();
+        let published_event = PublishedEvent {
+            id: state.next_id,
+            time: now.to_rfc3339(),
+            event,
+        };
+        state.next_id += 1;
+        trace!("EVENT - {}", published_event.to_json());
+        state.history.push_back(published_event.clone());
+        while state.history.len() > MAX_EVENT_HISTORY {
+            state.history.pop_front();
+        }
+        let mut i = 0;
+        while i < state.subscribers.len() {
+            match state.subscribers[i].try_send(published_event.clone()) {
+                Ok(_) => i += 1,
+                Err(ref why) if why.is_full() => {
+                    debug!("EVENT - Subscriber is falling behind, skipped event #{}", published_event.id);
+                    i += 1;
+                }
+                Err(_) => {
+                    // the subscriber has disconnected
+                    state.subscribers.swap_remove(i);
+                }
+            };
+        }
+    }
+
+    /// Returns a stream of every event published from now on, preceded by any remembered events after last_event_id.
+    pub fn subscribe(&self, last_event_id: Option<u64>) -> mpsc::Receiver<PublishedEvent> {
+        let mut state = self.state.lock().unwrap();
+        let (mut sender, receiver) = mpsc::channel(SUBSCRIBER_BUFFER_SIZE);
+        match last_event_id {
+            Some(last_event_id) => {
+                for published_event in state.history.iter().filter(|e| e.id > last_event_id) {
+                    let _ = sender.try_send(published_event.clone());
+                }
+            }
+            None => {}
+        };
+        state.subscribers.push(sender);
+        receiver
+    }
+}
+
+pub fn publish(archon: &Archon, event: ArchonEvent) {
+    archon.state.metrics.observe_event(&event);
+    archon.state.event_bus.publish(event, archon.now());
+}
+
+pub fn subscribe(archon: &Archon, last_event_id: Option<u64>) -> mpsc::Receiver<PublishedEvent> {
+    archon.state.event_bus.subscribe(last_event_id)
+}
+
+/// The name of a chain for an event, whether or not it's still configured.
+pub fn get_chain_name(archon: &Archon, chain_index: u8) -> String {
+    match super::get_configured_chain_from_index(archon, chain_index) {
+        Some(chain) => chain.name,
+        None => String::from("Unknown"),
+    }
+}
@@ -0,0 +1,318 @@
+use chrono::{DateTime, Local};
+use colored::Colorize;
+use std::net::IpAddr;
+
+use crate::accounts;
+use crate::capacity;
+use crate::context::Archon;
+use crate::estimation::{self, AccountRound, CapacityEstimate, NONCES_PER_TEBIBYTE};
+use crate::tls;
+
+// how sure the check must be that an account's deadlines are worse than expected to flag it as suspect, or unhealthy
+const SUSPECT_CONFIDENCE: f64 = 0.95;
+const UNHEALTHY_CONFIDENCE: f64 = 0.99;
+// an account mining at least this fraction of its expected capacity isn't flagged, however sure the check is
+const MIN_FLAGGED_SHORTFALL: f64 = 0.9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HealthStatus {
+    Healthy,
+    Suspect,
+    Unhealthy,
+    // fewer than 3 rounds with deadlines
+    InsufficientData,
+    // the account has no plots size configured or scanned, and no miner reports one for it
+    NoExpectedCapacity,
+}
+
+impl HealthStatus {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            HealthStatus::Healthy => "healthy",
+            HealthStatus::Suspect => "suspect",
+            HealthStatus::Unhealthy => "unhealthy",
+            HealthStatus::InsufficientData => "insufficientData",
+            HealthStatus::NoExpectedCapacity => "noExpectedCapacity",
+        }
+    }
+
+    pub fn is_flagged(&self) -> bool {
+        *self == HealthStatus::Suspect || *self == HealthStatus::Unhealthy
+    }
+}
+
+/// How an account's best deadlines in recent rounds compare to what its plots size should get.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlotHealth {
+    pub account_id: String,
+    pub status: HealthStatus,
+    // the plots size deadlines are expected to come from, in TiB, and whether it's from the config, the plot directories or the miners
+    pub expected_capacity_tebibytes: Option<f64>,
+    pub expected_capacity_source: Option<String>,
+    pub effective_capacity: Option<CapacityEstimate>,
+    // the effective capacity as a fraction of the expected capacity
+    pub performance: Option<f64>,
+    // how sure the check is that the account's deadlines are worse than expected, from 0 to 1
+    pub confidence: Option<f64>,
+    // rounds whose best deadline was worse than half of all rounds should be, out of the rounds it's known for
+    pub rounds_over_expected_median: usize,
+    pub comparable_rounds: usize,
+}
+
+// the standard normal cumulative distribution function, accurate to about 1e-7 (Abramowitz and Stegun 7.1.26)
+fn normal_cdf(z: f64) -> f64 {
+    let x = z.abs() / 2f64.sqrt();
+    let t = 1f64 / (1f64 + 0.3275911 * x);
+    let polynomial = t * (0.254829592 + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let erf = 1f64 - polynomial * (-x * x).exp();
+    if z >= 0f64 {
+        (1f64 + erf) / 2f64
+    } else {
+        (1f64 - erf) / 2f64
+    }
+}
+
+/// How sure we can be that the deadlines came from fewer nonces than expected, by a one sided likelihood ratio test
+/// of the exponential distribution the scaled best deadlines follow.
+pub fn get_shortfall_confidence(rounds_with_deadlines: usize, total_scaled_deadlines: f64, expected_nonces: f64) -> f64 {
+    let k = rounds_with_deadlines as f64;
+    let expected_rounds_with_deadlines = expected_nonces * total_scaled_deadlines;
+    let likelihood_ratio = if k > 0f64 {
+        2f64 * (k * (k / expected_rounds_with_deadlines).ln() - k + expected_rounds_with_deadlines)
+    } else {
+        2f64 * expected_rounds_with_deadlines
+    };
+    let signed_root = likelihood_ratio.max(0f64).sqrt() * if k < expected_rounds_with_deadlines { -1f64 } else { 1f64 };
+    normal_cdf(-signed_root)
+}
+
+// returns (rounds over the expected median, comparable rounds), a round which didn't get a deadline is only
+// known to be over the median if the target deadline was
+fn count_rounds_over_expected_median(account_rounds: &[AccountRound], expected_nonces: f64) -> (usize, usize) {
+    let scaled_median = 2f64.ln() / expected_nonces;
+    let mut rounds_over_expected_median = 0;
+    let mut comparable_rounds = 0;
+    for account_round in account_rounds {
+        match (account_round.get_scaled_deadline(), account_round.get_scaled_target_deadline()) {
+            (Some(scaled_deadline), _) => {
+                comparable_rounds += 1;
+                if scaled_deadline > scaled_median {
+                    rounds_over_expected_median += 1;
+                }
+            }
+            (None, Some(scaled_target_deadline)) if scaled_target_deadline >= scaled_median => {
+                comparable_rounds += 1;
+                rounds_over_expected_median += 1;
+            }
+            _ => {}
+        };
+    }
+    (rounds_over_expected_median, comparable_rounds)
+}
+
+/// The plots size an account's deadlines should come from: its own plots size, or else what its miners report.
+pub fn get_expected_capacity(archon: &Archon, account_id: u64, now: DateTime<Local>) -> Option<(f64, String)> {
+    match capacity::get_account_capacity(archon, account_id) {
+        Some((capacity_tebibytes, capacity_source)) => Some((capacity_tebibytes, String::from(capacity_source.describe()))),
+        None => accounts::get_reported_account_capacity(archon, account_id, now)
+            .filter(|capacity_tebibytes| *capacity_tebibytes > 0f64)
+            .map(|capacity_tebibytes| (capacity_tebibytes, String::from("miners"))),
+    }
+}
+
+pub fn check_plot_health(archon: &Archon, account_id: u64, now: DateTime<Local>) -> PlotHealth {
+    let account_rounds = estimation::get_account_rounds(archon, account_id);
+    let effective_capacity = estimation::estimate_capacity(&account_rounds);
+    let expected_capacity = get_expected_capacity(archon, account_id, now);
+    let mut plot_health = PlotHealth {
+        account_id: super::censor_account_id(archon, account_id),
+        status: HealthStatus::NoExpectedCapacity,
+        expected_capacity_tebibytes: expected_capacity.as_ref().map(|(capacity_tebibytes, _)| *capacity_tebibytes),
+        expected_capacity_source: expected_capacity.as_ref().map(|(_, capacity_source)| capacity_source.clone()),
+        effective_capacity: effective_capacity.clone(),
+        performance: None,
+        confidence: None,
+        rounds_over_expected_median: 0,
+        comparable_rounds: 0,
+    };
+    let expected_capacity_tebibytes = match expected_capacity {
+        Some((capacity_tebibytes, _)) => capacity_tebibytes,
+        None => return plot_health,
+    };
+    let expected_nonces = expected_capacity_tebibytes * NONCES_PER_TEBIBYTE;
+    let (rounds_over_expected_median, comparable_rounds) = count_rounds_over_expected_median(&account_rounds, expected_nonces);
+    plot_health.rounds_over_expected_median = rounds_over_expected_median;
+    plot_health.comparable_rounds = comparable_rounds;
+    let effective_capacity = match effective_capacity {
+        Some(effective_capacity) => effective_capacity,
+        None => {
+            plot_health.status = HealthStatus::InsufficientData;
+            return plot_health;
+        }
+    };
+    let (_, rounds_with_deadlines, total_scaled_deadlines) = estimation::get_scaled_deadline_totals(&account_rounds);
+    let performance = effective_capacity.capacity_tebibytes / expected_capacity_tebibytes;
+    let confidence = get_shortfall_confidence(rounds_with_deadlines, total_scaled_deadlines, expected_nonces);
+    let status = if performance >= MIN_FLAGGED_SHORTFALL || confidence < SUSPECT_CONFIDENCE {
+        HealthStatus::Healthy
+    } else if confidence < UNHEALTHY_CONFIDENCE {
+        HealthStatus::Suspect
+    } else {
+        HealthStatus::Unhealthy
+    };
+    plot_health.status = status;
+    plot_health.performance = Some(performance);
+    plot_health.confidence = Some(confidence);
+    plot_health
+}
+
+/// Checks the plot health of every known account.
+pub fn get_plot_health(archon: &Archon, now: DateTime<Local>) -> Vec<PlotHealth> {
+    accounts::get_known_account_ids(archon)
+        .into_iter()
+        .map(|account_id| check_plot_health(archon, account_id, now))
+        .collect()
+}
+
+/// Prints the plot health for the plot-health command. The rounds are only known to a running Archon, so it's asked for them.
+pub fn print_plot_health_report(archon: &Archon) {
+    // only ever ask this machine's own Archon, the API token mustn't be sent anywhere else
+    let bind_address = archon.conf.web_server_bind_address.trim_start_matches('[').trim_end_matches(']');
+    let host = match bind_address.parse::<IpAddr>() {
+        Ok(IpAddr::V6(_)) => "[::1]",
+        _ => "127.0.0.1",
+    };
+    let url = format!("{}://{}:{}/api/v1/plot-health",
+        if tls::is_enabled(archon) { "https" } else { "http" },
+        host,
+        archon.conf.web_server_port
+    );
+    // with TLS the certificate is validated as usual, so it has to be valid for the loopback address
+    let client = match reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .build()
+    {
+        Ok(client) => client,
+        Err(why) => {
+            println!("  {} {}", "ERROR".red().underline(), format!("Couldn't create an HTTP client: {}", why).yellow());
+            return;
+        }
+    };
+    let mut request = client.get(&*url);
+    match archon.conf.api_tokens.as_ref().and_then(|api_tokens| api_tokens.first()) {
+        Some(api_token) => request = request.bearer_auth(&*api_token.token),
+        None => {}
+    };
+    let plot_health: Vec<PlotHealth> = match request.send().and_then(|response| response.error_for_status()?.json()) {
+        Ok(plot_health) => plot_health,
+        Err(why) => {
+            let hint = if tls::is_enabled(archon) {
+                format!("is it running, with a TLS certificate valid for {}?", host)
+            } else {
+                String::from("is it running?")
+            };
+            println!("  {} {}", "ERROR".red().underline(), format!("Couldn't get the plot health from Archon at {}, {} {}", url, hint, why).yellow());
+            return;
+        }
+    };
+    let now: DateTime<Local> = archon.now();
+    println!("  {} {}", "Plot Health Report".cyan().bold(), format!("- {}", now.format("%Y-%m-%d %H:%M:%S")).white());
+    println!();
+    println!("    {:<22} {:<18} {:>14} {:>28} {:>12} {:>11} {:>14}",
+        "Account".green(),
+        "Status".green(),
+        "Expected".green(),
+        "Effective (95% Interval)".green(),
+        "Performance".green(),
+        "Confidence".green(),
+        "Over Median".green()
+    );
+    for account in &plot_health {
+        let status = match account.status {
+            HealthStatus::Healthy => account.status.describe().green(),
+            HealthStatus::Suspect => account.status.describe().yellow(),
+            HealthStatus::Unhealthy => account.status.describe().red().bold(),
+            _ => account.status.describe().white(),
+        };
+        println!("    {:<22} {:<18} {:>14} {:>28} {:>12} {:>11} {:>14}",
+            account.account_id,
+            status,
+            account.expected_capacity_tebibytes.map_or(String::from("-"), |capacity_tebibytes| format!("{:.2} TiB", capacity_tebibytes)),
+            account.effective_capacity.as_ref().map_or(String::from("-"), |effective_capacity| format!("{:.2} TiB ({:.2}-{:.2})",
+                effective_capacity.capacity_tebibytes,
+                effective_capacity.low_tebibytes,
+                effective_capacity.high_tebibytes
+            )),
+            account.performance.map_or(String::from("-"), |performance| format!("{:.0}%", performance * 100f64)),
+            account.confidence.map_or(String::from("-"), |confidence| format!("{:.1}%", confidence * 100f64)),
+            format!("{}/{}", account.rounds_over_expected_median, account.comparable_rounds)
+        );
+    }
+    let flagged: Vec<&PlotHealth> = plot_health.iter().filter(|account| account.status.is_flagged()).collect();
+    if flagged.len() > 0 {
+        println!("\n    {}", "Accounts with deadlines worse than their plots size should get:".red());
+        for account in flagged {
+            println!("      {}", format!("{} is mining like {:.0}% of {:.2} TiB ({:.1}% confidence). Check for failing disks, corrupted or mis-plotted files, or plot directories its miners aren't reading.",
+                account.account_id,
+                account.performance.unwrap_or_default() * 100f64,
+                account.expected_capacity_tebibytes.unwrap_or_default(),
+                account.confidence.unwrap_or_default() * 100f64
+            ).yellow());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::estimation::tests::create_account_round;
+
+    // with a base target of 1 and 4 TiB, the expected median deadline is ln(2) * 2^40
+    const EXPECTED_NONCES: f64 = 4f64 * NONCES_PER_TEBIBYTE;
+
+    #[test]
+    fn normal_cdf_matches_known_values() {
+        assert!((normal_cdf(0f64) - 0.5).abs() < 1e-7);
+        assert!((normal_cdf(1.96) - 0.9750021).abs() < 1e-6);
+        assert!((normal_cdf(-1.96) - 0.0249979).abs() < 1e-6);
+        assert!((normal_cdf(1f64) + normal_cdf(-1f64) - 1f64).abs() < 1e-12);
+    }
+
+    #[test]
+    fn shortfall_confidence_is_even_at_the_expected_rate() {
+        // 10 deadlines averaging 1 / expected nonces are just what the expected capacity gets
+        let confidence = get_shortfall_confidence(10, 10f64 / EXPECTED_NONCES, EXPECTED_NONCES);
+        assert!((confidence - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn shortfall_confidence_follows_the_effective_rate() {
+        let half_rate = get_shortfall_confidence(10, 20f64 / EXPECTED_NONCES, EXPECTED_NONCES);
+        let double_rate = get_shortfall_confidence(10, 5f64 / EXPECTED_NONCES, EXPECTED_NONCES);
+        assert!(half_rate > UNHEALTHY_CONFIDENCE);
+        assert!(double_rate < 0.05);
+        assert!(get_shortfall_confidence(0, 10f64 / EXPECTED_NONCES, EXPECTED_NONCES) > UNHEALTHY_CONFIDENCE);
+    }
+
+    #[test]
+    fn counts_rounds_over_the_expected_median() {
+        let account_rounds = vec![
+            create_account_round(1, None, Some(1 << 39)),
+            create_account_round(2, None, Some(1 << 41)),
+            // missing a deadline with a target deadline over the median is a round over it
+            create_account_round(3, Some(1 << 41), None),
+            // but under the median it isn't known to be either
+            create_account_round(4, Some(1 << 39), None),
+            create_account_round(5, None, None),
+        ];
+        assert_eq!(count_rounds_over_expected_median(&account_rounds, EXPECTED_NONCES), (2, 3));
+    }
+
+    #[test]
+    fn health_status_serializes_in_camel_case() {
+        assert_eq!(serde_json::to_string(&HealthStatus::InsufficientData).unwrap(), "\"insufficientData\"");
+        assert_eq!(serde_json::from_str::<HealthStatus>("\"noExpectedCapacity\"").unwrap(), HealthStatus::NoExpectedCapacity);
+    }
+}
@@ -4,11 +4,9 @@ use ansi_term;
 use chrono::{DateTime, Local};
 use colored;
 use colored::Colorize;
-use std::collections::HashMap;
 use std::fs::File;
 use std::process::exit;
 use std::sync::Arc;
-use std::sync::Mutex;
 use std::thread;
 use fern;
 
@@ -18,74 +16,62 @@ extern crate serde_derive;
 #[macro_use]
 extern crate log;
 
-#[macro_use]
-extern crate lazy_static;
-
+pub mod access;
+pub mod accounts;
+pub mod api;
 pub mod arbiter;
+pub mod auth;
+pub mod capacity;
 pub mod config;
+pub mod context;
+pub mod control;
+pub mod dashboard;
 pub mod upstream;
 pub mod web;
 pub mod error;
+pub mod estimation;
+pub mod events;
+pub mod health;
+pub mod metrics;
+pub mod miners;
+pub mod plots;
+pub mod prediction;
+pub mod quarantine;
+pub mod rounds;
+pub mod schedule;
+pub mod tls;
+pub mod watchdog;
+use crate::config::ChainSchedule;
 use crate::config::Config;
+use crate::context::Archon;
 use crate::config::PocChain;
 use crate::upstream::MiningInfo;
 
 const APP_NAME: &'static str = env!("CARGO_PKG_NAME");
 const VERSION: &'static str = env!("CARGO_PKG_VERSION");
 
-lazy_static! {
-    static ref CHAIN_MINING_INFOS: Arc<Mutex<HashMap<u8, (MiningInfo, DateTime<Local>)>>> = {
-        let chain_mining_infos = HashMap::new();
-        Arc::new(Mutex::new(chain_mining_infos))
-    };
-    static ref MINING_INFO_CACHE: Arc<Mutex<HashMap<u8, (u32, String)>>> = {
-        let mining_info_cached_map = HashMap::new();
-        Arc::new(Mutex::new(mining_info_cached_map))
-    };
-    static ref BLOCK_START_PRINTED: Arc<Mutex<HashMap<u8, u32>>> = {
-        let block_start_printed_map = HashMap::new();
-        Arc::new(Mutex::new(block_start_printed_map))
-    };
-    // Key = block height, Value = tuple (account_id, best_deadline)
-    static ref BEST_DEADLINES: Arc<Mutex<HashMap<u32, Vec<(u64, u64)>>>> = {
-        let best_deadlines = HashMap::new();
-        Arc::new(Mutex::new(best_deadlines))
-    };
-    static ref CHAIN_QUEUE_STATUS: Arc<Mutex<HashMap<u8, (u32, DateTime<Local>)>>> = {
-        let chain_queue_status = HashMap::new();
-        Arc::new(Mutex::new(chain_queue_status))
-    };
-    static ref CURRENT_CHAIN_INDEX: Arc<Mutex<u8>> = Arc::new(Mutex::new(0u8));
-    static ref LAST_MINING_INFO: Arc<Mutex<String>> = Arc::new(Mutex::new(String::from("")));
-    static ref CHAIN_NONCE_SUBMISSION_CLIENTS: Arc<Mutex<HashMap<u8, reqwest::Client>>> = {
-        let chain_nonce_submission_clients = HashMap::new();
-        Arc::new(Mutex::new(chain_nonce_submission_clients))
-    };
-    #[derive(Serialize)]
-    pub static ref CONF: Config = {
-        let c: Config = match File::open("archon.yaml").map(|file| {
-            Config::parse_config(file).map_err(|why| {
-                println!("  {}", why);
-                query_create_default_config();
-                println!("\n  {}", "Execution completed. Press enter to exit.".red().underline());
-                let mut blah = String::new();
-                std::io::stdin().read_line(&mut blah).expect("FAIL");
-                exit(0);
-            })
-        }).map_err(|why| {
-            println!("  {} {}\n  {}", "ERROR".red().underline(), "An error was encountered while attempting to open the config file.".red(), why);
+/// Loads archon.yaml, offering to create a default config file if it's missing or invalid.
+fn load_config() -> Config {
+    match File::open("archon.yaml").map(|file| {
+        Config::parse_config(file).map_err(|why| {
+            println!("  {}", why);
             query_create_default_config();
             println!("\n  {}", "Execution completed. Press enter to exit.".red().underline());
             let mut blah = String::new();
             std::io::stdin().read_line(&mut blah).expect("FAIL");
-            exit(0)
-        }) {
-            Ok(data) => data.unwrap(),
-            Err(_) => { unreachable!(); },
-        };
-
-        c
-    };
+            exit(0);
+        })
+    }).map_err(|why| {
+        println!("  {} {}\n  {}", "ERROR".red().underline(), "An error was encountered while attempting to open the config file.".red(), why);
+        query_create_default_config();
+        println!("\n  {}", "Execution completed. Press enter to exit.".red().underline());
+        let mut blah = String::new();
+        std::io::stdin().read_line(&mut blah).expect("FAIL");
+        exit(0)
+    }) {
+        Ok(data) => data.unwrap(),
+        Err(_) => { unreachable!(); },
+    }
 }
 
 fn main() {
@@ -94,8 +80,24 @@ fn main() {
 
     let app_name = uppercase_first(APP_NAME);
 
+    let archon = Arc::new(Archon::new(load_config()));
+    let archon = &archon;
+
+    // "archon plots" prints what's in the plot directories, "archon plot-health" asks the running Archon how healthy they look
+    match std::env::args().nth(1).as_ref().map(|arg| arg.as_str()) {
+        Some("plots") => {
+            plots::print_plot_report(archon);
+            return;
+        }
+        Some("plot-health") => {
+            health::print_plot_health_report(archon);
+            return;
+        }
+        _ => {}
+    };
+
     // setup logging
-    let console_logging_info = setup_logging();
+    let console_logging_info = setup_logging(archon);
 
     info!("{} v{} started", app_name, VERSION);
 
@@ -117,30 +119,37 @@ fn main() {
         "Thanks guys <3".magenta(),
     );
 
-    if crate::CONF.poc_chains.is_some() {
+    if archon.conf.poc_chains.is_some() {
         println!("{}", console_logging_info);
 
         println!("  {} {} {}",
-            get_time().white(),
+            get_time(archon).white(),
             "Config:".red(),
             format!("{} {}",
                 "Web Server Binding:".green(),
-                format!("http://{}:{}",
-                    crate::CONF.web_server_bind_address,
-                    crate::CONF.web_server_port
+                format!("{}://{}:{}",
+                    if tls::is_enabled(archon) { "https" } else { "http" },
+                    archon.conf.web_server_bind_address,
+                    archon.conf.web_server_port
                 )
                 .yellow()
             )
         );
-        if crate::CONF.priority_mode.unwrap_or(true) {
+        if arbiter::is_fair_share_mode(archon) {
+            println!("  {} {} {}",
+                get_time(archon).white(),
+                "Config:".red(),
+                format!("{} {}", "Queuing Mode:".green(), "Fair Share".yellow())
+            );
+        } else if archon.conf.priority_mode.unwrap_or(true) {
             println!("  {} {} {}",
-                get_time().white(),
+                get_time(archon).white(),
                 "Config:".red(),
                 format!("{} {}", "Queuing Mode:".green(), "Priority".yellow())
             );
-            if crate::CONF.interrupt_lower_priority_blocks.unwrap_or(true) {
+            if archon.conf.interrupt_lower_priority_blocks.unwrap_or(true) {
                 println!("  {} {} {}",
-                    get_time().white(),
+                    get_time(archon).white(),
                     "Config:".red(),
                     format!("{} {}",
                         "Interrupt Lower Priority Blocks:".green(),
@@ -150,7 +159,7 @@ fn main() {
             } else {
                 // interrupt lower priority blocks off
                 println!("  {} {} {}",
-                    get_time().white(),
+                    get_time(archon).white(),
                     "Config:".red(),
                     format!("{} {}",
                         "Interrupt Lower Priority Blocks:".green(),
@@ -160,7 +169,7 @@ fn main() {
             }
         } else {
             println!("  {} {} {}",
-                get_time().white(),
+                get_time(archon).white(),
                 "Config:".red(),
                 format!("{} {}",
                     "Queuing Mode:".green(),
@@ -169,39 +178,88 @@ fn main() {
             );
         }
         println!("  {} {} {}",
-            get_time().white(),
+            get_time(archon).white(),
             "Config:".red(),
             format!("{} {}",
                 "Grace Period:".green(),
-                format!("{} seconds", crate::CONF.grace_period).yellow()
+                format!("{} seconds", archon.conf.grace_period).yellow()
             )
         );
-        let total_plots_size_tebibytes = get_total_plots_size_in_tebibytes();
+        let total_plots_size_tebibytes = get_total_plots_size_in_tebibytes(archon);
+        let capacity_source = archon.conf.plot_capacity_source
+            .as_ref()
+            .and_then(|source| capacity::CapacitySource::parse(&*source));
+        let uses_reported_capacity = capacity_source == Some(capacity::CapacitySource::Miners) || capacity_source == Some(capacity::CapacitySource::Plots);
         let plots_zero_warning;
-        if total_plots_size_tebibytes == 0f64 {
+        if capacity_source == Some(capacity::CapacitySource::Miners) {
+            plots_zero_warning = " (Only used until miners report their capacity)";
+        } else if capacity_source == Some(capacity::CapacitySource::Plots) {
+            plots_zero_warning = " (Only used until plot files are found)";
+        } else if total_plots_size_tebibytes == 0f64 && (archon.conf.numeric_id_to_plots_size_in_tebibytes.is_some() || plots::has_plot_directories(archon)) {
+            plots_zero_warning = " (Dynamic deadlines are only used for accounts with their own plots size)";
+        } else if total_plots_size_tebibytes == 0f64 {
             plots_zero_warning = " (Warning: Dynamic deadlines require an accurate plot size. Dynamic Deadlines are disabled.)";
         } else {
             plots_zero_warning = "";
         }
         println!("  {} {} {}",
-            get_time().white(),
+            get_time(archon).white(),
             "Config:".red(),
             format!("{} {}{}",
                 "Total Plots Size:".green(),
                 format!("{} TiB", total_plots_size_tebibytes).yellow(),
-                plots_zero_warning.red(),
+                if uses_reported_capacity { plots_zero_warning.yellow() } else { plots_zero_warning.red() },
             )
         );
+        for source in archon.conf.plot_capacity_source.iter().chain(archon.conf.poc_chains.iter().flatten().filter_map(|chain| chain.plot_capacity_source.as_ref())) {
+            if capacity::CapacitySource::parse(&*source).is_none() {
+                println!("  {} {}", get_time(archon).white(), format!("WARNING \"{}\" is not a valid plotCapacitySource, it should be config, miners or plots. The config will be used.", source).yellow());
+                warn!("Invalid plotCapacitySource \"{}\"", source);
+            }
+        }
+        if plots::has_plot_directories(archon) {
+            plots::rescan_plot_directories(archon);
+            let plot_scan = plots::get_plot_scan(archon).expect("The plot directories were just scanned");
+            println!("  {} {} {}",
+                get_time(archon).white(),
+                "Config:".red(),
+                format!("{} {}",
+                    "Plot Directories:".green(),
+                    format!("{} Directories, {} Plot Files, {} Accounts, {:.2} TiB",
+                        plot_scan.directories.len(),
+                        plot_scan.get_num_files(),
+                        plot_scan.accounts.len(),
+                        plot_scan.total_capacity_tebibytes
+                    ).yellow()
+                )
+            );
+        } else if capacity_source == Some(capacity::CapacitySource::Plots) {
+            println!("  {} {}", get_time(archon).white(), "WARNING plotCapacitySource is plots, but no plotDirectories are configured. The config will be used.".yellow());
+            warn!("plotCapacitySource is plots, but no plotDirectories are configured");
+        }
+        let account_capacities: Vec<String> = accounts::get_known_account_ids(archon)
+            .into_iter()
+            .filter_map(|account_id| capacity::get_account_capacity(archon, account_id).map(|(capacity_tebibytes, capacity_source)| {
+                format!("{} = {:.2} TiB ({})", censor_account_id(archon, account_id), capacity_tebibytes, uppercase_first(capacity_source.describe()))
+            }))
+            .collect();
+        if account_capacities.len() > 0 {
+            println!("  {} {} {}",
+                get_time(archon).white(),
+                "Config:".red(),
+                format!("{} {}", "Account Plots Sizes:".green(), account_capacities.join(", ").yellow())
+            );
+        }
         println!("  {} {} {}",
-            get_time().white(),
+            get_time(archon).white(),
             "Config:".red(),
             "PoC Chains:".green()
         );
         let mut chain_counter = 0u8;
-        let mut multiple_same_priority_chains = false;
+        let mut shared_priority_tiers = false;
         let mut unused_passphrase_warnings = String::from("");
         let mut account_key_warnings = String::from("");
-        for inner in &crate::CONF.poc_chains {
+        for inner in &archon.conf.poc_chains {
             for chain in inner {
                 if chain.is_hdpool.unwrap_or_default() && chain.is_hpool.unwrap_or_default() {
                     // fatal error - can't have chain defined as for both HPOOL and HDPOOL
@@ -243,79 +301,99 @@ fn main() {
                     }
                 }
                 if chain.enabled.unwrap_or(true) {
-                    if get_num_chains_with_priority(chain.priority) > 1 {
-                        multiple_same_priority_chains = true;
+                    if get_num_chains_with_priority(archon, chain.priority) > 1 {
+                        shared_priority_tiers = true;
                     }
                     chain_counter += 1;
                     let chain_tdl = chain.target_deadline.unwrap_or_default();
                     let mut human_readable_target_deadline = String::from("");
-                    if crate::CONF
+                    if archon.conf
                         .show_human_readable_deadlines
                         .unwrap_or_default()
                     {
                         human_readable_target_deadline =
-                            format!(" ({})", format_timespan(chain_tdl));
+                            format!(" ({})", format_timespan(archon, chain_tdl));
                     }
                     let chain_tdl_str;
-                    if chain.use_dynamic_deadlines.unwrap_or_default() {
+                    if chain.use_dynamic_deadlines.unwrap_or_default() && capacity::get_capacity_source(archon, chain) == capacity::CapacitySource::Miners {
+                        chain_tdl_str = String::from("Dynamic (Reported Capacity)");
+                    } else if chain.use_dynamic_deadlines.unwrap_or_default() && capacity::get_capacity_source(archon, chain) == capacity::CapacitySource::Plots {
+                        chain_tdl_str = String::from("Dynamic (Scanned Plots)");
+                    } else if chain.use_dynamic_deadlines.unwrap_or_default() {
                         chain_tdl_str = String::from("Dynamic");
                     } else if chain_tdl == 0 {
                         chain_tdl_str = String::from("None");
                     } else {
                         chain_tdl_str = format!("{}{}", chain_tdl, human_readable_target_deadline);
                     }
-                    if crate::CONF.priority_mode.unwrap_or(true) {
-                        if crate::CONF.interrupt_lower_priority_blocks.unwrap_or(true) {
+                    if arbiter::is_fair_share_mode(archon) {
+                        println!("  {} {}  {} {}",
+                            get_time(archon).white(),
+                            "Config:".red(),
+                            format!("#{}:", chain_counter).green(),
+                            format!("{} {} {} {} {} {} {} {}",
+                                "Share:".color(get_color(archon, &*chain.color)).bold(),
+                                format!("{:.1}%", arbiter::get_target_mining_share(archon, chain) * 100f64).color(get_color(archon, &*chain.color)),
+                                "Name:".color(get_color(archon, &*chain.color)).bold(),
+                                format!("{}", &*chain.name).color(get_color(archon, &*chain.color)),
+                                "TDL:".color(get_color(archon, &*chain.color)).bold(),
+                                format!("{}", chain_tdl_str).color(get_color(archon, &*chain.color)),
+                                "URL:".color(get_color(archon, &*chain.color)).bold(),
+                                format!("{}", &*chain.url).color(get_color(archon, &*chain.color)),
+                            )
+                        );
+                    } else if archon.conf.priority_mode.unwrap_or(true) {
+                        if archon.conf.interrupt_lower_priority_blocks.unwrap_or(true) {
                             let mut requeue_str = "Yes";
                             if !chain.requeue_interrupted_blocks.unwrap_or(true) {
                                 requeue_str = "No";
                             }
                             println!("  {} {}  {} {}",
-                                get_time().white(),
+                                get_time(archon).white(),
                                 "Config:".red(),
                                 format!("#{}:", chain_counter).green(),
                                 format!("{} {} {} {} {} {} {} {} {} {}",
-                                    "Priority:".color(get_color(&*chain.color)).bold(),
-                                    format!("{}", &chain.priority).color(get_color(&*chain.color)),
-                                    "Name:".color(get_color(&*chain.color)).bold(),
-                                    format!("{}", &*chain.name).color(get_color(&*chain.color)),
-                                    "TDL:".color(get_color(&*chain.color)).bold(),
-                                    format!("{}", chain_tdl_str).color(get_color(&*chain.color)),
-                                    "URL:".color(get_color(&*chain.color)).bold(),
-                                    format!("{}", &*chain.url).color(get_color(&*chain.color)),
-                                    "Requeue:".color(get_color(&*chain.color)).bold(),
-                                    format!("{}", requeue_str).color(get_color(&*chain.color)),
+                                    "Priority:".color(get_color(archon, &*chain.color)).bold(),
+                                    format!("{}", &chain.priority).color(get_color(archon, &*chain.color)),
+                                    "Name:".color(get_color(archon, &*chain.color)).bold(),
+                                    format!("{}", &*chain.name).color(get_color(archon, &*chain.color)),
+                                    "TDL:".color(get_color(archon, &*chain.color)).bold(),
+                                    format!("{}", chain_tdl_str).color(get_color(archon, &*chain.color)),
+                                    "URL:".color(get_color(archon, &*chain.color)).bold(),
+                                    format!("{}", &*chain.url).color(get_color(archon, &*chain.color)),
+                                    "Requeue:".color(get_color(archon, &*chain.color)).bold(),
+                                    format!("{}", requeue_str).color(get_color(archon, &*chain.color)),
                                 )
                             );
                         } else {
                             println!("  {} {}  {} {}",
-                                get_time().white(),
+                                get_time(archon).white(),
                                 "Config:".red(),
                                 format!("#{}:", chain_counter).green(),
                                 format!("{} {} {} {} {} {} {} {}",
-                                    "Priority:".color(get_color(&*chain.color)).bold(),
-                                    format!("{}", &chain.priority).color(get_color(&*chain.color)),
-                                    "Name:".color(get_color(&*chain.color)).bold(),
-                                    format!("{}", &*chain.name).color(get_color(&*chain.color)),
-                                    "TDL:".color(get_color(&*chain.color)).bold(),
-                                    format!("{}", chain_tdl_str).color(get_color(&*chain.color)),
-                                    "URL:".color(get_color(&*chain.color)).bold(),
-                                    format!("{}", &*chain.url).color(get_color(&*chain.color)),
+                                    "Priority:".color(get_color(archon, &*chain.color)).bold(),
+                                    format!("{}", &chain.priority).color(get_color(archon, &*chain.color)),
+                                    "Name:".color(get_color(archon, &*chain.color)).bold(),
+                                    format!("{}", &*chain.name).color(get_color(archon, &*chain.color)),
+                                    "TDL:".color(get_color(archon, &*chain.color)).bold(),
+                                    format!("{}", chain_tdl_str).color(get_color(archon, &*chain.color)),
+                                    "URL:".color(get_color(archon, &*chain.color)).bold(),
+                                    format!("{}", &*chain.url).color(get_color(archon, &*chain.color)),
                                 )
                             );
                         }
                     } else {
                         println!("  {} {}  {} {}",
-                            get_time().white(),
+                            get_time(archon).white(),
                             "Config:".red(),
                             format!("#{}:", chain_counter).green(),
                             format!("{} {} {} {} {} {}",
-                                "Name:".color(get_color(&*chain.color)).bold(),
-                                format!("{}", &*chain.name).color(get_color(&*chain.color)),
-                                "TDL:".color(get_color(&*chain.color)).bold(),
-                                format!("{}", chain_tdl_str).color(get_color(&*chain.color)),
-                                "URL:".color(get_color(&*chain.color)).bold(),
-                                format!("{}", &*chain.url).color(get_color(&*chain.color)),
+                                "Name:".color(get_color(archon, &*chain.color)).bold(),
+                                format!("{}", &*chain.name).color(get_color(archon, &*chain.color)),
+                                "TDL:".color(get_color(archon, &*chain.color)).bold(),
+                                format!("{}", chain_tdl_str).color(get_color(archon, &*chain.color)),
+                                "URL:".color(get_color(archon, &*chain.color)).bold(),
+                                format!("{}", &*chain.url).color(get_color(archon, &*chain.color)),
                             )
                         );
                     }
@@ -323,24 +401,13 @@ fn main() {
             }
         }
 
-        if chain_counter == 0
-            || (CONF.priority_mode.unwrap_or(true) && multiple_same_priority_chains)
-        {
-            if chain_counter == 0 {
-                println!("  {} {} {}",
-                    get_time().white(),
-                    "ERROR".red().underline(),
-                    "You do not have any PoC Chains enabled. Archon has nothing to do!".yellow()
-                );
-                error!("There are no PoC Chains configured.");
-            } else {
-                println!("  {} {} {}",
-                    get_time().white(),
-                    "ERROR".red().underline(),
-                    "You have multiple chains configured with the same priority level! Priorities must be unique!".yellow()
-                );
-                error!("Multiple PoC Chains are configured with the same priority level. Priority levels must be unique.");
-            }
+        if chain_counter == 0 {
+            println!("  {} {} {}",
+                get_time(archon).white(),
+                "ERROR".red().underline(),
+                "You do not have any PoC Chains enabled. Archon has nothing to do!".yellow()
+            );
+            error!("There are no PoC Chains configured.");
 
             println!("\n  {}",
                 "Execution completed. Press enter to exit."
@@ -352,6 +419,25 @@ fn main() {
             std::io::stdin().read_line(&mut blah).expect("FAIL");
             exit(0);
         }
+        if arbiter::is_priority_mode(archon) && shared_priority_tiers {
+            println!("  {} {} {}",
+                get_time(archon).white(),
+                "Config:".red(),
+                format!("{} {}", "Tie Break Policy:".green(), arbiter::get_tie_break_policy(archon).describe().yellow())
+            );
+        }
+        for chain in archon.conf.poc_chains.iter().flatten() {
+            if !chain.enabled.unwrap_or(true) && chain.schedule.is_some() {
+                println!("  {} {}", get_time(archon).white(), format!("WARNING {} is disabled, its schedule will be ignored. Enable the chain and use windows with \"enabled: true\" to only mine it at certain times.", &*chain.name).yellow());
+                warn!("Schedule of disabled chain {} ignored", &*chain.name);
+            }
+        }
+        for policy in archon.conf.tie_break_policy.iter() {
+            if arbiter::TieBreakPolicy::parse(&*policy).is_none() {
+                println!("  {} {}", get_time(archon).white(), format!("WARNING \"{}\" is not a valid tieBreakPolicy, it should be roundRobin, fifo or expectedValue. Round robin will be used.", policy).yellow());
+                warn!("Invalid tieBreakPolicy \"{}\"", policy);
+            }
+        }
         if unused_passphrase_warnings.len() > 0 {
             let border = String::from("------------------------------------------------------------------------------------------");
             println!("{}\n  {}\n{}\n{}\n      {}\n{}",
@@ -371,11 +457,11 @@ fn main() {
 
         let valid_colors = ["green", "yellow", "blue", "magenta", "cyan", "white"];
         let mut invalid_color_found = false;
-        for inner in &crate::CONF.poc_chains {
+        for inner in &archon.conf.poc_chains {
             for chain in inner {
                 if chain.enabled.unwrap_or(true) {
                     if !valid_colors.contains(&&*chain.color) {
-                        println!("  {} {}", get_time().white(), format!("WARNING The {} chain uses the color \"{}\" which is invalid. Will pick a random valid color.", &*chain.name, &*chain.color).yellow());
+                        println!("  {} {}", get_time(archon).white(), format!("WARNING The {} chain uses the color \"{}\" which is invalid. Will pick a random valid color.", &*chain.name, &*chain.color).yellow());
                         invalid_color_found = true;
                     }
                 }
@@ -390,32 +476,184 @@ fn main() {
             valid_colors_str.truncate(valid_colors_str.len() - 1);
             println!(
                 "  {} {}",
-                get_time().white(),
+                get_time(archon).white(),
                 format!("Valid colors: {}", valid_colors_str)
             );
         }
 
-        // start mining info polling thread
-        println!("  {} {}", get_time().white(), "Starting upstream mining info polling thread.");
+        // check schedule windows are valid before they're applied
+        for inner in &archon.conf.poc_chains {
+            for chain in inner {
+                if chain.enabled.unwrap_or(true) {
+                    for schedules in &chain.schedule {
+                        for schedule in schedules {
+                            match schedule.validate() {
+                                Ok(_) => {}
+                                Err(why) => {
+                                    println!("  {} {}", get_time(archon).white(), format!("WARNING The {} chain has a schedule [{}] which will be ignored: {}", &*chain.name, schedule.describe(), why).yellow());
+                                    warn!("Chain \"{}\" has an invalid schedule [{}]: {}", &*chain.name, schedule.describe(), why);
+                                }
+                            };
+                        }
+                    }
+                }
+            }
+        }
+        // check API tokens before the web server starts accepting requests
+        let mut num_api_tokens = 0;
+        for api_tokens in &archon.conf.api_tokens {
+            for api_token in api_tokens {
+                num_api_tokens += 1;
+                let scope = api_token.scope.clone().unwrap_or(String::from("read"));
+                if auth::ApiScope::parse(&*scope).is_none() {
+                    let name = api_token.name.clone().unwrap_or(String::from("Unnamed"));
+                    println!("  {} {}", get_time(archon).white(), format!("WARNING The API token \"{}\" has the scope \"{}\" which is invalid. It will only be given read access.", name, scope).yellow());
+                    warn!("API token \"{}\" has an invalid scope \"{}\"", name, scope);
+                }
+            }
+        }
+        let api_access = match (num_api_tokens, archon.conf.api_loopback_only.unwrap_or_default()) {
+            (0, false) => String::from("Open"),
+            (0, true) => String::from("Host Machine Only"),
+            (n, false) => format!("{} Token(s)", n),
+            (n, true) => format!("{} Token(s), Host Machine Only", n),
+        };
+        println!("  {} {} {}",
+            get_time(archon).white(),
+            "Config:".red(),
+            format!("{} {}", "API Access:".green(), api_access.yellow())
+        );
+        // check the miner groups, an address which can't be parsed would otherwise be silently ignored
+        let mut num_miner_groups = 0;
+        for miner_groups in &archon.conf.miner_groups {
+            for miner_group in miner_groups {
+                num_miner_groups += 1;
+                for addresses in &miner_group.addresses {
+                    for address in addresses {
+                        if access::parse_network(address).is_none() {
+                            println!("  {} {}", get_time(archon).white(), format!("WARNING The miner group \"{}\" has the address \"{}\" which is not a valid IP address or CIDR range. It will be ignored.", miner_group.name, address).yellow());
+                            warn!("Miner group \"{}\" has an invalid address \"{}\"", miner_group.name, address);
+                        }
+                    }
+                }
+                if miner_group.addresses.as_ref().map_or(true, |addresses| addresses.len() == 0) && miner_group.secret.is_none() {
+                    println!("  {} {}", get_time(archon).white(), format!("WARNING The miner group \"{}\" has no addresses and no secret, so any miner can connect.", miner_group.name).yellow());
+                    warn!("Miner group \"{}\" lets in any miner", miner_group.name);
+                }
+            }
+        }
+        let miner_access = match (num_miner_groups, archon.conf.miner_submission_rate_limit.unwrap_or_default()) {
+            (0, 0) => String::from("Open"),
+            (0, limit) => format!("Open, Max {} Submissions/Minute Per Address", limit),
+            (n, 0) => format!("{} Group(s)", n),
+            (n, limit) => format!("{} Group(s), Max {} Submissions/Minute Per Address", n, limit),
+        };
+        println!("  {} {} {}",
+            get_time(archon).white(),
+            "Config:".red(),
+            format!("{} {}", "Miner Access:".green(), miner_access.yellow())
+        );
+        let max_missed_rounds = watchdog::get_max_missed_rounds(archon);
+        let poll_timeout = watchdog::get_poll_timeout(archon);
+        let miner_watchdog = match (max_missed_rounds, poll_timeout) {
+            (0, 0) => String::from("Disabled"),
+            (rounds, 0) => format!("Alert After {} Rounds Without Deadlines", rounds),
+            (0, seconds) => format!("Alert After {}s Without Polling", seconds),
+            (rounds, seconds) => format!("Alert After {} Rounds Without Deadlines Or {}s Without Polling", rounds, seconds),
+        };
+        println!("  {} {} {}",
+            get_time(archon).white(),
+            "Config:".red(),
+            format!("{} {}", "Miner Watchdog:".green(), miner_watchdog.yellow())
+        );
+        // check the TLS settings, both a certificate and a private key are needed to enable it
+        if archon.conf.tls_certificate_file.is_some() != archon.conf.tls_private_key_file.is_some() {
+            println!("  {} {}", get_time(archon).white(), "WARNING Only one of tlsCertificateFile and tlsPrivateKeyFile is set. TLS will stay disabled until both are configured.".yellow());
+            warn!("Only one of tlsCertificateFile and tlsPrivateKeyFile is set, TLS is disabled");
+        } else if !tls::is_enabled(archon) && archon.conf.tls_client_ca_file.is_some() {
+            println!("  {} {}", get_time(archon).white(), "WARNING tlsClientCaFile is set but TLS is not enabled. Miners will not be asked for client certificates.".yellow());
+            warn!("tlsClientCaFile is set but TLS is not enabled");
+        }
+        let tls_thread = match tls::is_enabled(archon) {
+            true => {
+                println!("  {} {} {}",
+                    get_time(archon).white(),
+                    "Config:".red(),
+                    format!("{} {}", "TLS:".green(), match tls::requires_client_certificates(archon) {
+                        true => "Enabled, Client Certificates Required For Miners",
+                        false => "Enabled",
+                    }.yellow())
+                );
+                let archon = archon.clone();
+                Some(thread::spawn(move || {
+                    tls::thread_watch_certificates(archon);
+                }))
+            }
+            false => None,
+        };
+        // start schedule thread
+        let schedule_thread = match schedule::has_schedules(archon) {
+            true => {
+                let archon = archon.clone();
+                Some(thread::spawn(move || {
+                    schedule::thread_apply_schedules(archon);
+                }))
+            }
+            false => None,
+        };
+        // start plot scanning thread
+        let plots_thread = match plots::has_plot_directories(archon) {
+            true => {
+                let archon = archon.clone();
+                Some(thread::spawn(move || {
+                    plots::thread_scan_plots(archon);
+                }))
+            }
+            false => None,
+        };
+        // start miner watchdog thread
+        let watchdog_thread = match poll_timeout > 0 {
+            true => {
+                let archon = archon.clone();
+                Some(thread::spawn(move || {
+                    watchdog::thread_watch_miners(archon);
+                }))
+            }
+            false => None,
+        };
+        // start mining info polling and arbiter thread
+        println!("  {} {}", get_time(archon).white(), "Starting upstream mining info polling thread.");
+        let arbiter_archon = archon.clone();
         let mi_thread = thread::spawn(move || {
-            arbiter::thread_arbitrate();
-        });
-        // start queue processing thread
-        let queue_proc_thread = thread::spawn(move || {
-            arbiter::thread_arbitrate_queue();
+            arbiter::thread_arbitrate(arbiter_archon);
         });
         // start version check thread
         let version_check_thread = thread::spawn(move || {
             thread_check_latest_githib_version();
         });
 
-        println!("  {} {}", get_time().white(), "Starting web server.".green() );
-        web::start_server();
+        println!("  {} {}", get_time(archon).white(), "Starting web server.".green() );
+        web::start_server(archon.clone());
         mi_thread.join().expect("Failed to join mining info thread.");
-        queue_proc_thread.join().expect("Failed to join queue processing thread.");
         version_check_thread.join().expect("Failed to join version check thread.");
+        match schedule_thread {
+            Some(schedule_thread) => schedule_thread.join().expect("Failed to join schedule thread."),
+            None => {}
+        };
+        match watchdog_thread {
+            Some(watchdog_thread) => watchdog_thread.join().expect("Failed to join miner watchdog thread."),
+            None => {}
+        };
+        match tls_thread {
+            Some(tls_thread) => tls_thread.join().expect("Failed to join certificate watch thread."),
+            None => {}
+        };
+        match plots_thread {
+            Some(plots_thread) => plots_thread.join().expect("Failed to join plot scanning thread."),
+            None => {}
+        };
     } else {
-        println!("  {} {} {}", get_time().white(), "ERROR".red().underline(), "You do not have any PoC Chains configured. Archon has nothing to do!".yellow());
+        println!("  {} {} {}", get_time(archon).white(), "ERROR".red().underline(), "You do not have any PoC Chains configured. Archon has nothing to do!".yellow());
     }
 
     println!("\n  {}", "Execution completed. Press enter to exit." .red().underline());
@@ -434,8 +672,8 @@ fn setup_ansi_support() {
 #[cfg(not(target_os = "windows"))]
 fn setup_ansi_support() {}
 
-fn setup_logging() -> String {
-    let logging_level = CONF.logging_level.clone().unwrap_or(String::from("info")).to_lowercase();
+fn setup_logging(archon: &Arc<Archon>) -> String {
+    let logging_level = archon.conf.logging_level.clone().unwrap_or(String::from("info")).to_lowercase();
     let logging_level_warning;
     let log_level = match logging_level.as_str() {
         "off" => {
@@ -468,7 +706,7 @@ fn setup_logging() -> String {
         },
     };
     let console_logging_message = format!("  {} {} {}",
-        get_time().white(),
+        get_time(archon).white(),
         "Config:".red(),
         format!("{} {}{}",
             "Logging Level:".green(),
@@ -480,7 +718,7 @@ fn setup_logging() -> String {
         // create logs directory
         if std::fs::create_dir("logs").is_ok() {}
         // grab number of files to keep in rotation from loaded config
-        let num_old_files = CONF.num_old_log_files_to_keep.unwrap_or(5);
+        let num_old_files = archon.conf.num_old_log_files_to_keep.unwrap_or(5);
         if num_old_files > 0 { // if 0 Archon will just keep overwriting the same file
             if num_old_files > 1 {
                 // do rotation
@@ -493,11 +731,12 @@ fn setup_logging() -> String {
         }
         match fern::log_file(format!("logs/{}.log", APP_NAME)) {
             Ok(log_file) => {
+                let log_archon = archon.clone();
                 match fern::Dispatch::new()
                     .format(move |out, message, record| {
                     out.finish(format_args!(
                         "{time}   [{level:level_width$}] {target:target_width$}\t> {msg}",
-                        time = Local::now().format("%Y-%m-%d %H:%M:%S"),
+                        time = log_archon.now().format("%Y-%m-%d %H:%M:%S"),
                         level = record.level(),
                         target = record.target(),
                         msg = message,
@@ -579,9 +818,9 @@ fn uppercase_first(s: &str) -> String {
     }
 }
 
-fn get_color(col: &str) -> &str {
+fn get_color<'a>(archon: &Archon, col: &'a str) -> &'a str {
     // if using poc chain colors is disabled in config, return white here
-    if !crate::CONF.use_poc_chain_colors.unwrap_or(true) {
+    if !archon.conf.use_poc_chain_colors.unwrap_or(true) {
         return "white";
     }
     use rand::seq::SliceRandom;
@@ -593,9 +832,9 @@ fn get_color(col: &str) -> &str {
     return &col;
 }
 
-fn get_cached_mining_info() -> Option<(u8, u32, String)> {
-    let cache_map = MINING_INFO_CACHE.lock().unwrap();
-    let index = CURRENT_CHAIN_INDEX.lock().unwrap();
+fn get_cached_mining_info(archon: &Archon) -> Option<(u8, u32, String)> {
+    let cache_map = archon.state.mining_info_cache.lock().unwrap();
+    let index = archon.state.current_chain_index.lock().unwrap();
     match cache_map.get(&index) {
         Some((height, json)) => {
             Some((*index, *height, json.clone()))
@@ -604,16 +843,16 @@ fn get_cached_mining_info() -> Option<(u8, u32, String)> {
     }
 }
 
-fn add_mining_info_to_cache(index: u8, mining_info: MiningInfo) -> String {
-    let mut cache_map = MINING_INFO_CACHE.lock().unwrap();
+fn add_mining_info_to_cache(archon: &Archon, index: u8, mining_info: MiningInfo) -> String {
+    let mut cache_map = archon.state.mining_info_cache.lock().unwrap();
     let mining_info_json = mining_info.to_json().to_string();
     debug!("ADD CACHE - Chain #{} Block #{}: {:?}", index, mining_info.height, mining_info);
     cache_map.insert(index, (mining_info.height, mining_info_json.clone()));
     mining_info_json
 }
 
-fn is_block_start_printed(index: u8, height: u32) -> bool {
-    let block_start_printed_map = BLOCK_START_PRINTED.lock().unwrap();
+fn is_block_start_printed(archon: &Archon, index: u8, height: u32) -> bool {
+    let block_start_printed_map = archon.state.block_start_printed.lock().unwrap();
     match block_start_printed_map.get(&index) {
         Some(matched_height) => {
             debug!("IsBlockStartPrinted - Chain #{} Block #{} = {} [Matched Height={}]", index, height, *matched_height == height, *matched_height);
@@ -627,27 +866,27 @@ fn is_block_start_printed(index: u8, height: u32) -> bool {
 }
 
 /// Return previously cached mining info if present, or create a cache for current mining info and return that
-fn get_current_mining_info_json() -> String {
-    match get_cached_mining_info() {
+fn get_current_mining_info_json(archon: &Archon) -> String {
+    match get_cached_mining_info(archon) {
         Some((index, height, mining_info_json)) => {
             // check if block start has been printed for this index & height
             //   TRUE: Go ahead and return the mining info
             //  FALSE: Return the previous mining info
-            if is_block_start_printed(index, height) {
+            if is_block_start_printed(archon, index, height) {
                 mining_info_json.clone()
             } else {
-                match get_current_mining_info() {
+                match get_current_mining_info(archon) {
                     Some(mining_info) => {
-                        if is_block_start_printed(index, mining_info.height) {
-                            add_mining_info_to_cache(index, mining_info.clone())
+                        if is_block_start_printed(archon, index, mining_info.height) {
+                            add_mining_info_to_cache(archon, index, mining_info.clone())
                         } else {
-                            let last_mining_info = LAST_MINING_INFO.lock().unwrap();
+                            let last_mining_info = archon.state.last_mining_info.lock().unwrap();
                             debug!("Chain #{} Block #{} is not printed to console yet, sending last mining info: {}", index, height, last_mining_info.clone());
                             last_mining_info.clone()
                         }
                     },
                     _ => {
-                        let last_mining_info = LAST_MINING_INFO.lock().unwrap();
+                        let last_mining_info = archon.state.last_mining_info.lock().unwrap();
                         info!("Chain #{} Block #{} is not printed to console yet, sending last mining info: {}", index, height, last_mining_info.clone());
                         last_mining_info.clone()
                     }
@@ -655,19 +894,19 @@ fn get_current_mining_info_json() -> String {
             }
         },
         None => {
-            let chain_map = CHAIN_MINING_INFOS.lock().unwrap();
-            let index = CURRENT_CHAIN_INDEX.lock().unwrap();
+            let chain_map = archon.state.chain_mining_infos.lock().unwrap();
+            let index = archon.state.current_chain_index.lock().unwrap();
             match chain_map.get(&index) {
-                Some((mining_info, _)) => add_mining_info_to_cache(*index, mining_info.clone()),
+                Some((mining_info, _)) => add_mining_info_to_cache(archon, *index, mining_info.clone()),
                 None => r#"{"result":"failure","reason":"Haven't found any mining info!"}"#.to_string(),
             }
         }
     }
 }
 
-fn get_current_mining_info() -> Option<MiningInfo> {
-    let chain_map = CHAIN_MINING_INFOS.lock().unwrap();
-    let index = CURRENT_CHAIN_INDEX.lock().unwrap();
+fn get_current_mining_info(archon: &Archon) -> Option<MiningInfo> {
+    let chain_map = archon.state.chain_mining_infos.lock().unwrap();
+    let index = archon.state.current_chain_index.lock().unwrap();
     match chain_map.get(&index) {
         Some((mining_info, _)) => Some(mining_info.clone()),
         None => None,
@@ -716,17 +955,18 @@ fn query_create_default_config() {
 }
 
 fn print_block_requeued_or_interrupted(
+    archon: &Archon,
     chain_name: &str,
     chain_color: &str,
     height: u32,
     requeued: bool,
 ) {
     let border = String::from("------------------------------------------------------------------------------------------");
-    let color = get_color(chain_color);
+    let color = get_color(archon, chain_color);
     if requeued {
         println!("{}", border.yellow());
         println!("  {} {} => {} | {}",
-            format!("{}", get_time()).white(),
+            format!("{}", get_time(archon)).white(),
             "INTERRUPTED & REQUEUED BLOCK".color(color),
             format!("{}", chain_name).color(color),
             format!("#{}", height).color(color)
@@ -735,7 +975,7 @@ fn print_block_requeued_or_interrupted(
     } else {
         println!("{}", border.red());
         println!("  {} {} => {} | {}",
-            format!("{}", get_time()).white(),
+            format!("{}", get_time(archon)).white(),
             "INTERRUPTED BLOCK".red(),
             format!("{}", chain_name).color(color),
             format!("#{}", height).color(color)
@@ -744,16 +984,105 @@ fn print_block_requeued_or_interrupted(
     }
 }
 
+fn print_fork(
+    archon: &Archon,
+    chain_name: &str,
+    chain_color: &str,
+    old_height: u32,
+    new_height: u32,
+    old_gen_sig: &str,
+    new_gen_sig: &str,
+) {
+    let border = String::from("------------------------------------------------------------------------------------------");
+    let color = get_color(archon, chain_color);
+    println!("{}", border.red());
+    println!("  {} {} => {} | {} => {}",
+        format!("{}", get_time(archon)).white(),
+        "FORK DETECTED".red(),
+        format!("{}", chain_name).color(color),
+        format!("#{}", old_height).color(color),
+        format!("#{}", new_height).color(color)
+    );
+    println!("  {} {}", "Old Generation Signature:".color(color).bold(), old_gen_sig.color(color));
+    println!("  {} {}", "New Generation Signature:".color(color).bold(), new_gen_sig.color(color));
+    println!("{}", border.red());
+}
+
+fn print_arbiter_decision(archon: &Archon, chain_name: &str, chain_color: &str, height: u32, decision: &str) {
+    // the queue is re-evaluated on every arbiter event, only print a decision the first time it is made for a block
+    let mut printed_arbiter_decisions = archon.state.printed_arbiter_decisions.lock().unwrap();
+    printed_arbiter_decisions.retain(|(name, printed_height, _)| name != chain_name || *printed_height == height);
+    if !printed_arbiter_decisions.insert((String::from(chain_name), height, String::from(decision))) {
+        return;
+    }
+    drop(printed_arbiter_decisions);
+    print_decision(archon, chain_name, chain_color, decision);
+}
+
+// prints a decision every time it is made, for one-off actions like an admin's
+fn print_decision(archon: &Archon, chain_name: &str, chain_color: &str, decision: &str) {
+    info!("DECISION - {} - {}", chain_name, decision);
+    println!("  {} {} {}",
+        get_time(archon).white(),
+        format!("{}", chain_name).color(get_color(archon, chain_color)),
+        decision.yellow()
+    );
+}
+
+fn print_schedule_transition(
+    archon: &Archon,
+    chain_name: &str,
+    chain_color: &str,
+    previous_schedule: Option<ChainSchedule>,
+    active_schedule: Option<ChainSchedule>,
+) {
+    let color = get_color(archon, chain_color);
+    match previous_schedule {
+        Some(schedule) => {
+            info!("SCHEDULE END - {} - {}", chain_name, schedule.describe());
+            println!("  {} {} {}",
+                get_time(archon).white(),
+                format!("{}", chain_name).color(color),
+                format!("Schedule [{}] ended.", schedule.describe()).yellow()
+            );
+        }
+        None => {}
+    };
+    match active_schedule {
+        Some(schedule) => {
+            let mut changes = Vec::new();
+            match schedule.enabled {
+                Some(true) => changes.push(String::from("Enabled")),
+                Some(false) => changes.push(String::from("Disabled")),
+                None => {}
+            };
+            if schedule.priority.is_some() {
+                changes.push(format!("Priority: {}", schedule.priority.unwrap()));
+            }
+            if schedule.target_deadline.is_some() {
+                changes.push(format!("TDL: {}", schedule.target_deadline.unwrap()));
+            }
+            info!("SCHEDULE START - {} - {} - {}", chain_name, schedule.describe(), changes.join(" | "));
+            println!("  {} {} {}",
+                get_time(archon).white(),
+                format!("{}", chain_name).color(color),
+                format!("Schedule [{}] started: {}", schedule.describe(), changes.join(" | ")).yellow()
+            );
+        }
+        None => {}
+    };
+}
+
 /*fn print_block_queued(chain_name: &str, chain_color: &str, height: u32) {
-    if CONF.show_block_queued_messages.unwrap_or(true) {
+    if archon.conf.show_block_queued_messages.unwrap_or(true) {
         let mut queued_block_message = String::from("");
         let border = String::from("------------------------------------------------------------------------------------------");
-        let color = get_color(chain_color);
+        let color = get_color(archon, chain_color);
         queued_block_message.push_str(
             format!(
                 "{}\n  {} {} => {} | {}\n{}",
                 border.color(color).bold(),
-                format!("{}", get_time()).white(),
+                format!("{}", get_time(archon)).white(),
                 "  QUEUED BLOCK".color(color),
                 format!("{}", chain_name).color(color),
                 format!("#{}", height).color(color),
@@ -766,6 +1095,7 @@ fn print_block_requeued_or_interrupted(
 }*/
 
 fn print_block_started(
+    archon: &Archon,
     chain_index: u8,
     height: u32,
     base_target: u32,
@@ -773,18 +1103,18 @@ fn print_block_started(
     target_deadline: u64,
     last_block_time: Option<u64>,
 ) {
-    if !is_block_start_printed(chain_index, height) {
-        let current_chain = get_chain_from_index(chain_index).unwrap();
+    if !is_block_start_printed(archon, chain_index, height) {
+        let current_chain = get_chain_from_index(archon, chain_index).unwrap();
         let mut new_block_message = String::from("");
         let border = String::from("------------------------------------------------------------------------------------------");
-        let color = get_color(&*current_chain.color);
+        let color = get_color(archon, &*current_chain.color);
         let last_block_time_str;
         match last_block_time {
             Some(time) => {
                 if time > 0 {
                     let human_time;
-                    if CONF.show_human_readable_deadlines.unwrap_or(true) {
-                        human_time = format!(" ({})", format_timespan(time));
+                    if archon.conf.show_human_readable_deadlines.unwrap_or(true) {
+                        human_time = format!(" ({})", format_timespan(archon, time));
                     } else {
                         human_time = String::from("");
                     }
@@ -804,12 +1134,12 @@ fn print_block_started(
         };
         /*let mut prev_block_time = 0;
         if height > 0 {
-            prev_block_time = arbiter::get_time_since_block_start(height - 1).unwrap_or(0);
+            prev_block_time = arbiter::get_time_since_block_start(archon, height - 1).unwrap_or(0);
         }
         let prev_block_time_str;
         if prev_block_time > 0 {
-            if CONF.show_human_readable_deadlines.unwrap_or(true) {
-                prev_block_time_str = format!("[#{} Run time: {} secs ({})]", height - 1, prev_block_time, format_timespan(prev_block_time));
+            if archon.conf.show_human_readable_deadlines.unwrap_or(true) {
+                prev_block_time_str = format!("[#{} Run time: {} secs ({})]", height - 1, prev_block_time, format_timespan(archon, prev_block_time));
             } else {
                 prev_block_time_str = format!("[#{} Run time: {} secs", height - 1, prev_block_time);
             }
@@ -820,7 +1150,7 @@ fn print_block_started(
             format!("{}{}\n  {} {} => {} | {}\n{}\n  {}          {}\n",
                 last_block_time_str.yellow(),
                 border.color(color).bold(),
-                format!("{}", get_time()).white(),
+                format!("{}", get_time(archon)).white(),
                 " STARTED BLOCK".color(color),
                 format!("{}", &*current_chain.name).color(color),
                 format!("#{}", height).color(color),
@@ -839,7 +1169,7 @@ fn print_block_started(
             actual_target_deadline = current_chain.target_deadline.unwrap()
         }
         let mut human_readable_target_deadline = String::from("");
-        match get_dynamic_deadline_for_block(base_target) {
+        match get_dynamic_deadline_for_block(archon, chain_index, base_target) {
             (true, _, net_difficulty, dynamic_target_deadline) => {
                 let mut dynamic_target_deadline_warning = String::from("");
                 if dynamic_target_deadline < actual_target_deadline {
@@ -848,17 +1178,17 @@ fn print_block_started(
                     dynamic_target_deadline_warning =
                         format!(" [Dyn > Max Target! - {}]", dynamic_target_deadline);
                 }
-                if crate::CONF
+                if archon.conf
                     .show_human_readable_deadlines
                     .unwrap_or_default()
                 {
                     human_readable_target_deadline =
-                        format!(" ({})", format_timespan(actual_target_deadline));
+                        format!(" ({})", format_timespan(archon, actual_target_deadline));
                     if dynamic_target_deadline_warning.len() > 0 {
                         dynamic_target_deadline_warning
                             .truncate(dynamic_target_deadline_warning.len() - 1);
                         dynamic_target_deadline_warning.push_str(
-                            format!(" ({})]", format_timespan(dynamic_target_deadline)).as_str(),
+                            format!(" ({})]", format_timespan(archon, dynamic_target_deadline)).as_str(),
                         );
                     }
                 }
@@ -902,9 +1232,9 @@ fn print_block_started(
                 }
             }
             (false, _, net_difficulty, _) => {
-                if crate::CONF.show_human_readable_deadlines.unwrap_or_default() {
+                if archon.conf.show_human_readable_deadlines.unwrap_or_default() {
                     human_readable_target_deadline =
-                        format!(" ({})", format_timespan(actual_target_deadline));
+                        format!(" ({})", format_timespan(archon, actual_target_deadline));
                 }
                 new_block_message.push_str(
                     format!(
@@ -944,6 +1274,16 @@ fn print_block_started(
                 }
             }
         };
+        if arbiter::is_fair_share_mode(archon) {
+            let (actual_share, target_share) = arbiter::get_mining_share(archon, chain_index);
+            new_block_message.push_str(
+                format!("  {}         {}\n",
+                    "Mining Share:".color(color).bold(),
+                    format!("{:.1}% (Target: {:.1}%)", actual_share * 100f64, target_share * 100f64).color(color)
+                )
+                .as_str(),
+            );
+        }
         new_block_message.push_str(
             format!("  {} {}\n{}",
                 "Generation Signature:".color(color).bold(),
@@ -953,7 +1293,7 @@ fn print_block_started(
             .as_str(),
         );
         debug!("SET BLOCK START PRINTED {} #{}", chain_index, height);
-        let mut block_start_printed_map = BLOCK_START_PRINTED.lock().unwrap();
+        let mut block_start_printed_map = archon.state.block_start_printed.lock().unwrap();
         block_start_printed_map.insert(chain_index, height);
         println!("{}", new_block_message);
     }
@@ -961,6 +1301,7 @@ fn print_block_started(
 
 #[allow(dead_code)]
 fn print_nonce_skipped(
+    archon: &Archon,
     index: u8,
     height: u32,
     account_id: u64,
@@ -968,12 +1309,12 @@ fn print_nonce_skipped(
     user_agent: &str,
     target_deadline: u64,
 ) {
-    let current_chain = get_chain_from_index(index).unwrap();
-    let color = get_color(&*current_chain.color);
+    let current_chain = get_chain_from_index(archon, index).unwrap();
+    let color = get_color(archon, &*current_chain.color);
     let mut deadline_string = deadline.to_string();
-    if crate::CONF.show_human_readable_deadlines.unwrap_or_default()
+    if archon.conf.show_human_readable_deadlines.unwrap_or_default()
     {
-        deadline_string.push_str(format!(" ({})", format_timespan(deadline)).as_str());
+        deadline_string.push_str(format!(" ({})", format_timespan(archon, deadline)).as_str());
     }
     let deadline_color = match deadline {
         0...3600 => "green",
@@ -985,7 +1326,7 @@ fn print_nonce_skipped(
         "Block #".color(color).bold(),
         height.to_string().color(color),
         "Numeric ID:".color(color).bold(),
-        censor_account_id(account_id).color(color),
+        censor_account_id(archon, account_id).color(color),
         format!(" [TDL: {}]", target_deadline).red(),
         "Skipped:".yellow(),
         deadline_string.color(deadline_color),
@@ -993,6 +1334,7 @@ fn print_nonce_skipped(
 }
 
 fn print_nonce_submission(
+    archon: &Archon,
     index: u8,
     height: u32,
     account_id: u64,
@@ -1002,19 +1344,19 @@ fn print_nonce_submission(
     id_override: bool,
     remote_addr: String,
 ) {
-    let current_chain = get_chain_from_index(index).unwrap();
+    let current_chain = get_chain_from_index(archon, index).unwrap();
 
     // check if this is a submission for the actual current chain we're mining
-    let actual_current_chain_index = arbiter::get_current_chain_index();
-    let actual_current_chain_height = arbiter::get_latest_chain_info(actual_current_chain_index).0;
+    let actual_current_chain_index = arbiter::get_current_chain_index(archon);
+    let actual_current_chain_height = arbiter::get_latest_chain_info(archon, actual_current_chain_index).0;
 
     if actual_current_chain_index == index && actual_current_chain_height == height {
         //let scoop_num = rand::thread_rng().gen_range(0, 4097);
-        let color = get_color(&*current_chain.color);
+        let color = get_color(archon, &*current_chain.color);
         let mut deadline_string = deadline.to_string();
-        if crate::CONF.show_human_readable_deadlines.unwrap_or_default()
+        if archon.conf.show_human_readable_deadlines.unwrap_or_default()
         {
-            deadline_string.push_str(format!(" ({})", format_timespan(deadline)).as_str());
+            deadline_string.push_str(format!(" ({})", format_timespan(archon, deadline)).as_str());
         }
         let deadline_color = match deadline {
             0...3600 => "green",
@@ -1022,7 +1364,7 @@ fn print_nonce_submission(
             _ => "white",
         };
         // remote_addr is an endpoint, need to truncate the port and just leave the hostname/ip
-        let remote_address = match CONF.show_miner_addresses.unwrap_or_default() {
+        let remote_address = match archon.conf.show_miner_addresses.unwrap_or_default() {
             true => {
                 let mut addr = remote_addr;
                 let mut port_index = 0;
@@ -1049,7 +1391,7 @@ fn print_nonce_submission(
                 "Block #".color(color).bold(),
                 height.to_string().color(color),
                 "Numeric ID:".color(color).bold(),
-                censor_account_id(account_id).color(color),
+                censor_account_id(archon, account_id).color(color),
                 "Deadline:".color(color).bold(),
                 deadline_string.color(deadline_color)
             );
@@ -1060,7 +1402,7 @@ fn print_nonce_submission(
                 "Block #".color(color).bold(),
                 height.to_string().color(color),
                 "Numeric ID:".color(color).bold(),
-                censor_account_id(account_id).color(color),
+                censor_account_id(archon, account_id).color(color),
                 format!(" [TDL: {}]", target_deadline).red(),
                 "Deadline:".color(color).bold(),
                 deadline_string.color(deadline_color),
@@ -1069,10 +1411,10 @@ fn print_nonce_submission(
     }
 }
 
-fn get_num_chains_with_priority(priority: u8) -> u8 {
-    if CONF.poc_chains.is_some() {
+fn get_num_chains_with_priority(archon: &Archon, priority: u8) -> u8 {
+    if archon.conf.poc_chains.is_some() {
         let mut count = 0;
-        for inner in &CONF.poc_chains {
+        for inner in &archon.conf.poc_chains {
             for chain in inner {
                 if chain.priority == priority && chain.enabled.unwrap_or(true) {
                     count += 1;
@@ -1084,15 +1426,15 @@ fn get_num_chains_with_priority(priority: u8) -> u8 {
     return 0u8;
 }
 
-fn print_nonce_accepted(chain_index: u8, block_height: u32, deadline: u64, confirmation_time_ms: i64) {
-    let current_chain = get_chain_from_index(chain_index).unwrap();
+fn print_nonce_accepted(archon: &Archon, chain_index: u8, block_height: u32, deadline: u64, confirmation_time_ms: i64) {
+    let current_chain = get_chain_from_index(archon, chain_index).unwrap();
 
     // check if this is a submission for the actual current chain we're mining
-    let actual_current_chain_index = arbiter::get_current_chain_index();
-    let actual_current_chain_height = arbiter::get_latest_chain_info(actual_current_chain_index).0;
+    let actual_current_chain_index = arbiter::get_current_chain_index(archon);
+    let actual_current_chain_height = arbiter::get_latest_chain_info(archon, actual_current_chain_index).0;
 
     if actual_current_chain_index == chain_index && actual_current_chain_height == block_height {
-        let color = get_color(&*current_chain.color);
+        let color = get_color(archon, &*current_chain.color);
         println!("            {}                     {}{}",
             "Confirmed:".green(),
             deadline.to_string().color(color),
@@ -1101,14 +1443,14 @@ fn print_nonce_accepted(chain_index: u8, block_height: u32, deadline: u64, confi
     }
 }
 
-fn print_nonce_rejected(chain_index: u8, block_height: u32, deadline: u64, rejection_time_ms: i64) {
+fn print_nonce_rejected(archon: &Archon, chain_index: u8, block_height: u32, deadline: u64, rejection_time_ms: i64) {
     // check if this is a submission for the actual current chain we're mining
-    let actual_current_chain_index = arbiter::get_current_chain_index();
-    let actual_current_chain_height = arbiter::get_latest_chain_info(actual_current_chain_index).0;
+    let actual_current_chain_index = arbiter::get_current_chain_index(archon);
+    let actual_current_chain_height = arbiter::get_latest_chain_info(archon, actual_current_chain_index).0;
 
     if actual_current_chain_index == chain_index && actual_current_chain_height == block_height {
-        let current_chain = get_chain_from_index(chain_index).unwrap();
-        let color = get_color(&*current_chain.color);
+        let current_chain = get_chain_from_index(archon, chain_index).unwrap();
+        let color = get_color(archon, &*current_chain.color);
         println!("            {}                      {}{}",
             "Rejected:".red(),
             deadline.to_string().color(color),
@@ -1123,7 +1465,7 @@ fn get_network_difficulty_for_block(base_target: u32, block_time_seconds: u16) -
     return (4398046511104u64 / block_time_seconds as u64) / base_target as u64;
 }
 
-fn get_total_plots_size_in_tebibytes() -> f64 {
+fn get_total_plots_size_in_tebibytes(archon: &Archon) -> f64 {
     // sum up plot size vars from config
     let mut plot_size_tebibytes = 0f64;
     // calculate conversion multipliers
@@ -1142,25 +1484,25 @@ fn get_total_plots_size_in_tebibytes() -> f64 {
     let gib_to_tib_multiplier = 2f64.powi(30) / 2f64.powi(40);
     // Proof: 1024 GiB: 1024 * (2^30/2^40) = 1.000 TiB
 
-    match crate::CONF.total_plots_size_in_gigabytes {
+    match archon.conf.total_plots_size_in_gigabytes {
         Some(size_gb) => {
             plot_size_tebibytes += size_gb * gb_to_tib_multiplier;
         }
         _ => {}
     }
-    match crate::CONF.total_plots_size_in_terabytes {
+    match archon.conf.total_plots_size_in_terabytes {
         Some(size_tb) => {
             plot_size_tebibytes += size_tb * tb_to_tib_multiplier;
         }
         _ => {}
     }
-    match crate::CONF.total_plots_size_in_gibibytes {
+    match archon.conf.total_plots_size_in_gibibytes {
         Some(size_gib) => {
             plot_size_tebibytes += size_gib * gib_to_tib_multiplier; // can just do size_gib/1024 to get GiB => TiB, but this way is cooler... :D
         }
         _ => {}
     }
-    match crate::CONF.total_plots_size_in_tebibytes {
+    match archon.conf.total_plots_size_in_tebibytes {
         Some(size_tib) => {
             plot_size_tebibytes += size_tib;
         }
@@ -1170,13 +1512,28 @@ fn get_total_plots_size_in_tebibytes() -> f64 {
 }
 
 #[allow(dead_code)]
-fn get_dynamic_deadline_for_block(base_target: u32) -> (bool, f64, u64, u64) {
-    let chain_index = arbiter::get_current_chain_index();
-    let current_chain = get_chain_from_index(chain_index).unwrap();
+fn get_dynamic_deadline_for_block(archon: &Archon, chain_index: u8, base_target: u32) -> (bool, f64, u64, u64) {
+    let current_chain = get_chain_from_index(archon, chain_index).unwrap();
+    let (plot_size_tebibytes, _) = capacity::get_plot_capacity(archon, chain_index);
+    return get_dynamic_deadline_for_capacity(current_chain.use_dynamic_deadlines.unwrap_or_default(), plot_size_tebibytes, base_target);
+}
+
+/// Like get_dynamic_deadline_for_block, but calculated from the account's own plot capacity if it has one.
+fn get_dynamic_deadline_for_account(archon: &Archon, chain_index: u8, account_id: u64, base_target: u32) -> (bool, f64, u64, u64) {
+    let current_chain = get_chain_from_index(archon, chain_index).unwrap();
+    match capacity::get_account_capacity(archon, account_id) {
+        Some((account_plot_size_tebibytes, _)) if current_chain.use_dynamic_deadlines.unwrap_or_default() && account_plot_size_tebibytes > 0f64 => {
+            get_dynamic_deadline_for_capacity(true, account_plot_size_tebibytes, base_target)
+        }
+        _ => get_dynamic_deadline_for_block(archon, chain_index, base_target),
+    }
+}
+
+/// Calculates the dynamic target deadline for a block from the given plot capacity.
+fn get_dynamic_deadline_for_capacity(use_dynamic_deadlines: bool, plot_size_tebibytes: f64, base_target: u32) -> (bool, f64, u64, u64) {
     let net_diff = get_network_difficulty_for_block(base_target, 240) as u64;
-    let plot_size_tebibytes = get_total_plots_size_in_tebibytes();
     // are we using dynamic deadlines for this chain?
-    if current_chain.use_dynamic_deadlines.unwrap_or_default() && plot_size_tebibytes > 0f64 {
+    if use_dynamic_deadlines && plot_size_tebibytes > 0f64 {
         let dynamic_target_deadline = (720f64 * (net_diff as f64) / plot_size_tebibytes) as u64;
         return (true, plot_size_tebibytes, net_diff, dynamic_target_deadline);
     } else {
@@ -1184,17 +1541,51 @@ fn get_dynamic_deadline_for_block(base_target: u32) -> (bool, f64, u64, u64) {
     }
 }
 
-fn get_time() -> String {
-    let local_time: DateTime<Local> = Local::now();
-    if crate::CONF.use_24_hour_time.unwrap_or_default() {
+fn get_time(archon: &Archon) -> String {
+    let local_time: DateTime<Local> = archon.now();
+    if archon.conf.use_24_hour_time.unwrap_or_default() {
         return local_time.format("%H:%M:%S").to_string();
     }
     return local_time.format("%I:%M:%S%P").to_string();
 }
 
-fn get_chain_from_index(index: u8) -> Option<PocChain> {
+/// Returns a chain with the overrides from its schedule window and the admin API currently in effect applied.
+fn get_chain_from_index(archon: &Archon, index: u8) -> Option<PocChain> {
+    match get_configured_chain_from_index(archon, index) {
+        Some(mut chain) => {
+            match schedule::get_active_schedule(archon, index) {
+                Some(active_schedule) => {
+                    if active_schedule.priority.is_some() {
+                        chain.priority = active_schedule.priority.unwrap();
+                    }
+                    if active_schedule.target_deadline.is_some() {
+                        chain.target_deadline = active_schedule.target_deadline;
+                    }
+                }
+                None => {}
+            };
+            // an override from the admin API takes precedence over the schedule
+            match control::get_chain_override(archon, index, archon.now()) {
+                Some(chain_override) => {
+                    if chain_override.priority.is_some() {
+                        chain.priority = chain_override.priority.unwrap();
+                    }
+                    if chain_override.target_deadline.is_some() {
+                        chain.target_deadline = chain_override.target_deadline;
+                    }
+                }
+                None => {}
+            };
+            Some(chain)
+        }
+        None => None,
+    }
+}
+
+/// Returns a chain exactly as it is configured in archon.yaml.
+fn get_configured_chain_from_index(archon: &Archon, index: u8) -> Option<PocChain> {
     let mut i = 0;
-    for inner in &crate::CONF.poc_chains {
+    for inner in &archon.conf.poc_chains {
         for chain in inner {
             if chain.enabled.unwrap_or(true) {
                 if i == index {
@@ -1207,9 +1598,9 @@ fn get_chain_from_index(index: u8) -> Option<PocChain> {
     return None;
 }
 
-fn get_chain_index(chain_url: &str, chain_name: &str) -> u8 {
+fn get_chain_index(archon: &Archon, chain_url: &str, chain_name: &str) -> u8 {
     let mut index = 0;
-    for inner in &crate::CONF.poc_chains {
+    for inner in &archon.conf.poc_chains {
         for chain in inner {
             if chain.enabled.unwrap_or(true) {
                 if chain.url == chain_url && chain.name == chain_name {
@@ -1223,21 +1614,21 @@ fn get_chain_index(chain_url: &str, chain_name: &str) -> u8 {
 }
 
 #[allow(dead_code)]
-fn get_mining_info_for_chain(chain_url: &str, chain_name: &str) -> (MiningInfo, DateTime<Local>) {
-    let index = get_chain_index(chain_url, chain_name);
-    let chain_map = crate::CHAIN_MINING_INFOS.lock().unwrap();
+fn get_mining_info_for_chain(archon: &Archon, chain_url: &str, chain_name: &str) -> (MiningInfo, DateTime<Local>) {
+    let index = get_chain_index(archon, chain_url, chain_name);
+    let chain_map = archon.state.chain_mining_infos.lock().unwrap();
     match chain_map.get(&index) {
         Some((mining_info, time)) => {
             return (mining_info.clone(), time.clone());
         }
         None => {
-            return (MiningInfo::empty(), Local::now());
+            return (MiningInfo::empty(), archon.now());
         }
     }
 }
 
-fn format_timespan(timespan: u64) -> String {
-    if !crate::CONF
+fn format_timespan(archon: &Archon, timespan: u64) -> String {
+    if !archon.conf
         .show_human_readable_deadlines
         .unwrap_or_default()
     {
@@ -1281,9 +1672,9 @@ fn format_timespan(timespan: u64) -> String {
     );
 }
 
-fn censor_account_id(account_id: u64) -> String {
+fn censor_account_id(archon: &Archon, account_id: u64) -> String {
     let mut as_string = account_id.to_string();
-    if CONF.mask_account_ids_in_console.unwrap_or_default() {
+    if archon.conf.mask_account_ids_in_console.unwrap_or_default() {
         as_string.replace_range(1..as_string.len() - 3, "XXXXXXXXXXXXXXXX");
     }
     return as_string;
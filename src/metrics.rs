@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+
+use crate::access;
+use crate::arbiter;
+use crate::context::Archon;
+use crate::dashboard;
+use crate::events::ArchonEvent;
+use crate::prediction;
+
+// upper bounds, in seconds, of the upstream request latency histogram's buckets
+const LATENCY_BUCKETS: [f64; 11] = [0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+const BLOCKS_STARTED: &'static str = "archon_blocks_started_total";
+const BLOCKS_INTERRUPTED: &'static str = "archon_blocks_interrupted_total";
+const BLOCKS_REQUEUED: &'static str = "archon_blocks_requeued_total";
+const SUBMISSIONS_RECEIVED: &'static str = "archon_submissions_received_total";
+const SUBMISSIONS_FORWARDED: &'static str = "archon_submissions_forwarded_total";
+const SUBMISSIONS_ACCEPTED: &'static str = "archon_submissions_accepted_total";
+const SUBMISSIONS_REJECTED: &'static str = "archon_submissions_rejected_total";
+const SUBMISSIONS_FAKE_CONFIRMED: &'static str = "archon_submissions_fake_confirmed_total";
+const UPSTREAM_OUTAGE_SECONDS: &'static str = "archon_upstream_outage_seconds_total";
+
+// (name, help) of every per-chain counter, in the order they're exported
+const COUNTERS: [(&'static str, &'static str); 9] = [
+    (BLOCKS_STARTED, "Blocks sent out to the miners."),
+    (BLOCKS_INTERRUPTED, "Blocks cut short by another chain's block and dropped."),
+    (BLOCKS_REQUEUED, "Blocks cut short by another chain's block and requeued."),
+    (SUBMISSIONS_RECEIVED, "Deadlines submitted by miners."),
+    (SUBMISSIONS_FORWARDED, "Deadlines forwarded to the upstream."),
+    (SUBMISSIONS_ACCEPTED, "Forwarded deadlines accepted by the upstream."),
+    (SUBMISSIONS_REJECTED, "Forwarded deadlines rejected by the upstream."),
+    (SUBMISSIONS_FAKE_CONFIRMED, "Deadlines confirmed to the miner without being forwarded, because they were over the target deadline or worse than the account's best."),
+    (UPSTREAM_OUTAGE_SECONDS, "Total time the upstream has been unreachable, including the current outage."),
+];
+
+struct Histogram {
+    bucket_counts: [u64; 11],
+    sum: f64,
+    count: u64,
+}
+
+/// Counters and histograms for the /metrics endpoint.
+pub struct Metrics {
+    // Key = tuple (chain index, counter name)
+    counters: Mutex<HashMap<(u8, &'static str), u64>>,
+    // Key = tuple (chain index, request type)
+    upstream_latencies: Mutex<HashMap<(u8, &'static str), Histogram>>,
+}
+
+impl Metrics {
+    pub fn new() -> Metrics {
+        Metrics {
+            counters: Mutex::new(HashMap::new()),
+            upstream_latencies: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn increment(&self, chain_index: u8, counter: &'static str, amount: u64) {
+        *self.counters.lock().unwrap().entry((chain_index, counter)).or_insert(0) += amount;
+    }
+
+    fn get_counter(&self, chain_index: u8, counter: &'static str) -> u64 {
+        *self.counters.lock().unwrap().get(&(chain_index, counter)).unwrap_or(&0)
+    }
+
+    /// Counts the arbiter's decisions and the upstreams' answers as they're published.
+    pub fn observe_event(&self, event: &ArchonEvent) {
+        match event {
+            ArchonEvent::BlockStarted { chain_index, .. } => self.increment(*chain_index, BLOCKS_STARTED, 1),
+            ArchonEvent::BlockInterrupted { chain_index, .. } => self.increment(*chain_index, BLOCKS_INTERRUPTED, 1),
+            ArchonEvent::BlockRequeued { chain_index, .. } => self.increment(*chain_index, BLOCKS_REQUEUED, 1),
+            ArchonEvent::NonceSubmitted { chain_index, forwarded, .. } => {
+                self.increment(*chain_index, SUBMISSIONS_RECEIVED, 1);
+                if *forwarded {
+                    self.increment(*chain_index, SUBMISSIONS_FORWARDED, 1);
+                } else {
+                    self.increment(*chain_index, SUBMISSIONS_FAKE_CONFIRMED, 1);
+                }
+            }
+            ArchonEvent::NonceAccepted { chain_index, .. } => self.increment(*chain_index, SUBMISSIONS_ACCEPTED, 1),
+            ArchonEvent::NonceRejected { chain_index, .. } => self.increment(*chain_index, SUBMISSIONS_REJECTED, 1),
+            ArchonEvent::UpstreamRecovered { chain_index, outage_seconds, .. } => {
+                self.increment(*chain_index, UPSTREAM_OUTAGE_SECONDS, *outage_seconds as u64)
+            }
+            _ => {}
+        };
+    }
+
+    /// Records how long an upstream took to answer a request.
+    pub fn observe_upstream_latency(&self, chain_index: u8, request_type: &'static str, seconds: f64) {
+        let mut upstream_latencies = self.upstream_latencies.lock().unwrap();
+        let histogram = upstream_latencies.entry((chain_index, request_type)).or_insert(Histogram {
+            bucket_counts: [0; 11],
+            sum: 0.0,
+            count: 0,
+        });
+        for (i, upper_bound) in LATENCY_BUCKETS.iter().enumerate() {
+            if seconds <= *upper_bound {
+                histogram.bucket_counts[i] += 1;
+            }
+        }
+        histogram.sum += seconds;
+        histogram.count += 1;
+    }
+}
+
+// escapes a label value per the Prometheus text format
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn write_header(out: &mut String, name: &str, metric_type: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, metric_type);
+}
+
+/// Renders every metric in the Prometheus text exposition format.
+pub fn render(archon: &Archon) -> String {
+    let now = archon.now();
+    let metrics = &archon.state.metrics;
+    let mut chains = Vec::new();
+    for inner in &archon.conf.poc_chains {
+        for chain in inner {
+            if chain.enabled.unwrap_or(true) {
+                let index = super::get_chain_index(archon, &*chain.url, &*chain.name);
+                chains.push((index, chain.clone(), format!("chain=\"{}\"", escape_label(&*chain.name))));
+            }
+        }
+    }
+    let mut out = String::new();
+
+    let chain_mining_infos = archon.state.chain_mining_infos.lock().unwrap().clone();
+    write_header(&mut out, "archon_chain_height", "gauge", "The latest block height reported by the chain's upstream.");
+    for (index, _, labels) in &chains {
+        if let Some((mining_info, _)) = chain_mining_infos.get(index) {
+            let _ = writeln!(out, "archon_chain_height{{{}}} {}", labels, mining_info.height);
+        }
+    }
+    write_header(&mut out, "archon_chain_base_target", "gauge", "The base target of the chain's latest block.");
+    for (index, _, labels) in &chains {
+        if let Some((mining_info, _)) = chain_mining_infos.get(index) {
+            let _ = writeln!(out, "archon_chain_base_target{{{}}} {}", labels, mining_info.base_target);
+        }
+    }
+    write_header(&mut out, "archon_chain_network_difficulty_tebibytes", "gauge", "The chain's network difficulty, as an estimate of the network's total capacity in TiB.");
+    for (index, chain, labels) in &chains {
+        if let Some((mining_info, _)) = chain_mining_infos.get(index) {
+            if mining_info.base_target > 0 {
+                let network_difficulty = super::get_network_difficulty_for_block(mining_info.base_target, prediction::get_block_time(chain) as u16);
+                let _ = writeln!(out, "archon_chain_network_difficulty_tebibytes{{{}}} {}", labels, network_difficulty);
+            }
+        }
+    }
+
+    for (counter, help) in COUNTERS.iter() {
+        write_header(&mut out, counter, "counter", help);
+        for (index, _, labels) in &chains {
+            let mut value = metrics.get_counter(*index, counter);
+            if *counter == UPSTREAM_OUTAGE_SECONDS {
+                if let Some(outage_since) = archon.state.chain_outages.lock().unwrap().get(index) {
+                    value += (now - *outage_since).num_seconds().max(0) as u64;
+                }
+            }
+            let _ = writeln!(out, "{}{{{}}} {}", counter, labels, value);
+        }
+    }
+
+    write_header(&mut out, "archon_upstream_up", "gauge", "Whether the chain's upstream is answering mining info requests.");
+    for (index, _, labels) in &chains {
+        let up = if arbiter::is_chain_in_outage(archon, *index) { 0 } else { 1 };
+        let _ = writeln!(out, "archon_upstream_up{{{}}} {}", labels, up);
+    }
+
+    write_header(&mut out, "archon_upstream_request_duration_seconds", "histogram", "How long the chain's upstream took to answer requests.");
+    let upstream_latencies = metrics.upstream_latencies.lock().unwrap();
+    for (index, _, labels) in &chains {
+        for request_type in ["getMiningInfo", "submitNonce"].iter() {
+            if let Some(histogram) = upstream_latencies.get(&(*index, *request_type)) {
+                let labels = format!("{},request=\"{}\"", labels, request_type);
+                for (i, upper_bound) in LATENCY_BUCKETS.iter().enumerate() {
+                    let _ = writeln!(out, "archon_upstream_request_duration_seconds_bucket{{{},le=\"{}\"}} {}", labels, upper_bound, histogram.bucket_counts[i]);
+                }
+                let _ = writeln!(out, "archon_upstream_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}", labels, histogram.count);
+                let _ = writeln!(out, "archon_upstream_request_duration_seconds_sum{{{}}} {}", labels, histogram.sum);
+                let _ = writeln!(out, "archon_upstream_request_duration_seconds_count{{{}}} {}", labels, histogram.count);
+            }
+        }
+    }
+    drop(upstream_latencies);
+
+    write_header(&mut out, "archon_best_deadline_seconds", "gauge", "Each account's best deadline for the block currently being mined.");
+    if let Some(current_block) = dashboard::get_current_block(archon, now) {
+        let labels = format!("chain=\"{}\"", escape_label(&*current_block.chain_name));
+        for account_deadline in dashboard::get_account_deadlines(archon, arbiter::get_current_chain_index(archon), current_block.height) {
+            let _ = writeln!(out, "archon_best_deadline_seconds{{{},account=\"{}\"}} {}", labels, escape_label(&*account_deadline.account_id), account_deadline.deadline);
+        }
+    }
+
+    write_header(&mut out, "archon_miner_requests_rejected_total", "counter", "Miner requests refused by access control.");
+    for (reason, count) in access::get_rejection_counts(archon) {
+        let _ = writeln!(out, "archon_miner_requests_rejected_total{{reason=\"{}\"}} {}", reason.name(), count);
+    }
+    out
+}
@@ -0,0 +1,174 @@
+use chrono::{DateTime, Local};
+use std::collections::{BTreeSet, VecDeque};
+
+use crate::context::Archon;
+use crate::rounds::{self, RoundOutcome};
+
+// miners which haven't made a request in this many seconds are no longer considered connected
+const CONNECTED_MINER_TIMEOUT: i64 = 300;
+// miners which haven't made a request in this many seconds are forgotten
+const MINER_EXPIRY: i64 = 86_400;
+// how many of each miner's rounds are kept
+const MAX_MINER_ROUNDS: usize = 10;
+// how many of each miner's getMiningInfo poll intervals are averaged
+const MAX_POLL_INTERVALS: usize = 20;
+
+/// How a miner took part in a round: whether it asked for mining info or submitted anything while it ran.
+#[derive(Debug, Clone)]
+pub struct MinerRound {
+    pub chain_index: u8,
+    pub height: u32,
+    pub submissions: u32,
+    pub active: bool,
+}
+
+/// A miner seen polling for mining info or submitting nonces.
+#[derive(Debug, Clone)]
+pub struct Miner {
+    pub address: String,
+    pub software: String,
+    pub first_seen: DateTime<Local>,
+    pub last_seen: DateTime<Local>,
+    pub last_polled: Option<DateTime<Local>>,
+    // the most recent intervals between getMiningInfo requests, in milliseconds
+    pub poll_intervals: VecDeque<i64>,
+    pub accounts: BTreeSet<u64>,
+    // the capacity reported in the miner's X-Capacity header, in GiB
+    pub capacity: Option<u64>,
+    // the rounds started while the miner was connected, newest first
+    pub rounds: VecDeque<MinerRound>,
+    pub silent_rounds: u32,
+    pub total_submissions: u64,
+}
+
+impl Miner {
+    fn new(address: String, software: String, now: DateTime<Local>) -> Miner {
+        Miner {
+            address,
+            software,
+            first_seen: now,
+            last_seen: now,
+            last_polled: None,
+            poll_intervals: VecDeque::new(),
+            accounts: BTreeSet::new(),
+            capacity: None,
+            rounds: VecDeque::new(),
+            silent_rounds: 0,
+            total_submissions: 0,
+        }
+    }
+
+    pub fn is_connected(&self, now: DateTime<Local>) -> bool {
+        now.signed_duration_since(self.last_seen).num_seconds() < CONNECTED_MINER_TIMEOUT
+    }
+
+    /// The average time between the miner's getMiningInfo requests, in seconds.
+    pub fn get_average_poll_interval(&self) -> Option<f64> {
+        if self.poll_intervals.len() == 0 {
+            return None;
+        }
+        Some(self.poll_intervals.iter().sum::<i64>() as f64 / self.poll_intervals.len() as f64 / 1000f64)
+    }
+
+    // the miner's entry for the given round, added if the miner only turned up after the round started
+    fn get_round_mut(&mut self, chain_index: u8, height: u32) -> &mut MinerRound {
+        match self.rounds.iter().position(|round| round.chain_index == chain_index && round.height == height) {
+            Some(i) => &mut self.rounds[i],
+            None => {
+                self.rounds.push_front(MinerRound { chain_index, height, submissions: 0, active: false });
+                self.rounds.truncate(MAX_MINER_ROUNDS);
+                &mut self.rounds[0]
+            }
+        }
+    }
+}
+
+// (chain index, height) of the round being mined
+fn get_current_round(archon: &Archon) -> Option<(u8, u32)> {
+    match rounds::get_recent_rounds(archon).first() {
+        Some(round) if round.outcome == RoundOutcome::Mining => Some((round.chain_index, round.height)),
+        _ => None,
+    }
+}
+
+// finds the miner's registry entry, adding it if this is its first request
+fn update_miner<F: FnOnce(&mut Miner)>(archon: &Archon, remote_addr: &str, software: &str, capacity: Option<u64>, now: DateTime<Local>, update: F) {
+    let current_round = get_current_round(archon);
+    let address = get_address_without_port(remote_addr);
+    let mut miners = archon.state.miners.lock().unwrap();
+    let miner = miners
+        .entry((address.clone(), String::from(software)))
+        .or_insert_with(|| Miner::new(address, String::from(software), now));
+    miner.last_seen = now;
+    if capacity.is_some() {
+        miner.capacity = capacity;
+    }
+    if let Some((chain_index, height)) = current_round {
+        miner.get_round_mut(chain_index, height).active = true;
+    }
+    update(miner);
+}
+
+/// Remembers a getMiningInfo request from a miner. Miners are told apart by their address and software.
+pub fn record_mining_info_request(archon: &Archon, remote_addr: &str, software: &str, capacity: Option<u64>, now: DateTime<Local>) {
+    update_miner(archon, remote_addr, software, capacity, now, |miner| {
+        if let Some(last_polled) = miner.last_polled {
+            miner.poll_intervals.push_back(now.signed_duration_since(last_polled).num_milliseconds());
+            while miner.poll_intervals.len() > MAX_POLL_INTERVALS {
+                miner.poll_intervals.pop_front();
+            }
+        }
+        miner.last_polled = Some(now);
+    });
+}
+
+/// Remembers a deadline submitted by a miner, against the round for the block it was submitted for.
+pub fn record_nonce_submission(archon: &Archon, remote_addr: &str, software: &str, capacity: Option<u64>, account_id: u64, height: u32, now: DateTime<Local>) {
+    let submission_round = rounds::get_recent_rounds(archon)
+        .into_iter()
+        .find(|round| round.height == height)
+        .map(|round| (round.chain_index, round.height));
+    update_miner(archon, remote_addr, software, capacity, now, |miner| {
+        miner.accounts.insert(account_id);
+        miner.total_submissions += 1;
+        if let Some((chain_index, height)) = submission_round {
+            let round = miner.get_round_mut(chain_index, height);
+            round.submissions += 1;
+            round.active = true;
+        }
+    });
+}
+
+/// Adds the new round to every connected miner, and counts the last round as silent for those which didn't make a request during it.
+/// Miners which haven't been seen in a long time are forgotten.
+pub fn record_round_started(archon: &Archon, chain_index: u8, height: u32, now: DateTime<Local>) {
+    let mut miners = archon.state.miners.lock().unwrap();
+    miners.retain(|_, miner| now.signed_duration_since(miner.last_seen).num_seconds() < MINER_EXPIRY);
+    for miner in miners.values_mut() {
+        if !miner.is_connected(now) {
+            continue;
+        }
+        match miner.rounds.front() {
+            Some(round) if !round.active => miner.silent_rounds += 1,
+            _ => {}
+        };
+        miner.get_round_mut(chain_index, height);
+    }
+}
+
+/// Returns every miner in the registry, most recently seen first.
+pub fn get_miners(archon: &Archon) -> Vec<Miner> {
+    let mut miners: Vec<Miner> = archon.state.miners.lock().unwrap().values().cloned().collect();
+    miners.sort_by(|a, b| b.last_seen.cmp(&a.last_seen));
+    miners
+}
+
+pub fn get_address_without_port(remote_addr: &str) -> String {
+    match remote_addr.rfind(':') {
+        // leave bare IPv6 addresses alone, they're full of colons
+        Some(i) if !remote_addr[..i].contains(':') || remote_addr.starts_with('[') => {
+            String::from(remote_addr[..i].trim_start_matches('[').trim_end_matches(']'))
+        }
+        _ => String::from(remote_addr),
+    }
+}
@@ -0,0 +1,381 @@
+use chrono::{DateTime, Local};
+use colored::Colorize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+
+use crate::context::Archon;
+
+// every nonce is 4096 scoops of 64 bytes
+const NONCE_SIZE: u64 = 262_144;
+// how often the plot directories are scanned again, in seconds
+const PLOT_SCAN_INTERVAL: u64 = 600;
+
+/// A plot file, going by its name: <accountId>_<startNonce>_<nonces>, or <accountId>_<startNonce>_<nonces>_<stagger> for PoC1 plots.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlotFile {
+    pub path: String,
+    #[serde(skip)]
+    pub account_id: u64,
+    pub start_nonce: u64,
+    pub nonces: u64,
+    // the size of the file on disk, in bytes
+    pub file_size: u64,
+}
+
+impl PlotFile {
+    fn end_nonce(&self) -> u64 {
+        self.start_nonce.saturating_add(self.nonces)
+    }
+}
+
+/// Two plot files for the same account which contain some of the same nonces, so part of one of them is wasted space.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlotOverlap {
+    pub account_id: String,
+    pub first: String,
+    pub second: String,
+    pub overlapping_nonces: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountPlots {
+    #[serde(skip)]
+    pub numeric_id: u64,
+    pub account_id: String,
+    pub files: Vec<PlotFile>,
+    // nonces which are in more than one file are only counted once
+    pub nonces: u64,
+    pub capacity_tebibytes: f64,
+}
+
+/// Everything found in the plot directories.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlotScan {
+    pub time: String,
+    pub directories: Vec<String>,
+    pub accounts: Vec<AccountPlots>,
+    pub total_capacity_tebibytes: f64,
+    pub overlaps: Vec<PlotOverlap>,
+    // directories which couldn't be read, and why
+    pub errors: Vec<String>,
+    // files in the plot directories which aren't named like plots
+    pub ignored_files: usize,
+}
+
+impl PlotScan {
+    pub fn get_num_files(&self) -> usize {
+        self.accounts.iter().map(|account| account.files.len()).sum()
+    }
+}
+
+/// Parses a plot file's name into (account id, start nonce, nonces).
+pub fn parse_plot_file_name(file_name: &str) -> Option<(u64, u64, u64)> {
+    let parts: Vec<&str> = file_name.split('_').collect();
+    if parts.len() != 3 && parts.len() != 4 {
+        return None;
+    }
+    let mut numbers = parts.iter().map(|part| part.parse::<u64>().ok());
+    let account_id = numbers.next()??;
+    let start_nonce = numbers.next()??;
+    let nonces = numbers.next()??;
+    if parts.len() == 4 {
+        numbers.next()??;
+    }
+    if nonces == 0 {
+        return None;
+    }
+    Some((account_id, start_nonce, nonces))
+}
+
+fn nonces_to_tebibytes(nonces: u64) -> f64 {
+    (nonces as f64 * NONCE_SIZE as f64) / 2f64.powi(40)
+}
+
+// finds every pair of files whose nonces overlap, the files must be sorted by start nonce
+fn find_overlaps(archon: &Archon, files: &Vec<PlotFile>) -> Vec<PlotOverlap> {
+    let mut overlaps = Vec::new();
+    for (i, first) in files.iter().enumerate() {
+        for second in files[i + 1..].iter().take_while(|second| second.start_nonce < first.end_nonce()) {
+            overlaps.push(PlotOverlap {
+                account_id: super::censor_account_id(archon, first.account_id),
+                first: first.path.clone(),
+                second: second.path.clone(),
+                overlapping_nonces: first.end_nonce().min(second.end_nonce()) - second.start_nonce,
+            });
+        }
+    }
+    overlaps
+}
+
+// the number of distinct nonces in the files, the files must be sorted by start nonce
+fn count_distinct_nonces(files: &Vec<PlotFile>) -> u64 {
+    let mut nonces = 0;
+    let mut counted_up_to = 0;
+    for file in files {
+        let start = file.start_nonce.max(counted_up_to);
+        if file.end_nonce() > start {
+            nonces += file.end_nonce() - start;
+        }
+        counted_up_to = counted_up_to.max(file.end_nonce());
+    }
+    nonces
+}
+
+/// Reads the plot files from every configured plot directory. Directories are not searched recursively.
+pub fn scan_plot_directories(archon: &Archon) -> PlotScan {
+    let directories = archon.conf.plot_directories.clone().unwrap_or_default();
+    let mut files_by_account: BTreeMap<u64, Vec<PlotFile>> = BTreeMap::new();
+    let mut errors = Vec::new();
+    let mut ignored_files = 0;
+    for directory in &directories {
+        let entries = match fs::read_dir(directory) {
+            Ok(entries) => entries,
+            Err(why) => {
+                errors.push(format!("{}: {}", directory, why));
+                continue;
+            }
+        };
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let metadata = match entry.metadata() {
+                Ok(metadata) if metadata.is_file() => metadata,
+                _ => continue,
+            };
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            match parse_plot_file_name(&*file_name) {
+                Some((account_id, start_nonce, nonces)) => {
+                    files_by_account.entry(account_id).or_insert(Vec::new()).push(PlotFile {
+                        path: Path::new(directory).join(&file_name).to_string_lossy().to_string(),
+                        account_id,
+                        start_nonce,
+                        nonces,
+                        file_size: metadata.len(),
+                    });
+                }
+                None => ignored_files += 1,
+            };
+        }
+    }
+    let mut accounts = Vec::new();
+    let mut overlaps = Vec::new();
+    for (account_id, mut files) in files_by_account {
+        files.sort_by_key(|file| file.start_nonce);
+        overlaps.extend(find_overlaps(archon, &files));
+        let nonces = count_distinct_nonces(&files);
+        accounts.push(AccountPlots {
+            numeric_id: account_id,
+            account_id: super::censor_account_id(archon, account_id),
+            files,
+            nonces,
+            capacity_tebibytes: nonces_to_tebibytes(nonces),
+        });
+    }
+    PlotScan {
+        time: archon.now().to_rfc3339(),
+        directories,
+        total_capacity_tebibytes: accounts.iter().map(|account| account.capacity_tebibytes).sum(),
+        accounts,
+        overlaps,
+        errors,
+        ignored_files,
+    }
+}
+
+pub fn has_plot_directories(archon: &Archon) -> bool {
+    archon.conf.plot_directories.as_ref().map_or(false, |directories| directories.len() > 0)
+}
+
+/// The results of the latest scan of the plot directories, if they've been scanned.
+pub fn get_plot_scan(archon: &Archon) -> Option<PlotScan> {
+    archon.state.plot_scan.lock().unwrap().clone()
+}
+
+/// The total capacity found in the plot directories, in TiB.
+pub fn get_scanned_capacity(archon: &Archon) -> Option<f64> {
+    archon.state.plot_scan.lock().unwrap().as_ref().map(|plot_scan| plot_scan.total_capacity_tebibytes)
+}
+
+/// The capacity of an account's plot files found in the plot directories, in TiB.
+pub fn get_scanned_account_capacity(archon: &Archon, account_id: u64) -> Option<f64> {
+    archon.state.plot_scan.lock().unwrap().as_ref().and_then(|plot_scan| {
+        plot_scan.accounts
+            .iter()
+            .find(|account| account.numeric_id == account_id)
+            .map(|account| account.capacity_tebibytes)
+    })
+}
+
+/// The IDs of the accounts found in the plot directories.
+pub fn get_scanned_account_ids(archon: &Archon) -> Vec<u64> {
+    archon.state.plot_scan.lock().unwrap().as_ref().map_or(Vec::new(), |plot_scan| {
+        plot_scan.accounts.iter().map(|account| account.numeric_id).collect()
+    })
+}
+
+/// Scans the plot directories again, printing any problems which weren't there last time.
+pub fn rescan_plot_directories(archon: &Archon) {
+    let plot_scan = scan_plot_directories(archon);
+    let previous_plot_scan = get_plot_scan(archon);
+    let is_new = |problem: &String, previous_problems: Option<Vec<String>>| !previous_problems.map_or(false, |previous_problems| previous_problems.contains(problem));
+    for error in &plot_scan.errors {
+        if is_new(error, previous_plot_scan.as_ref().map(|previous_plot_scan| previous_plot_scan.errors.clone())) {
+            warn!("PLOTS - Couldn't read plot directory {}", error);
+            println!("  {} {}", super::get_time(archon).white(), format!("WARNING Couldn't read plot directory {}", error).yellow());
+        }
+    }
+    let describe_overlap = |overlap: &PlotOverlap| format!("{} and {} overlap by {} nonces", overlap.first, overlap.second, overlap.overlapping_nonces);
+    for overlap in plot_scan.overlaps.iter().map(describe_overlap) {
+        if is_new(&overlap, previous_plot_scan.as_ref().map(|previous_plot_scan| previous_plot_scan.overlaps.iter().map(describe_overlap).collect())) {
+            warn!("PLOTS - {}", overlap);
+            println!("  {} {}", super::get_time(archon).white(), format!("WARNING Plot files {}, the overlap is wasted space.", overlap).yellow());
+        }
+    }
+    match &previous_plot_scan {
+        Some(previous_plot_scan) if previous_plot_scan.total_capacity_tebibytes != plot_scan.total_capacity_tebibytes => {
+            info!("PLOTS - Capacity changed from {:.2} TiB to {:.2} TiB", previous_plot_scan.total_capacity_tebibytes, plot_scan.total_capacity_tebibytes);
+            println!("  {} {}", super::get_time(archon).white(), format!("Plot capacity changed from {:.2} TiB to {:.2} TiB.", previous_plot_scan.total_capacity_tebibytes, plot_scan.total_capacity_tebibytes).green());
+        }
+        _ => {}
+    };
+    *archon.state.plot_scan.lock().unwrap() = Some(plot_scan);
+}
+
+/// Scans the plot directories every 10 minutes, so new drives are picked up without a restart.
+pub fn thread_scan_plots(archon: Arc<Archon>) {
+    let archon = &*archon;
+    loop {
+        thread::sleep(std::time::Duration::from_secs(PLOT_SCAN_INTERVAL));
+        rescan_plot_directories(archon);
+    }
+}
+
+/// Prints what's in the plot directories for the plots command.
+pub fn print_plot_report(archon: &Archon) {
+    if !has_plot_directories(archon) {
+        println!("  {} {}", "ERROR".red().underline(), "You do not have any plotDirectories configured.".yellow());
+        return;
+    }
+    let plot_scan = scan_plot_directories(archon);
+    let scanned_at: DateTime<Local> = archon.now();
+    println!("  {} {}", "Plot Report".cyan().bold(), format!("- {}", scanned_at.format("%Y-%m-%d %H:%M:%S")).white());
+    for directory in &plot_scan.directories {
+        println!("    {} {}", "Directory:".green(), directory);
+    }
+    println!();
+    println!("    {:<22} {:>6} {:>14} {:>12}", "Account".green(), "Files".green(), "Nonces".green(), "Capacity".green());
+    for account in &plot_scan.accounts {
+        println!("    {:<22} {:>6} {:>14} {:>12}",
+            account.account_id,
+            account.files.len(),
+            account.nonces,
+            format!("{:.2} TiB", account.capacity_tebibytes).yellow()
+        );
+    }
+    println!("    {:<22} {:>6} {:>14} {:>12}",
+        "Total".green(),
+        plot_scan.get_num_files(),
+        plot_scan.accounts.iter().map(|account| account.nonces).sum::<u64>(),
+        format!("{:.2} TiB", plot_scan.total_capacity_tebibytes).yellow().bold()
+    );
+    if plot_scan.ignored_files > 0 {
+        println!("\n    {}", format!("{} file(s) aren't named like plot files and were ignored.", plot_scan.ignored_files).white());
+    }
+    if plot_scan.overlaps.len() > 0 {
+        println!("\n    {}", "Overlapping plots:".red());
+        for overlap in &plot_scan.overlaps {
+            println!("      {}", format!("{} and {} overlap by {} nonces ({:.2} GiB wasted)",
+                overlap.first,
+                overlap.second,
+                overlap.overlapping_nonces,
+                (overlap.overlapping_nonces * NONCE_SIZE) as f64 / 2f64.powi(30)
+            ).yellow());
+        }
+    }
+    if plot_scan.errors.len() > 0 {
+        println!("\n    {}", "Couldn't read:".red());
+        for error in &plot_scan.errors {
+            println!("      {}", error.yellow());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_archon() -> Archon {
+        Archon::new(serde_yaml::from_str("gracePeriod: 30\nwebServerBindAddress: 127.0.0.1\nwebServerPort: 1337\n").unwrap())
+    }
+
+    fn create_plot_file(path: &str, start_nonce: u64, nonces: u64) -> PlotFile {
+        PlotFile {
+            path: String::from(path),
+            account_id: 1,
+            start_nonce,
+            nonces,
+            file_size: nonces * NONCE_SIZE,
+        }
+    }
+
+    #[test]
+    fn parses_plot_file_names() {
+        assert_eq!(parse_plot_file_name("12345_0_1000"), Some((12345, 0, 1000)));
+        assert_eq!(parse_plot_file_name("12345_1000_2000_8"), Some((12345, 1000, 2000)));
+    }
+
+    #[test]
+    fn rejects_invalid_plot_file_names() {
+        assert_eq!(parse_plot_file_name("12345_0_0"), None);
+        assert_eq!(parse_plot_file_name("12345_0_0_8"), None);
+        assert_eq!(parse_plot_file_name("12345_0"), None);
+        assert_eq!(parse_plot_file_name("12345_0_1000_8_1"), None);
+        assert_eq!(parse_plot_file_name("12345_0_1000_x"), None);
+        assert_eq!(parse_plot_file_name("plots_0_1000"), None);
+    }
+
+    #[test]
+    fn counts_distinct_nonces() {
+        let files = vec![
+            create_plot_file("a", 0, 1000),
+            create_plot_file("b", 500, 1000),
+            create_plot_file("c", 600, 100),
+            create_plot_file("d", 2000, 500),
+        ];
+        assert_eq!(count_distinct_nonces(&files), 2000);
+        assert_eq!(count_distinct_nonces(&Vec::new()), 0);
+    }
+
+    #[test]
+    fn finds_overlapping_and_nested_files() {
+        let archon = create_archon();
+        let files = vec![
+            create_plot_file("a", 0, 1000),
+            create_plot_file("b", 500, 1000),
+            create_plot_file("c", 600, 100),
+            create_plot_file("d", 2000, 500),
+        ];
+        let overlaps: Vec<(String, String, u64)> = find_overlaps(&archon, &files)
+            .into_iter()
+            .map(|overlap| (overlap.first, overlap.second, overlap.overlapping_nonces))
+            .collect();
+        assert_eq!(overlaps, vec![
+            (String::from("a"), String::from("b"), 500),
+            (String::from("a"), String::from("c"), 100),
+            (String::from("b"), String::from("c"), 100),
+        ]);
+    }
+
+    #[test]
+    fn adjacent_files_do_not_overlap() {
+        let archon = create_archon();
+        let files = vec![create_plot_file("a", 0, 1000), create_plot_file("b", 1000, 1000)];
+        assert!(find_overlaps(&archon, &files).is_empty());
+        assert_eq!(count_distinct_nonces(&files), 2000);
+    }
+}
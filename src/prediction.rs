@@ -0,0 +1,102 @@
+use chrono::{DateTime, Local};
+use std::collections::VecDeque;
+
+use crate::config::PocChain;
+use crate::context::Archon;
+
+// below this many recorded intervals, predictions fall back to an exponential distribution
+const MIN_INTERVALS_FOR_EMPIRICAL_PREDICTION: usize = 10;
+// the target time between blocks, in seconds, of BHD and of every other coin
+const BHD_BLOCK_TIME: u64 = 300;
+const DEFAULT_BLOCK_TIME: u64 = 240;
+
+/// Records the time between the previous block and a new block for a chain.
+pub fn record_block_arrival(archon: &Archon, chain_index: u8, previous_block_time: DateTime<Local>, block_time: DateTime<Local>) {
+    let interval = (block_time - previous_block_time).num_seconds();
+    if interval <= 0 {
+        return;
+    }
+    let max_intervals = match super::get_chain_from_index(archon, chain_index) {
+        Some(chain) => chain.historical_rounds.unwrap_or(360) as usize,
+        None => 360,
+    };
+    let mut chain_block_intervals_map = archon.state.chain_block_intervals.lock().unwrap();
+    let intervals = chain_block_intervals_map.entry(chain_index).or_insert(VecDeque::new());
+    intervals.push_back(interval);
+    while intervals.len() > max_intervals {
+        intervals.pop_front();
+    }
+    debug!("BLOCK INTERVAL - Chain #{} - {}s ({} recorded)", chain_index, interval, intervals.len());
+}
+
+pub fn get_block_intervals(archon: &Archon, chain_index: u8) -> Vec<i64> {
+    match archon.state.chain_block_intervals.lock().unwrap().get(&chain_index) {
+        Some(intervals) => intervals.iter().cloned().collect(),
+        None => Vec::new(),
+    }
+}
+
+pub fn get_mean_block_interval(archon: &Archon, chain_index: u8) -> f64 {
+    let intervals = get_block_intervals(archon, chain_index);
+    if intervals.len() > 0 {
+        return intervals.iter().sum::<i64>() as f64 / intervals.len() as f64;
+    }
+    // no history yet, use the coin's block time
+    match super::get_chain_from_index(archon, chain_index) {
+        Some(chain) => get_block_time(&chain) as f64,
+        None => DEFAULT_BLOCK_TIME as f64,
+    }
+}
+
+/// The coin's target time between blocks, in seconds.
+pub fn get_block_time(chain: &PocChain) -> u64 {
+    if chain.is_bhd.unwrap_or_default() {
+        return BHD_BLOCK_TIME;
+    }
+    return DEFAULT_BLOCK_TIME;
+}
+
+/// Returns when the estimate from get_block_arrival_probability next changes if the chain doesn't find a block first,
+/// or None if it only changes with a new block. The empirical estimate only changes as the time since the last block
+/// reaches a recorded interval, or comes within `within_seconds` of one.
+pub fn get_next_arrival_probability_change(archon: &Archon, chain_index: u8, within_seconds: i64, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let block_time = match archon.state.chain_mining_infos.lock().unwrap().get(&chain_index) {
+        Some((_, block_time)) => *block_time,
+        None => return None,
+    };
+    let intervals = get_block_intervals(archon, chain_index);
+    if intervals.len() < MIN_INTERVALS_FOR_EMPIRICAL_PREDICTION {
+        return None;
+    }
+    let elapsed = (now - block_time).num_seconds();
+    intervals
+        .iter()
+        .flat_map(|interval| vec![*interval, *interval - within_seconds])
+        .filter(|change| *change > elapsed)
+        .min()
+        .map(|change| block_time + chrono::Duration::seconds(change))
+}
+
+/// Estimates the probability that a chain's next block arrives within `within_seconds` from now,
+/// given how long it has been since its last block.
+pub fn get_block_arrival_probability(archon: &Archon, chain_index: u8, within_seconds: i64, now: DateTime<Local>) -> f64 {
+    let elapsed = match archon.state.chain_mining_infos.lock().unwrap().get(&chain_index) {
+        Some((_, block_time)) => (now - *block_time).num_seconds(),
+        None => return 0f64,
+    };
+    let intervals = get_block_intervals(archon, chain_index);
+    if intervals.len() >= MIN_INTERVALS_FOR_EMPIRICAL_PREDICTION {
+        // of the past blocks which took longer than the time elapsed so far, how many arrived within the window
+        let still_waiting = intervals.iter().filter(|interval| **interval > elapsed).count();
+        if still_waiting > 0 {
+            let arrived_in_window = intervals
+                .iter()
+                .filter(|interval| **interval > elapsed && **interval <= elapsed + within_seconds)
+                .count();
+            return arrived_in_window as f64 / still_waiting as f64;
+        }
+        // the block is overdue compared to every recorded interval, the exponential estimate is all that's left
+    }
+    let mean_interval = get_mean_block_interval(archon, chain_index);
+    return 1f64 - (-(within_seconds as f64) / mean_interval).exp();
+}
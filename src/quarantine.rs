@@ -0,0 +1,164 @@
+use chrono::{DateTime, Local};
+use colored::Colorize;
+
+use crate::config::PocChain;
+use crate::context::Archon;
+use crate::events::{self, ArchonEvent};
+use crate::prediction;
+
+// block times are exponentially distributed, a gap 10 times the block time only happens about once every 22,000 blocks
+const DEFAULT_MAX_BLOCK_INTERVAL_MULTIPLIER: u64 = 10;
+const DEFAULT_MAX_FAILED_SUBMISSIONS: u8 = 5;
+// a chain quarantined for failing submissions is given another chance after this many seconds, doubling each time
+// it's quarantined again without a submission getting through in between
+const SUBMISSIONS_FAILING_BACKOFF: i64 = 300;
+const MAX_SUBMISSIONS_FAILING_BACKOFF: i64 = 3600;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum QuarantineReason {
+    Stale,
+    SubmissionsFailing,
+}
+
+impl QuarantineReason {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            QuarantineReason::Stale => "Upstream is stuck on an old block",
+            QuarantineReason::SubmissionsFailing => "Deadline submissions keep failing",
+        }
+    }
+}
+
+/// The longest a chain can go without a new block before its upstream is considered stale, in seconds.
+pub fn get_max_block_interval(chain: &PocChain) -> u64 {
+    match chain.max_block_interval {
+        Some(max_block_interval) if max_block_interval > 0 => max_block_interval,
+        _ => prediction::get_block_time(chain) * DEFAULT_MAX_BLOCK_INTERVAL_MULTIPLIER,
+    }
+}
+
+pub fn is_chain_stale(chain: &PocChain, last_new_block_time: DateTime<Local>, now: DateTime<Local>) -> bool {
+    (now - last_new_block_time).num_seconds() > get_max_block_interval(chain) as i64
+}
+
+pub fn get_quarantine(archon: &Archon, chain_index: u8) -> Option<(QuarantineReason, DateTime<Local>)> {
+    match archon.state.chain_quarantines.lock().unwrap().get(&chain_index) {
+        Some(quarantine) => Some(*quarantine),
+        None => None,
+    }
+}
+
+pub fn is_chain_quarantined(archon: &Archon, chain_index: u8) -> bool {
+    get_quarantine(archon, chain_index).is_some()
+}
+
+/// Returns (chain index, reason, quarantined since) for every quarantined chain.
+pub fn get_quarantined_chains(archon: &Archon) -> Vec<(u8, QuarantineReason, DateTime<Local>)> {
+    let mut quarantined_chains: Vec<(u8, QuarantineReason, DateTime<Local>)> = archon.state.chain_quarantines
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(index, (reason, since))| (*index, *reason, *since))
+        .collect();
+    quarantined_chains.sort_by_key(|(index, _, _)| *index);
+    quarantined_chains
+}
+
+/// Takes a chain out of the queue until it's healthy again. Blocks from a quarantined chain are not started.
+pub fn quarantine_chain(archon: &Archon, chain_index: u8, reason: QuarantineReason, now: DateTime<Local>) {
+    if is_chain_quarantined(archon, chain_index) {
+        return;
+    }
+    let chain = match super::get_chain_from_index(archon, chain_index) {
+        Some(chain) => chain,
+        None => return,
+    };
+    archon.state.chain_quarantines.lock().unwrap().insert(chain_index, (reason, now));
+    if reason == QuarantineReason::SubmissionsFailing {
+        *archon.state.chain_quarantine_strikes.lock().unwrap().entry(chain_index).or_insert(0) += 1;
+    }
+    warn!("QUARANTINE - {} - {}", &*chain.name, reason.describe());
+    events::publish(archon, ArchonEvent::ChainQuarantined {
+        chain_index,
+        chain_name: chain.name.clone(),
+        reason: String::from(reason.describe()),
+    });
+    println!("  {} {} {}",
+        super::get_time(archon).white(),
+        format!("{}", &*chain.name).color(super::get_color(archon, &*chain.color)),
+        format!("Quarantined: {}. Its blocks won't be mined until it recovers.", reason.describe()).red()
+    );
+}
+
+/// Releases a chain from quarantine if it was quarantined for this reason: a stale chain once it finds a new block,
+/// a chain with failing submissions once one gets through or its backoff runs out.
+pub fn release_chain(archon: &Archon, chain_index: u8, reason: QuarantineReason, now: DateTime<Local>) {
+    let mut chain_quarantines_map = archon.state.chain_quarantines.lock().unwrap();
+    let since = match chain_quarantines_map.get(&chain_index) {
+        Some((quarantine_reason, since)) if *quarantine_reason == reason => *since,
+        _ => return,
+    };
+    chain_quarantines_map.remove(&chain_index);
+    drop(chain_quarantines_map);
+    if reason == QuarantineReason::SubmissionsFailing {
+        // it takes another full run of failures to quarantine the chain again
+        archon.state.chain_submission_failures.lock().unwrap().remove(&chain_index);
+    }
+    let chain = match super::get_chain_from_index(archon, chain_index) {
+        Some(chain) => chain,
+        None => return,
+    };
+    let quarantine_duration_str = super::format_timespan(archon, (now - since).num_seconds() as u64);
+    info!("QUARANTINE OVER - {} - {} | Quarantined for: {}", &*chain.name, reason.describe(), quarantine_duration_str);
+    events::publish(archon, ArchonEvent::ChainReleased {
+        chain_index,
+        chain_name: chain.name.clone(),
+    });
+    println!("  {} {} {}",
+        super::get_time(archon).white(),
+        format!("{}", &*chain.name).color(super::get_color(archon, &*chain.color)),
+        format!("Released from quarantine, total time quarantined: {}.", quarantine_duration_str).green()
+    );
+}
+
+/// Returns when the quarantine of a chain with failing submissions runs out, if it's quarantined for that.
+pub fn get_backoff_expiry(archon: &Archon, chain_index: u8) -> Option<DateTime<Local>> {
+    let since = match get_quarantine(archon, chain_index) {
+        Some((QuarantineReason::SubmissionsFailing, since)) => since,
+        _ => return None,
+    };
+    let strikes = *archon.state.chain_quarantine_strikes.lock().unwrap().get(&chain_index).unwrap_or(&1);
+    let mut backoff = SUBMISSIONS_FAILING_BACKOFF;
+    for _ in 1..strikes {
+        backoff = (backoff * 2).min(MAX_SUBMISSIONS_FAILING_BACKOFF);
+    }
+    Some(since + chrono::Duration::seconds(backoff))
+}
+
+/// Returns the chain whose quarantine runs out next and when, if any quarantine runs out on its own.
+pub fn get_next_backoff_expiry(archon: &Archon) -> Option<(u8, DateTime<Local>)> {
+    get_quarantined_chains(archon)
+        .into_iter()
+        .filter_map(|(index, _, _)| get_backoff_expiry(archon, index).map(|expires| (index, expires)))
+        .min_by_key(|(_, expires)| *expires)
+}
+
+/// Counts submissions in a row which couldn't be forwarded upstream.
+/// Returns true when the chain has just reached its limit and should be quarantined.
+pub fn record_submission_result(archon: &Archon, chain_index: u8, failed: bool) -> bool {
+    let mut chain_submission_failures_map = archon.state.chain_submission_failures.lock().unwrap();
+    if !failed {
+        chain_submission_failures_map.remove(&chain_index);
+        archon.state.chain_quarantine_strikes.lock().unwrap().remove(&chain_index);
+        return false;
+    }
+    let failures = chain_submission_failures_map.entry(chain_index).or_insert(0);
+    *failures = failures.saturating_add(1);
+    let max_failed_submissions = match super::get_chain_from_index(archon, chain_index) {
+        Some(chain) => chain.max_failed_submissions.unwrap_or(DEFAULT_MAX_FAILED_SUBMISSIONS),
+        None => DEFAULT_MAX_FAILED_SUBMISSIONS,
+    };
+    debug!("SUBMISSION FAILED - Chain #{} - {} in a row (Limit: {})", chain_index, *failures, max_failed_submissions);
+    return max_failed_submissions > 0 && *failures == max_failed_submissions;
+}
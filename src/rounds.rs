@@ -0,0 +1,81 @@
+use chrono::{DateTime, Local};
+use std::collections::VecDeque;
+
+use crate::context::Archon;
+use crate::upstream::MiningInfo;
+
+// how many rounds are kept for the dashboard
+const MAX_RECENT_ROUNDS: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RoundOutcome {
+    Mining,
+    Finished,
+    Interrupted,
+    Requeued,
+}
+
+impl RoundOutcome {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            RoundOutcome::Mining => "mining",
+            RoundOutcome::Finished => "finished",
+            RoundOutcome::Interrupted => "interrupted",
+            RoundOutcome::Requeued => "requeued",
+        }
+    }
+}
+
+/// A block which was sent out to the miners.
+#[derive(Debug, Clone)]
+pub struct Round {
+    pub chain_index: u8,
+    pub height: u32,
+    pub base_target: u32,
+    // the upstream's target deadline, miners don't submit deadlines over it
+    pub target_deadline: u64,
+    pub started: DateTime<Local>,
+    pub ended: Option<DateTime<Local>>,
+    pub outcome: RoundOutcome,
+}
+
+/// Records the start of a new round. Whatever round was running before it is marked as finished.
+pub fn record_round_started(archon: &Archon, chain_index: u8, mining_info: &MiningInfo, now: DateTime<Local>) {
+    let mut recent_rounds = archon.state.recent_rounds.lock().unwrap();
+    end_current_round(&mut recent_rounds, RoundOutcome::Finished, now);
+    recent_rounds.push_front(Round {
+        chain_index,
+        height: mining_info.height,
+        base_target: mining_info.base_target,
+        target_deadline: mining_info.target_deadline,
+        started: now,
+        ended: None,
+        outcome: RoundOutcome::Mining,
+    });
+    recent_rounds.truncate(MAX_RECENT_ROUNDS);
+}
+
+/// Records that the current round was cut short by another chain's block.
+pub fn record_round_interrupted(archon: &Archon, requeued: bool, now: DateTime<Local>) {
+    let outcome = if requeued {
+        RoundOutcome::Requeued
+    } else {
+        RoundOutcome::Interrupted
+    };
+    end_current_round(&mut archon.state.recent_rounds.lock().unwrap(), outcome, now);
+}
+
+fn end_current_round(recent_rounds: &mut VecDeque<Round>, outcome: RoundOutcome, now: DateTime<Local>) {
+    match recent_rounds.front_mut() {
+        Some(round) if round.outcome == RoundOutcome::Mining => {
+            round.outcome = outcome;
+            round.ended = Some(now);
+        }
+        _ => {}
+    };
+}
+
+/// Returns the most recent rounds, newest first.
+pub fn get_recent_rounds(archon: &Archon) -> Vec<Round> {
+    archon.state.recent_rounds.lock().unwrap().iter().cloned().collect()
+}
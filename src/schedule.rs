@@ -0,0 +1,85 @@
+use std::sync::Arc;
+use std::thread;
+
+use crate::arbiter::{self, ArbiterEvent};
+use crate::config::ChainSchedule;
+use crate::context::Archon;
+
+/// Returns the schedule window currently in effect for a chain, if any.
+pub fn get_active_schedule(archon: &Archon, chain_index: u8) -> Option<ChainSchedule> {
+    match archon.state.chain_active_schedules.lock().unwrap().get(&chain_index) {
+        Some(schedule) => Some(schedule.clone()),
+        None => None,
+    }
+}
+
+/// Checks if a chain is switched off by its schedule. A chain with any window which enables it is
+/// switched off outside of its windows.
+pub fn is_chain_scheduled_off(archon: &Archon, chain_index: u8) -> bool {
+    match get_active_schedule(archon, chain_index) {
+        Some(schedule) => !schedule.enabled.unwrap_or(true),
+        None => match super::get_configured_chain_from_index(archon, chain_index) {
+            Some(chain) => match chain.schedule {
+                Some(schedules) => schedules
+                    .iter()
+                    .any(|schedule| schedule.enabled == Some(true) && schedule.validate().is_ok()),
+                None => false,
+            },
+            None => false,
+        },
+    }
+}
+
+// the first matching window in a chain's schedule wins
+fn find_active_schedule(archon: &Archon, schedules: &Vec<ChainSchedule>) -> Option<ChainSchedule> {
+    let now = archon.now();
+    for schedule in schedules {
+        if schedule.is_active(now) {
+            return Some(schedule.clone());
+        }
+    }
+    return None;
+}
+
+pub fn has_schedules(archon: &Archon) -> bool {
+    for inner in &archon.conf.poc_chains {
+        for chain in inner {
+            if chain.enabled.unwrap_or(true) && chain.schedule.is_some() {
+                return true;
+            }
+        }
+    }
+    return false;
+}
+
+/// Re-evaluates every chain's schedule once a second and announces windows opening and closing.
+pub fn thread_apply_schedules(archon: Arc<Archon>) {
+    let archon = &*archon;
+    loop {
+        for inner in &archon.conf.poc_chains {
+            for chain in inner {
+                if !chain.enabled.unwrap_or(true) {
+                    continue;
+                }
+                let schedules = match &chain.schedule {
+                    Some(schedules) => schedules,
+                    None => continue,
+                };
+                let index = super::get_chain_index(archon, &*chain.url, &*chain.name);
+                let active_schedule = find_active_schedule(archon, schedules);
+                let previous_schedule = get_active_schedule(archon, index);
+                if active_schedule != previous_schedule {
+                    let mut chain_active_schedules_map = archon.state.chain_active_schedules.lock().unwrap();
+                    match &active_schedule {
+                        Some(schedule) => chain_active_schedules_map.insert(index, schedule.clone()),
+                        None => chain_active_schedules_map.remove(&index),
+                    };
+                    drop(chain_active_schedules_map);
+                    super::print_schedule_transition(archon, &*chain.name, &*chain.color, previous_schedule, active_schedule);
+                    arbiter::send_arbiter_event(archon, ArbiterEvent::ScheduleChanged(index));
+                }
+            }
+        }
+        thread::sleep(std::time::Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,223 @@
+use actix_net::service::NewServiceExt;
+use actix_net::ssl::OpensslAcceptor;
+use actix_web::server::{openssl_acceptor_with_flags, HttpServer, IntoHttpHandler, ServerFlags};
+use actix_web::HttpRequest;
+use chrono::{DateTime, Local};
+use colored::Colorize;
+use openssl::nid::Nid;
+use openssl::ssl::{SslAcceptor, SslAcceptorBuilder, SslContext, SslFiletype, SslMethod, SslRef, SslVerifyMode};
+use openssl::x509::{X509Name, X509VerifyResult};
+use std::collections::HashMap;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::SystemTime;
+use tokio_tcp::TcpStream;
+
+use crate::context::Archon;
+
+// how often the certificate files are checked for changes, in seconds
+const CERTIFICATE_CHECK_INTERVAL: u64 = 10;
+// idle connections are closed after a few seconds, so a peer which hasn't made a request in this long is gone, in seconds
+const PEER_TIMEOUT: i64 = 600;
+
+/// The client certificate a TLS connection was opened with, if any.
+struct TlsPeer {
+    // the verified certificate's common name
+    subject: Option<String>,
+    last_seen: DateTime<Local>,
+}
+
+/// The certificate handed to new connections, and the client certificates of open connections.
+pub struct TlsState {
+    context: RwLock<Option<SslContext>>,
+    // modification times of the certificate files when they were loaded
+    loaded_file_times: Mutex<Vec<Option<SystemTime>>>,
+    // Key = the connection's remote address
+    peers: Mutex<HashMap<SocketAddr, TlsPeer>>,
+}
+
+impl TlsState {
+    pub fn new() -> TlsState {
+        TlsState {
+            context: RwLock::new(None),
+            loaded_file_times: Mutex::new(Vec::new()),
+            peers: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+pub fn is_enabled(archon: &Archon) -> bool {
+    archon.conf.tls_certificate_file.is_some() && archon.conf.tls_private_key_file.is_some()
+}
+
+pub fn requires_client_certificates(archon: &Archon) -> bool {
+    is_enabled(archon) && archon.conf.tls_client_ca_file.is_some()
+}
+
+fn get_certificate_files(archon: &Archon) -> Vec<String> {
+    let mut files = Vec::new();
+    files.extend(archon.conf.tls_certificate_file.clone());
+    files.extend(archon.conf.tls_private_key_file.clone());
+    files.extend(archon.conf.tls_client_ca_file.clone());
+    files
+}
+
+fn get_modified_times(files: &Vec<String>) -> Vec<Option<SystemTime>> {
+    files
+        .iter()
+        .map(|file| std::fs::metadata(file).and_then(|metadata| metadata.modified()).ok())
+        .collect()
+}
+
+fn create_acceptor_builder(archon: &Archon) -> Result<SslAcceptorBuilder, String> {
+    let certificate_file = archon.conf.tls_certificate_file.clone().unwrap_or_default();
+    let private_key_file = archon.conf.tls_private_key_file.clone().unwrap_or_default();
+    let mut builder = SslAcceptor::mozilla_intermediate(SslMethod::tls())
+        .map_err(|why| format!("Couldn't set up TLS: {}", why))?;
+    builder
+        .set_certificate_chain_file(&*certificate_file)
+        .map_err(|why| format!("Couldn't load the certificate {}: {}", certificate_file, why))?;
+    builder
+        .set_private_key_file(&*private_key_file, SslFiletype::PEM)
+        .map_err(|why| format!("Couldn't load the private key {}: {}", private_key_file, why))?;
+    builder
+        .check_private_key()
+        .map_err(|_| format!("The private key {} doesn't belong to the certificate {}", private_key_file, certificate_file))?;
+    match &archon.conf.tls_client_ca_file {
+        Some(client_ca_file) => {
+            builder
+                .set_ca_file(&*client_ca_file)
+                .map_err(|why| format!("Couldn't load the client CA {}: {}", client_ca_file, why))?;
+            let client_ca_names = X509Name::load_client_ca_file(&*client_ca_file)
+                .map_err(|why| format!("Couldn't load the client CA {}: {}", client_ca_file, why))?;
+            builder.set_client_ca_list(client_ca_names);
+            // ask every client for a certificate without insisting on one, only miners need one, browsers don't.
+            // a certificate which isn't signed by the CA still fails the handshake
+            builder.set_verify(SslVerifyMode::PEER);
+        }
+        None => {}
+    };
+    Ok(builder)
+}
+
+/// Loads the certificate files for new connections. Connections which are already open keep the certificate they
+/// were opened with.
+pub fn load_certificates(archon: &Archon) -> Result<(), String> {
+    let loaded_file_times = get_modified_times(&get_certificate_files(archon));
+    let context = create_acceptor_builder(archon)?.build().into_context();
+    *archon.state.tls.context.write().unwrap() = Some(context);
+    *archon.state.tls.loaded_file_times.lock().unwrap() = loaded_file_times;
+    Ok(())
+}
+
+/// Binds the server to an address with TLS.
+pub fn bind<H, F>(server: HttpServer<H, F>, address: &str, archon: Arc<Archon>) -> io::Result<HttpServer<H, F>>
+where
+    H: IntoHttpHandler + 'static,
+    F: Fn() -> H + Send + Clone + 'static,
+{
+    load_certificates(&archon).map_err(|why| io::Error::new(io::ErrorKind::InvalidData, why))?;
+    let mut builder = create_acceptor_builder(&archon).map_err(|why| io::Error::new(io::ErrorKind::InvalidData, why))?;
+    // OpenSSL calls this for every handshake, even when the client doesn't send a server name,
+    // so it's where connections pick up reloaded certificates
+    let callback_archon = archon.clone();
+    builder.set_servername_callback(move |ssl, _| {
+        match &*callback_archon.state.tls.context.read().unwrap() {
+            Some(context) => {
+                if let Err(why) = ssl.set_ssl_context(context) {
+                    warn!("TLS - Couldn't use the reloaded certificate for a connection: {}", why);
+                }
+            }
+            None => {}
+        };
+        Ok(())
+    });
+    let acceptor = openssl_acceptor_with_flags(builder, ServerFlags::HTTP1 | ServerFlags::HTTP2)?;
+    server.bind_with(address, move || {
+        let archon = archon.clone();
+        OpensslAcceptor::<TcpStream>::new(acceptor.clone())
+            .map(move |stream| {
+                let ssl_stream = stream.get_ref();
+                match ssl_stream.get_ref().peer_addr() {
+                    Ok(address) => record_peer(&archon, address, get_client_certificate_subject(ssl_stream.ssl())),
+                    Err(_) => {}
+                };
+                stream
+            })
+            .map_err(|_| ())
+    })
+}
+
+// the common name of a client certificate which was verified against the client CA
+fn get_client_certificate_subject(ssl: &SslRef) -> Option<String> {
+    if ssl.verify_result() != X509VerifyResult::OK {
+        return None;
+    }
+    let certificate = ssl.peer_certificate()?;
+    let subject = match certificate.subject_name().entries_by_nid(Nid::COMMONNAME).next() {
+        Some(common_name) => Some(String::from_utf8_lossy(common_name.data().as_slice()).to_string()),
+        None => None,
+    };
+    Some(subject.unwrap_or(String::from("Unnamed")))
+}
+
+fn record_peer(archon: &Archon, address: SocketAddr, subject: Option<String>) {
+    let now = archon.now();
+    let mut peers = archon.state.tls.peers.lock().unwrap();
+    peers.retain(|_, peer| (now - peer.last_seen).num_seconds() < PEER_TIMEOUT);
+    peers.insert(address, TlsPeer { subject, last_seen: now });
+}
+
+/// Checks a miner's connection was opened with a client certificate signed by the client CA, if there is one.
+/// Returns the reason to give the miner if it wasn't.
+pub fn authorize_miner(req: &HttpRequest<Arc<Archon>>) -> Result<(), String> {
+    let archon = req.state();
+    if !requires_client_certificates(archon) {
+        return Ok(());
+    }
+    let address = match req.peer_addr() {
+        Some(address) => address,
+        None => return Err(String::from("A client certificate is required.")),
+    };
+    let mut peers = archon.state.tls.peers.lock().unwrap();
+    match peers.get_mut(&address) {
+        Some(peer) => {
+            peer.last_seen = archon.now();
+            match &peer.subject {
+                Some(subject) => {
+                    trace!("TLS - Miner [{}] has client certificate \"{}\"", address, subject);
+                    Ok(())
+                }
+                None => Err(String::from("A client certificate is required.")),
+            }
+        }
+        None => Err(String::from("A client certificate is required.")),
+    }
+}
+
+/// Reloads the certificates whenever their files change, so a renewed certificate is used without a restart.
+pub fn thread_watch_certificates(archon: Arc<Archon>) {
+    let archon = &*archon;
+    let files = get_certificate_files(archon);
+    loop {
+        thread::sleep(std::time::Duration::from_secs(CERTIFICATE_CHECK_INTERVAL));
+        let modified_times = get_modified_times(&files);
+        if modified_times == *archon.state.tls.loaded_file_times.lock().unwrap() {
+            continue;
+        }
+        match load_certificates(archon) {
+            Ok(_) => {
+                info!("TLS - Reloaded certificates");
+                println!("  {} {}", super::get_time(archon).white(), "TLS certificates reloaded, new connections will use them.".green());
+            }
+            Err(why) => {
+                // don't retry until the files change again, the certificates already loaded are still in use
+                *archon.state.tls.loaded_file_times.lock().unwrap() = modified_times;
+                error!("TLS - Couldn't reload certificates: {}", why);
+                println!("  {} {}", super::get_time(archon).white(), format!("ERROR Couldn't reload the TLS certificates, still using the old ones: {}", why).red());
+            }
+        };
+    }
+}
@@ -0,0 +1,356 @@
+use chrono::{DateTime, Local};
+use colored::Colorize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::context::Archon;
+use crate::events::{self, ArchonEvent};
+use crate::miners;
+use crate::rounds::{self, RoundOutcome};
+
+const DEFAULT_MAX_MISSED_ROUNDS: u32 = 5;
+const DEFAULT_POLL_TIMEOUT: u64 = 120;
+// how often miners' polling is checked, in seconds
+const POLL_CHECK_INTERVAL: u64 = 5;
+
+/// A miner, told apart by its address and software, or an account whose deadlines are being watched.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum WatchedSubject {
+    Miner { address: String, software: String },
+    Account(u64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlertReason {
+    StoppedPolling,
+    StoppedSubmitting,
+}
+
+impl AlertReason {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AlertReason::StoppedPolling => "stoppedPolling",
+            AlertReason::StoppedSubmitting => "stoppedSubmitting",
+        }
+    }
+}
+
+// a subject's deadlines for one chain
+struct SubmissionActivity {
+    last_height: u32,
+    // finished rounds of the chain in a row without a deadline from the subject
+    missed_rounds: u32,
+}
+
+/// What each miner and account normally submits deadlines for, and the alerts currently raised.
+pub struct WatchdogState {
+    // Key = tuple (subject, chain index)
+    submission_activity: Mutex<HashMap<(WatchedSubject, u8), SubmissionActivity>>,
+    // Key = tuple (subject, reason, chain index if it's about submissions), Value = time the alert was raised
+    alerts: Mutex<HashMap<(WatchedSubject, AlertReason, Option<u8>), DateTime<Local>>>,
+}
+
+impl WatchdogState {
+    pub fn new() -> WatchdogState {
+        WatchdogState {
+            submission_activity: Mutex::new(HashMap::new()),
+            alerts: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+/// How many finished rounds of a chain a miner or account can go without a deadline before an alert is raised, 0 if disabled.
+pub fn get_max_missed_rounds(archon: &Archon) -> u32 {
+    archon.conf.miner_watchdog_rounds.unwrap_or(DEFAULT_MAX_MISSED_ROUNDS)
+}
+
+/// How long a miner can go without asking for mining info before an alert is raised, in seconds. 0 if disabled.
+pub fn get_poll_timeout(archon: &Archon) -> u64 {
+    archon.conf.miner_watchdog_poll_timeout.unwrap_or(DEFAULT_POLL_TIMEOUT)
+}
+
+fn describe_subject(archon: &Archon, subject: &WatchedSubject) -> String {
+    match subject {
+        WatchedSubject::Miner { address, software } => match archon.conf.show_miner_addresses.unwrap_or_default() {
+            true => format!("Miner {} [{}]", software, address),
+            false => format!("Miner {}", software),
+        },
+        WatchedSubject::Account(account_id) => format!("Account {}", super::censor_account_id(archon, *account_id)),
+    }
+}
+
+fn is_alert_raised(archon: &Archon, subject: &WatchedSubject, reason: AlertReason, chain_index: Option<u8>) -> bool {
+    archon.state.watchdog.alerts.lock().unwrap().contains_key(&(subject.clone(), reason, chain_index))
+}
+
+fn raise_alert(archon: &Archon, subject: &WatchedSubject, reason: AlertReason, chain_index: Option<u8>, detail: String, now: DateTime<Local>) {
+    archon.state.watchdog.alerts.lock().unwrap().insert((subject.clone(), reason, chain_index), now);
+    let description = describe_subject(archon, subject);
+    let chain_name = chain_index.map(|chain_index| events::get_chain_name(archon, chain_index));
+    warn!("WATCHDOG - {} - {}", description, detail);
+    events::publish(archon, ArchonEvent::MinerAlert {
+        subject: description.clone(),
+        reason: String::from(reason.name()),
+        chain_index,
+        chain_name,
+        detail: detail.clone(),
+    });
+    println!("  {} {} {}",
+        super::get_time(archon).white(),
+        format!("{}:", description).red(),
+        detail.yellow()
+    );
+}
+
+fn clear_alert(archon: &Archon, subject: &WatchedSubject, reason: AlertReason, chain_index: Option<u8>, now: DateTime<Local>) {
+    let since = match archon.state.watchdog.alerts.lock().unwrap().remove(&(subject.clone(), reason, chain_index)) {
+        Some(since) => since,
+        None => return,
+    };
+    let description = describe_subject(archon, subject);
+    let chain_name = chain_index.map(|chain_index| events::get_chain_name(archon, chain_index));
+    let alert_seconds = (now - since).num_seconds();
+    let detail = match (reason, &chain_name) {
+        (AlertReason::StoppedSubmitting, Some(chain_name)) => format!("Submitting deadlines for {} again, after {} seconds.", chain_name, alert_seconds),
+        _ => format!("Asking for mining info again, after {} seconds.", alert_seconds),
+    };
+    info!("WATCHDOG RECOVERED - {} - {}", description, detail);
+    events::publish(archon, ArchonEvent::MinerRecovered {
+        subject: description.clone(),
+        reason: String::from(reason.name()),
+        chain_index,
+        chain_name,
+        alert_seconds,
+    });
+    println!("  {} {} {}",
+        super::get_time(archon).white(),
+        format!("{}:", description).green(),
+        detail.green()
+    );
+}
+
+/// Remembers which chains a miner and account submit deadlines for, and clears any alert about them not doing so.
+pub fn record_submission(archon: &Archon, chain_index: u8, height: u32, account_id: u64, remote_addr: &str, software: &str, now: DateTime<Local>) {
+    let subjects = [
+        WatchedSubject::Miner {
+            address: miners::get_address_without_port(remote_addr),
+            software: String::from(software),
+        },
+        WatchedSubject::Account(account_id),
+    ];
+    for subject in subjects.iter() {
+        archon.state.watchdog.submission_activity.lock().unwrap().insert((subject.clone(), chain_index), SubmissionActivity {
+            last_height: height,
+            missed_rounds: 0,
+        });
+        clear_alert(archon, subject, AlertReason::StoppedSubmitting, Some(chain_index), now);
+    }
+}
+
+/// Called when a round starts. If the round before it ran its course, every miner and account which normally submits
+/// deadlines for its chain but didn't has missed it, and an alert is raised once they've missed too many in a row.
+pub fn check_finished_round(archon: &Archon, now: DateTime<Local>) {
+    let max_missed_rounds = get_max_missed_rounds(archon);
+    if max_missed_rounds == 0 {
+        return;
+    }
+    let (chain_index, height) = match rounds::get_recent_rounds(archon).get(1) {
+        Some(round) if round.outcome == RoundOutcome::Finished => (round.chain_index, round.height),
+        _ => return,
+    };
+    let mut missing_subjects = Vec::new();
+    for ((subject, activity_chain_index), activity) in archon.state.watchdog.submission_activity.lock().unwrap().iter_mut() {
+        if *activity_chain_index != chain_index || activity.last_height == height {
+            continue;
+        }
+        activity.missed_rounds += 1;
+        if activity.missed_rounds >= max_missed_rounds {
+            missing_subjects.push((subject.clone(), activity.missed_rounds));
+        }
+    }
+    for (subject, missed_rounds) in missing_subjects {
+        if is_alert_raised(archon, &subject, AlertReason::StoppedSubmitting, Some(chain_index))
+            // a miner which has stopped polling has already been reported
+            || is_alert_raised(archon, &subject, AlertReason::StoppedPolling, None)
+        {
+            continue;
+        }
+        let detail = format!("No deadlines for {} in the last {} rounds. It may have crashed or lost its plots.", events::get_chain_name(archon, chain_index), missed_rounds);
+        raise_alert(archon, &subject, AlertReason::StoppedSubmitting, Some(chain_index), detail, now);
+    }
+}
+
+/// Raises an alert when a miner stops asking for mining info, and reports it when it starts again.
+pub fn thread_watch_miners(archon: Arc<Archon>) {
+    let archon = &*archon;
+    loop {
+        thread::sleep(std::time::Duration::from_secs(POLL_CHECK_INTERVAL));
+        let now = archon.now();
+        let poll_timeout = get_poll_timeout(archon) as i64;
+        let registered_miners = miners::get_miners(archon);
+        for miner in &registered_miners {
+            let last_polled = match miner.last_polled {
+                Some(last_polled) => last_polled,
+                None => continue,
+            };
+            let subject = WatchedSubject::Miner {
+                address: miner.address.clone(),
+                software: miner.software.clone(),
+            };
+            let seconds_since_polled = (now - last_polled).num_seconds();
+            let alert_raised = is_alert_raised(archon, &subject, AlertReason::StoppedPolling, None);
+            if poll_timeout > 0 && seconds_since_polled > poll_timeout && !alert_raised {
+                let detail = format!("Hasn't asked for mining info in {} seconds. It may have crashed or lost its connection.", seconds_since_polled);
+                raise_alert(archon, &subject, AlertReason::StoppedPolling, None, detail, now);
+            } else if seconds_since_polled <= poll_timeout && alert_raised {
+                clear_alert(archon, &subject, AlertReason::StoppedPolling, None, now);
+            }
+        }
+        // forget about miners which have been dropped from the registry
+        let is_registered = |subject: &WatchedSubject| match subject {
+            WatchedSubject::Miner { address, software } => registered_miners.iter().any(|miner| miner.address == *address && miner.software == *software),
+            WatchedSubject::Account(_) => true,
+        };
+        archon.state.watchdog.alerts.lock().unwrap().retain(|(subject, _, _), _| is_registered(subject));
+        archon.state.watchdog.submission_activity.lock().unwrap().retain(|(subject, _), _| is_registered(subject));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::upstream::MiningInfo;
+    use chrono::TimeZone;
+
+    const CONFIG: &str = "
+gracePeriod: 30
+webServerBindAddress: 127.0.0.1
+webServerPort: 1337
+minerWatchdogRounds: 3
+pocChains:
+  - name: Alpha
+    priority: 0
+    url: http://127.0.0.1:1
+    color: cyan
+  - name: Beta
+    priority: 1
+    url: http://127.0.0.1:2
+    color: green
+";
+    const ALPHA: u8 = 0;
+    const BETA: u8 = 1;
+    const ACCOUNT_ID: u64 = 12345;
+
+    fn create_archon(config: &str) -> Archon {
+        Archon::new(serde_yaml::from_str(config).unwrap())
+    }
+
+    fn get_time() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2020, 1, 1, 12, 0, 0).unwrap()
+    }
+
+    fn get_miner() -> WatchedSubject {
+        WatchedSubject::Miner {
+            address: String::from("192.168.1.10"),
+            software: String::from("scavenger"),
+        }
+    }
+
+    fn submit_deadline(archon: &Archon, chain_index: u8, height: u32) {
+        record_submission(archon, chain_index, height, ACCOUNT_ID, "192.168.1.10:50000", "scavenger", get_time());
+    }
+
+    // starts a round, which finishes the one before it
+    fn start_round(archon: &Archon, chain_index: u8, height: u32) {
+        let mining_info = MiningInfo {
+            height,
+            ..MiningInfo::empty()
+        };
+        rounds::record_round_started(archon, chain_index, &mining_info, get_time());
+        check_finished_round(archon, get_time());
+    }
+
+    fn get_missed_rounds(archon: &Archon, subject: &WatchedSubject, chain_index: u8) -> u32 {
+        archon.state.watchdog.submission_activity.lock().unwrap().get(&(subject.clone(), chain_index)).unwrap().missed_rounds
+    }
+
+    fn is_submitting_alert_raised(archon: &Archon, subject: &WatchedSubject, chain_index: u8) -> bool {
+        is_alert_raised(archon, subject, AlertReason::StoppedSubmitting, Some(chain_index))
+    }
+
+    #[test]
+    fn alert_is_raised_after_too_many_missed_rounds() {
+        let archon = create_archon(CONFIG);
+        start_round(&archon, ALPHA, 100);
+        submit_deadline(&archon, ALPHA, 100);
+        // the round with a deadline isn't missed
+        start_round(&archon, ALPHA, 101);
+        assert_eq!(get_missed_rounds(&archon, &get_miner(), ALPHA), 0);
+        start_round(&archon, ALPHA, 102);
+        start_round(&archon, ALPHA, 103);
+        assert_eq!(get_missed_rounds(&archon, &get_miner(), ALPHA), 2);
+        assert!(!is_submitting_alert_raised(&archon, &get_miner(), ALPHA));
+        start_round(&archon, ALPHA, 104);
+        assert_eq!(get_missed_rounds(&archon, &get_miner(), ALPHA), 3);
+        assert_eq!(get_missed_rounds(&archon, &WatchedSubject::Account(ACCOUNT_ID), ALPHA), 3);
+        assert!(is_submitting_alert_raised(&archon, &get_miner(), ALPHA));
+        assert!(is_submitting_alert_raised(&archon, &WatchedSubject::Account(ACCOUNT_ID), ALPHA));
+    }
+
+    #[test]
+    fn only_finished_rounds_of_the_same_chain_are_missed() {
+        let archon = create_archon(CONFIG);
+        start_round(&archon, ALPHA, 100);
+        submit_deadline(&archon, ALPHA, 100);
+        start_round(&archon, ALPHA, 101);
+        // a round cut short by another chain's block
+        rounds::record_round_interrupted(&archon, false, get_time());
+        start_round(&archon, BETA, 500);
+        // a round of another chain
+        start_round(&archon, ALPHA, 102);
+        assert_eq!(get_missed_rounds(&archon, &get_miner(), ALPHA), 0);
+        start_round(&archon, BETA, 501);
+        assert_eq!(get_missed_rounds(&archon, &get_miner(), ALPHA), 1);
+    }
+
+    #[test]
+    fn deadline_resets_missed_rounds_and_clears_the_alert() {
+        let archon = create_archon(CONFIG);
+        start_round(&archon, ALPHA, 100);
+        submit_deadline(&archon, ALPHA, 100);
+        for height in 101..105 {
+            start_round(&archon, ALPHA, height);
+        }
+        assert!(is_submitting_alert_raised(&archon, &get_miner(), ALPHA));
+        submit_deadline(&archon, ALPHA, 104);
+        assert_eq!(get_missed_rounds(&archon, &get_miner(), ALPHA), 0);
+        assert!(!is_submitting_alert_raised(&archon, &get_miner(), ALPHA));
+        assert!(!is_submitting_alert_raised(&archon, &WatchedSubject::Account(ACCOUNT_ID), ALPHA));
+    }
+
+    #[test]
+    fn miner_which_stopped_polling_is_not_reported_again() {
+        let archon = create_archon(CONFIG);
+        start_round(&archon, ALPHA, 100);
+        submit_deadline(&archon, ALPHA, 100);
+        raise_alert(&archon, &get_miner(), AlertReason::StoppedPolling, None, String::from("Stopped polling."), get_time());
+        for height in 101..105 {
+            start_round(&archon, ALPHA, height);
+        }
+        assert!(!is_submitting_alert_raised(&archon, &get_miner(), ALPHA));
+        assert!(is_submitting_alert_raised(&archon, &WatchedSubject::Account(ACCOUNT_ID), ALPHA));
+    }
+
+    #[test]
+    fn zero_rounds_disables_the_check() {
+        let archon = create_archon(&*CONFIG.replace("minerWatchdogRounds: 3", "minerWatchdogRounds: 0"));
+        start_round(&archon, ALPHA, 100);
+        submit_deadline(&archon, ALPHA, 100);
+        for height in 101..110 {
+            start_round(&archon, ALPHA, height);
+        }
+        assert_eq!(get_missed_rounds(&archon, &get_miner(), ALPHA), 0);
+        assert!(!is_submitting_alert_raised(&archon, &get_miner(), ALPHA));
+    }
+}
@@ -1,10 +1,21 @@
+use crate::access;
+use crate::api;
 use crate::arbiter;
+use crate::auth::{self, ApiScope};
 use actix_web::http::{header, Method, StatusCode};
 use actix_web::{server, App, Error, HttpRequest, HttpResponse};
 use futures::future::{result, FutureResult};
+use std::collections::HashMap;
 use std::string::*;
+use std::sync::Arc;
 
-use crate::config::{Config, PocChain};
+use crate::config::{Config, MinerGroup, PocChain};
+use crate::context::Archon;
+use crate::dashboard;
+use crate::metrics;
+use crate::miners;
+use crate::quarantine::{self, QuarantineReason};
+use crate::tls;
 
 fn parse_u32_from_str(txt: &str) -> u32 {
     match txt.parse::<u32>() {
@@ -81,7 +92,7 @@ impl SubmitNonceErrorResponse {
     }
 }
 
-fn try_get_query_string_value(req: &HttpRequest, name: &str) -> (bool, String) {
+fn try_get_query_string_value(req: &HttpRequest<Arc<Archon>>, name: &str) -> (bool, String) {
     match req.query().get(name) {
         Some(val) => {
             return (true, val.clone());
@@ -113,7 +124,7 @@ impl SubmitNonceInfo {
     }
 }
 
-fn try_get_submit_nonce_data(req: &HttpRequest) -> Option<SubmitNonceInfo> {
+fn try_get_submit_nonce_data(req: &HttpRequest<Arc<Archon>>) -> Option<SubmitNonceInfo> {
     let block_height = match try_get_query_string_value(req, "blockheight") {
         (true, val) => parse_u32_from_str(val.as_str()),
         _ => 0u32,
@@ -147,7 +158,7 @@ fn create_response(status_code: StatusCode, body: String) -> FutureResult<HttpRe
         .body(body)))
 }
 
-fn get_x_deadline_value(req: &HttpRequest) -> (bool, u64) {
+fn get_x_deadline_value(req: &HttpRequest<Arc<Archon>>) -> (bool, u64) {
     match req.headers().get("X-Deadline") {
         Some(container) => {
             match container.to_str() {
@@ -159,7 +170,16 @@ fn get_x_deadline_value(req: &HttpRequest) -> (bool, u64) {
     }
 }
 
-fn get_miner_software(req: &HttpRequest) -> &str {
+// the capacity miners such as scavenger report, in GiB
+fn get_x_capacity_value(req: &HttpRequest<Arc<Archon>>) -> Option<u64> {
+    req.headers().get("X-Capacity")
+        .and_then(|container| container.to_str().ok())
+        .and_then(|value| value.trim().parse::<f64>().ok())
+        .filter(|capacity| *capacity >= 0f64)
+        .map(|capacity| capacity.round() as u64)
+}
+
+fn get_miner_software(req: &HttpRequest<Arc<Archon>>) -> &str {
     match req.headers().get(header::USER_AGENT) {
         Some(container) => {
             match container.to_str() {
@@ -171,7 +191,7 @@ fn get_miner_software(req: &HttpRequest) -> &str {
     }
 }
 
-fn get_miner_software_alt(req: &HttpRequest) -> &str {
+fn get_miner_software_alt(req: &HttpRequest<Arc<Archon>>) -> &str {
     match req.headers().get("X-Miner") {
         Some(container) => {
             match container.to_str() {
@@ -183,18 +203,30 @@ fn get_miner_software_alt(req: &HttpRequest) -> &str {
     }
 }
 
-fn handle_get_mining_info(req: &HttpRequest) -> FutureResult<HttpResponse, Error> {
+fn handle_get_mining_info(req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    let archon = req.state();
     debug!("GetMiningInfo Request from [{}] (Method: {})", req.connection_info().remote().unwrap_or("Unknown"), req.method().to_string());
-    create_response(StatusCode::OK, super::get_current_mining_info_json())
+    miners::record_mining_info_request(archon, req.connection_info().remote().unwrap_or("Unknown"), get_miner_software(&req), get_x_capacity_value(&req), archon.now());
+    create_response(StatusCode::OK, super::get_current_mining_info_json(archon))
 }
 
-fn handle_submit_nonce(req: &HttpRequest) -> FutureResult<HttpResponse, Error> {
+fn handle_submit_nonce(req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    let archon = req.state();
     debug!("SubmitNonce Request from [{}] (Method: {})", req.connection_info().remote().unwrap_or("Unknown"), req.method().to_string());
     match *req.method() {
         Method::POST => {
             match try_get_submit_nonce_data(req) {
                 Some(submit_nonce_data) => {
                     let miner_software = get_miner_software(&req);
+                    miners::record_nonce_submission(
+                        archon,
+                        req.connection_info().remote().unwrap_or("Unknown"),
+                        miner_software,
+                        get_x_capacity_value(&req),
+                        submit_nonce_data.account_id,
+                        submit_nonce_data.block_height.unwrap_or(0),
+                        archon.now(),
+                    );
                     let (is_adjusted, x_deadline) = get_x_deadline_value(&req);
                     let deadline;
                     if is_adjusted && x_deadline < u64::max_value() {
@@ -207,6 +239,7 @@ fn handle_submit_nonce(req: &HttpRequest) -> FutureResult<HttpResponse, Error> {
                     create_response(
                         StatusCode::OK, 
                         arbiter::process_nonce_submission(
+                            archon,
                             submit_nonce_data.block_height.unwrap_or(0),
                             submit_nonce_data.account_id,
                             submit_nonce_data.nonce,
@@ -218,7 +251,7 @@ fn handle_submit_nonce(req: &HttpRequest) -> FutureResult<HttpResponse, Error> {
                     )
                 },
                 _ => {
-                    create_response(StatusCode::OK, r#"{"result":"failure","reason":"Required parameters for nonce submission were not present. Must include ID/Nonce/Deadline."} "#.to_string())
+                    create_response(StatusCode::OK, r#"{"result":"failure","reason":"Required parameters for nonce submission were not present. Must include ID/Nonce/Deadline."}"#.to_string())
                 }
             }
         },
@@ -228,7 +261,20 @@ fn handle_submit_nonce(req: &HttpRequest) -> FutureResult<HttpResponse, Error> {
     }
 }
 
-fn burst_handler(req: &HttpRequest) -> FutureResult<HttpResponse, Error> {
+fn burst_handler(req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    match access::authorize_miner(req, &*try_get_query_string_value(&req, "requestType").1, get_miner_software(&req)) {
+        Ok(_) => {}
+        Err(rejection) => {
+            let mut response = HttpResponse::build(rejection.reason.status());
+            response.header(header::USER_AGENT, get_user_agent_str());
+            if let Some(retry_after) = rejection.retry_after {
+                response.header(header::RETRY_AFTER, retry_after.to_string());
+            }
+            return result(Ok(response
+                .content_type("application/json")
+                .body(serde_json::json!({ "result": "failure", "reason": rejection.message }).to_string())));
+        }
+    };
     match try_get_query_string_value(&req, "requestType") {
         (true, request_type) => {
             match request_type.to_lowercase().as_str() {
@@ -238,22 +284,24 @@ fn burst_handler(req: &HttpRequest) -> FutureResult<HttpResponse, Error> {
             }
         },
         (false, _) => {
-            create_response(StatusCode::BAD_REQUEST, r#"{"result":"failure","reason":"requestType parameter was not found."#.to_string())
+            create_response(StatusCode::BAD_REQUEST, r#"{"result":"failure","reason":"requestType parameter was not found."}"#.to_string())
         }
     }
 }
 
 fn handle_invalid_request_type() -> FutureResult<HttpResponse, Error> {
-    create_response(StatusCode::BAD_REQUEST, r#"{"result":"failure","reason":"requestType parameter was not found."#.to_string())
+    create_response(StatusCode::BAD_REQUEST, r#"{"result":"failure","reason":"requestType is not supported."}"#.to_string())
 }
 
-fn handle_api_get_best_deadlines(req: &HttpRequest) -> FutureResult<HttpResponse, Error> {
+fn handle_api_get_best_deadlines(req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    let archon = req.state();
     debug!("GetBestDeadlines Request from [{}] (Method: {})", req.connection_info().remote().unwrap_or("Unknown"), req.method().to_string());
     match try_get_query_string_value(&req, "height") {
         (true, height_str) => 
             match str::parse::<u32>(height_str.as_str()) {
                 Ok(height) => {
-                    let best_block_deadlines = arbiter::get_best_deadlines_for_block(height);
+                    let chain_index = arbiter::get_chain_index_from_height(archon, height);
+                    let best_block_deadlines = arbiter::get_best_deadlines_for_block(archon, chain_index, height);
                     let json;
                     if best_block_deadlines.is_some() {
                         json = serde_json::to_string(&best_block_deadlines.unwrap()).unwrap_or(r#"{"result":"failure","reason":"Couldn't serialize best deadlines."}"#.to_string());
@@ -265,170 +313,230 @@ fn handle_api_get_best_deadlines(req: &HttpRequest) -> FutureResult<HttpResponse
                 Err(_) => create_response(StatusCode::OK, r#"{"result":"failure","reason":"Couldn't parse block height."}"#.to_string())
             },
         (false, _) => {
-            let best_deadlines = arbiter::get_best_deadlines();
+            // this request predates multiple chains, so it only knows block heights
+            let mut best_deadlines: HashMap<u32, Vec<(u64, u64)>> = HashMap::new();
+            for ((_, height), deadlines) in arbiter::get_best_deadlines(archon) {
+                best_deadlines.entry(height).or_insert(Vec::new()).extend(deadlines);
+            }
             let json = serde_json::to_string(&best_deadlines).unwrap_or(r#"{"result":"failure","reason":"Couldn't serialize best deadlines."}"#.to_string());
             create_response(StatusCode::OK, json)
         }
     }
 }
 
-fn handle_api_get_config(req: &HttpRequest) -> FutureResult<HttpResponse, Error> {
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MiningShare {
+    name: String,
+    actual_share: f64,
+    target_share: f64,
+}
+
+fn handle_api_get_mining_shares(req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    let archon = req.state();
+    debug!("GetMiningShares Request from [{}] (Method: {})", req.connection_info().remote().unwrap_or("Unknown"), req.method().to_string());
+    let mining_shares: Vec<MiningShare> = arbiter::get_mining_shares(archon)
+        .into_iter()
+        .map(|(name, actual_share, target_share)| MiningShare { name, actual_share, target_share })
+        .collect();
+    let json = serde_json::to_string(&mining_shares).unwrap_or(r#"{"result":"failure","reason":"Couldn't serialize mining shares."}"#.to_string());
+    create_response(StatusCode::OK, json)
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct QuarantinedChain {
+    name: String,
+    reason: QuarantineReason,
+    description: String,
+    since: String,
+}
+
+fn handle_api_get_quarantined_chains(req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    let archon = req.state();
+    debug!("GetQuarantinedChains Request from [{}] (Method: {})", req.connection_info().remote().unwrap_or("Unknown"), req.method().to_string());
+    let quarantined_chains: Vec<QuarantinedChain> = quarantine::get_quarantined_chains(archon)
+        .into_iter()
+        .filter_map(|(index, reason, since)| {
+            super::get_chain_from_index(archon, index).map(|chain| QuarantinedChain {
+                name: chain.name,
+                reason,
+                description: String::from(reason.describe()),
+                since: since.to_rfc3339(),
+            })
+        })
+        .collect();
+    let json = serde_json::to_string(&quarantined_chains).unwrap_or(r#"{"result":"failure","reason":"Couldn't serialize quarantined chains."}"#.to_string());
+    create_response(StatusCode::OK, json)
+}
+
+fn handle_api_get_dashboard(req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    let archon = req.state();
+    debug!("GetDashboard Request from [{}] (Method: {})", req.connection_info().remote().unwrap_or("Unknown"), req.method().to_string());
+    let json = serde_json::to_string(&dashboard::get_dashboard_snapshot(archon)).unwrap_or(r#"{"result":"failure","reason":"Couldn't serialize dashboard."}"#.to_string());
+    create_response(StatusCode::OK, json)
+}
+
+fn handle_api_get_config(req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    let archon = req.state();
     debug!("GetConfig Request from [{}] (Method: {})", req.connection_info().remote().unwrap_or("Unknown"), req.method().to_string());
-    // don't allow access to other machines for this request - used for modifying the config from the WebUI only
-    let mut remote_address = req.connection_info().remote().unwrap_or("").to_string();
-    if remote_address.len() >= 9 {
-        remote_address.truncate(9);
-        if remote_address == "127.0.0.1" || remote_address.to_lowercase() == "localhost" {
-            // copy config into new obj that we can serialize
-            let mut conf = Config {
-                grace_period: crate::CONF.grace_period,
-                priority_mode: crate::CONF.priority_mode,
-                interrupt_lower_priority_blocks: crate::CONF.interrupt_lower_priority_blocks,
-                web_server_bind_address: crate::CONF.web_server_bind_address.clone(),
-                web_server_port: crate::CONF.web_server_port,
-                use_poc_chain_colors: crate::CONF.use_poc_chain_colors,
-                poc_chains: Some(Vec::new()),
-                outage_status_update_interval: crate::CONF.outage_status_update_interval,
-                total_plots_size_in_tebibytes: crate::CONF.total_plots_size_in_tebibytes,
-                total_plots_size_in_terabytes: crate::CONF.total_plots_size_in_terabytes,
-                total_plots_size_in_gibibytes: crate::CONF.total_plots_size_in_gibibytes,
-                total_plots_size_in_gigabytes: crate::CONF.total_plots_size_in_gigabytes,
-                show_human_readable_deadlines: crate::CONF.show_human_readable_deadlines,
-                mask_account_ids_in_console: crate::CONF.mask_account_ids_in_console,
-                use_24_hour_time: crate::CONF.use_24_hour_time,
-                num_old_log_files_to_keep: crate::CONF.num_old_log_files_to_keep,
-                logging_level: crate::CONF.logging_level.clone(),
-                show_miner_addresses: crate::CONF.show_miner_addresses,
-            };
-            let mut chains: Vec<PocChain> = Vec::new();
-            for inner in &crate::CONF.poc_chains {
-                for chain in inner {
-                    chains.push(chain.clone());
-                }
-            }
-            conf.poc_chains = Some(chains);
-            let json = serde_json::to_string(&conf).unwrap_or(r#"{"result":"failure","reason":"Couldn't serialize Config object."#.to_string());
-            create_response(StatusCode::OK, json)
-        } else {
-            create_response(StatusCode::FORBIDDEN, r#"{"result":"failure","reason":"This request can only be made from the host machine."}"#.to_string())
+    // copy config into new obj that we can serialize, api_handler has already checked this is an admin request
+    let mut conf = Config {
+        grace_period: archon.conf.grace_period,
+        priority_mode: archon.conf.priority_mode,
+        interrupt_lower_priority_blocks: archon.conf.interrupt_lower_priority_blocks,
+        fair_share_mode: archon.conf.fair_share_mode,
+        tie_break_policy: archon.conf.tie_break_policy.clone(),
+        look_ahead_probability: archon.conf.look_ahead_probability,
+        look_ahead_action: archon.conf.look_ahead_action.clone(),
+        web_server_bind_address: archon.conf.web_server_bind_address.clone(),
+        web_server_port: archon.conf.web_server_port,
+        use_poc_chain_colors: archon.conf.use_poc_chain_colors,
+        poc_chains: Some(Vec::new()),
+        outage_status_update_interval: archon.conf.outage_status_update_interval,
+        total_plots_size_in_tebibytes: archon.conf.total_plots_size_in_tebibytes,
+        total_plots_size_in_terabytes: archon.conf.total_plots_size_in_terabytes,
+        total_plots_size_in_gibibytes: archon.conf.total_plots_size_in_gibibytes,
+        total_plots_size_in_gigabytes: archon.conf.total_plots_size_in_gigabytes,
+        plot_capacity_source: archon.conf.plot_capacity_source.clone(),
+        plot_directories: archon.conf.plot_directories.clone(),
+        numeric_id_to_plots_size_in_tebibytes: archon.conf.numeric_id_to_plots_size_in_tebibytes.clone(),
+        show_human_readable_deadlines: archon.conf.show_human_readable_deadlines,
+        mask_account_ids_in_console: archon.conf.mask_account_ids_in_console,
+        use_24_hour_time: archon.conf.use_24_hour_time,
+        num_old_log_files_to_keep: archon.conf.num_old_log_files_to_keep,
+        logging_level: archon.conf.logging_level.clone(),
+        show_miner_addresses: archon.conf.show_miner_addresses,
+        // never hand out the API tokens themselves
+        api_tokens: None,
+        api_loopback_only: archon.conf.api_loopback_only,
+        tls_certificate_file: archon.conf.tls_certificate_file.clone(),
+        tls_private_key_file: archon.conf.tls_private_key_file.clone(),
+        tls_client_ca_file: archon.conf.tls_client_ca_file.clone(),
+        // the miner groups' secrets are left out for the same reason
+        miner_groups: archon.conf.miner_groups.as_ref().map(|miner_groups| miner_groups
+            .iter()
+            .map(|miner_group| MinerGroup { secret: None, ..miner_group.clone() })
+            .collect()),
+        miner_submission_rate_limit: archon.conf.miner_submission_rate_limit,
+        miner_watchdog_rounds: archon.conf.miner_watchdog_rounds,
+        miner_watchdog_poll_timeout: archon.conf.miner_watchdog_poll_timeout,
+    };
+    let mut chains: Vec<PocChain> = Vec::new();
+    for inner in &archon.conf.poc_chains {
+        for chain in inner {
+            chains.push(chain.clone());
         }
-    } else {
-        create_response(StatusCode::FORBIDDEN, r#"{"result":"failure","reason":"This request can only be made from the host machine."}"#.to_string())
     }
+    conf.poc_chains = Some(chains);
+    let json = serde_json::to_string(&conf).unwrap_or(r#"{"result":"failure","reason":"Couldn't serialize Config object."}"#.to_string());
+    create_response(StatusCode::OK, json)
 }
 
-fn api_handler(req: &HttpRequest) -> FutureResult<HttpResponse, Error> {
+fn api_handler(req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
     match try_get_query_string_value(&req, "requestType") {
         (true, request_type) => {
-            match request_type.to_lowercase().as_str() {
+            let request_type = request_type.to_lowercase();
+            // the config is used for modifying the config from the WebUI only
+            let required_scope = match request_type.as_str() {
+                "getconfig" => ApiScope::Admin,
+                _ => ApiScope::Read,
+            };
+            match auth::authorize(req, required_scope) {
+                Ok(_) => {}
+                Err((status_code, reason)) => return api::create_error_response(status_code, &*reason),
+            };
+            match request_type.as_str() {
                 "getbestdeadlines" => handle_api_get_best_deadlines(&req),
                 "getconfig" => handle_api_get_config(&req),
+                "getdashboard" => handle_api_get_dashboard(&req),
+                "getminingshares" => handle_api_get_mining_shares(&req),
+                "getquarantinedchains" => handle_api_get_quarantined_chains(&req),
                 _ => handle_invalid_request_type()
             }
         },
         (false, _) => {
-            create_response(StatusCode::BAD_REQUEST, r#"{"result":"failure","reason":"requestType parameter was not found."#.to_string())
+            create_response(StatusCode::BAD_REQUEST, r#"{"result":"failure","reason":"requestType parameter was not found."}"#.to_string())
         }
     }
 }
 
-fn webui_handler(req: &HttpRequest) -> FutureResult<HttpResponse, Error> {
-    debug!("WEB UI Request from [{}] (Method: {})", req.connection_info().remote().unwrap_or("Unknown"), req.method().to_string());
-    // just serve a static landing page for now
-    result(Ok(HttpResponse::build(StatusCode::NOT_FOUND)
+// the dashboard is compiled into the binary so it works without internet access
+const DASHBOARD_HTML: &'static str = include_str!("../assets/dashboard/index.html");
+const DASHBOARD_CSS: &'static str = include_str!("../assets/dashboard/dashboard.css");
+const DASHBOARD_JS: &'static str = include_str!("../assets/dashboard/dashboard.js");
+
+fn create_asset_response(content_type: &str, body: &'static str) -> FutureResult<HttpResponse, Error> {
+    result(Ok(HttpResponse::build(StatusCode::OK)
         .header(header::USER_AGENT, get_user_agent_str())
-        .content_type("text/html")
-        .body(r#"<!DOCTYPE html>
-
-<head>
-    <title>Archon | Coming Soon</title>
-	
-    <link href="data:image/x-icon;base64,AAABAAEAEBAAAAEAIABoBAAAFgAAACgAAAAQAAAAIAAAAAEAIAAAAAAAAAQAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAANywXHS8oFhAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAaFokE3tVHJNsSRR5fVgFLXNPBQ5bPwYGX0oIAQAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAHNaJAaJYxAuwZoJeaeMFK6zlyb1n4AV+Kd8Bt+GYQi4fmEKm4BeBj1ZPQYHAAAAAAAAAAAAAAAAAAAAAAAAAAC9jQszsYoPzte5FPzGrCH/w6wl/7WaF//DlQr/nXcM/7GNB/6VbATfdk0EgloxAwoAAAAAAAAAAIg+AASrdgYgzKIGmOrOCf3hyyL/7OJQ/8yzNP/Cnh7/y6oX/9q1Ef/myQj/0rkO/6d4COaIUAROAAAAAK18HQfOrh5l3cQQz+7eF/n79Cj/+PM8/+Pfdv+Ohkf/noso/+TUMf/45R3/9+kU//fydP/ey1T/mmcKvmUvAiPVwkgl7uh6zvv3dP/9/Db//f5g//r6h/+3tYD/RUg9/1ZQJv/b0kn//PQp/+zaJP/x65X/3cpQ/6p7B+t5RQVK2Mo/GfHsgb3695b/9u8y//v6fP///+H/zcy5/09TTv+Bfmn/9fO3//n3eP/KrzL/r4cc/6+AD/+dawTZgFAGLdezAATq2jSX38lE/9a6Pf/597T//v73/8fHvf9LTkf/j4+A//Py3P/u7Z7/08pa/6+NGP+newf/mG4FuHNKBREAAAAA5c8bhtOyOP/UvUv/5eKO/8C9nP9bWlD/HB8e/zw4Kv+Lh2X/wbxz/+LWP//hzRb/sIgG/5VsBopPOwcCAAAAAOPCEHnkwhn+vKEw/4+HQP9yakf/Wk04/xobGf83Jhn/Sz4j/1xUJP+Gch7/w6IO/55wA/B9UgRMAAAAAAAAAADlzRJB6dQa5bifMf94Zjv/p5FI/5eHTv87PTT/Szwm/5Z6LP+Yfiv/jW0r/8ejFf+jcQPDdkkDGQAAAAAAAAAA28sPB+3nIIXq3Tb2x7BQ/+TbUv/MvVz/bGpW/2FQMP/LtjH/4M4v/76UJP+9mQ74oXgKeQAAAAAAAAAAAAAAAAAAAADp4SAS7OUpc8+6Q6nazUrt3M8//4FyS/+Dajj/3cou/8mqIPymdhLCo3kJaohgCxwAAAAAAAAAAAAAAAAAAAAAAAAAAPv9DQKykjEO3skkauLXJqaQei3NqZI01uLNI+vQqRWkrH0SLQAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA7tcPAd+3EQbb0xgKiHEbKLagHjXbvR1ZzaMSFQAAAAAAAAAAAAAAAAAAAAAAAAAA//8AAP7/AAD8DwAA8AMAAOADAADAAQAAgAEAAIABAACAAQAAgAEAAMADAADAAwAAwAcAAPAPAAD8HwAA//8AAA==" rel="icon" type="image/x-icon" />
-
-	<link href="https://fonts.googleapis.com/css?family=Teko" rel="stylesheet" />
-	
-    <style type="text/css">
-        html, body {
-			background: #36393f;
-            font-family: "Teko", "Open Sans", sans-serif;
-			color: #DCDCDE;
-        }
-		div.container {
-			margin: 0 auto;
-			height: 325px;
-			width: 500px;
-			margin-top: 200px;
-			background: #606c88; /* Old browsers */
-			background: -moz-linear-gradient(top, #606c88 0%, #3f4c6b 100%); /* FF3.6-15 */
-			background: -webkit-linear-gradient(top, #606c88 0%,#3f4c6b 100%); /* Chrome10-25,Safari5.1-6 */
-			background: linear-gradient(to bottom, #606c88 0%,#3f4c6b 100%); /* W3C, IE10+, FF16+, Chrome26+, Opera12+, Safari7+ */
-			filter: progid:DXImageTransform.Microsoft.gradient( startColorstr='#606c88', endColorstr='#3f4c6b',GradientType=0 ); /* IE6-9 */
-			-webkit-border-radius: 20px;
-			-moz-border-radius: 20px;
-			border-radius: 20px;
-			padding: 0px 20px 10px 20px;
-			border: 1px solid #000000;    
-			-moz-box-shadow: inset 2px 2px 2px rgba(255, 255, 255, .4), inset -2px -2px 2px rgba(0, 0, 0, .4);
-			-webkit-box-shadow: inset 2px 2px 2px rgba(255, 255, 255, .4), inset -2px -2px 2px rgba(0, 0, 0, .4);
-			box-shadow: inset 2px 2px 2px rgba(255, 255, 255, .4), inset -2px -2px 2px rgba(0, 0, 0, .4);
-		}
-		div.container > div.img {
-			margin: 0 auto;
-			text-align: center;
-		}
-		div.container > div.img > img {
-			position: relative;
-			top: -150px;
-		}
-		div.container > div.text {
-			margin-top: -175px;
-		}
-		div.merging {
-			text-align: left;
-			font-size: 6em;
-		}
-		div.merging2 {
-			margin-top: -40px;
-			text-align: right;
-			font-size: 2em;
-		}
-    </style>
-</head>
-
-<body>
-	<div class="container">
-		<div class="img"><img src="https://www.dropbox.com/s/yvkbeb6vn6ttjm1/Archon-Icon.png?dl=1" height="344" /></div>
-		<div class="text">
-			<div class="merging">"MERGING...</div>
-			<div class="merging2">...is not yet complete..."</div>
-		</div>
-	</div>
-</body>
-
-</html>"#)))
+        .header(header::CACHE_CONTROL, "no-cache")
+        .content_type(content_type)
+        .body(body)))
+}
+
+fn metrics_handler(req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    let archon = req.state();
+    debug!("Metrics Request from [{}] (Method: {})", req.connection_info().remote().unwrap_or("Unknown"), req.method().to_string());
+    match auth::authorize(req, ApiScope::Read) {
+        Ok(_) => {}
+        Err((status_code, reason)) => return api::create_error_response(status_code, &*reason),
+    };
+    result(Ok(HttpResponse::build(StatusCode::OK)
+        .header(header::USER_AGENT, get_user_agent_str())
+        .content_type("text/plain; version=0.0.4; charset=utf-8")
+        .body(metrics::render(archon))))
+}
+
+fn webui_handler(req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    debug!("WEB UI Request from [{}] (Method: {})", req.connection_info().remote().unwrap_or("Unknown"), req.method().to_string());
+    create_asset_response("text/html; charset=utf-8", DASHBOARD_HTML)
+}
+
+fn webui_css_handler(_req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    create_asset_response("text/css; charset=utf-8", DASHBOARD_CSS)
+}
+
+fn webui_js_handler(_req: &HttpRequest<Arc<Archon>>) -> FutureResult<HttpResponse, Error> {
+    create_asset_response("application/javascript; charset=utf-8", DASHBOARD_JS)
 }
 
 pub fn get_user_agent_str() -> String {
     format!("{} v{}", super::uppercase_first(super::APP_NAME), super::VERSION)
 }
 
-pub fn start_server() {
+pub fn start_server(archon: Arc<Archon>) {
     use colored::Colorize;
     use std::process::exit;
     let archon_web_server_sys = actix::System::new("archon");
-    server::new(|| {
-        App::new()
+    let app_archon = archon.clone();
+    let web_server = server::new(move || {
+        App::with_state(app_archon.clone())
             .resource("/", |r| r.route().a(webui_handler))
+            .resource("/dashboard.css", |r| r.route().a(webui_css_handler))
+            .resource("/dashboard.js", |r| r.route().a(webui_js_handler))
             .resource("/burst", |r| r.route().a(burst_handler))
             .resource("/api", |r| r.route().a(api_handler))
+            .scope("/api/v1", api::configure_scope)
+            .resource("/metrics", |r| r.method(Method::GET).a(metrics_handler))
             .default_resource(|r| {
                 r.route().f(|_| HttpResponse::MethodNotAllowed());
             })
-    })
-    .bind(format!("{}:{}", &crate::CONF.web_server_bind_address, &crate::CONF.web_server_port))
-    .map_err(|why| {
-        println!("\n\n  ERROR: Couldn't bind to {}:{}! Please ensure it isn't in use! - {:?}",  &crate::CONF.web_server_bind_address, &crate::CONF.web_server_port, why);
-        error!("Couldn't bind to {}:{}! Please ensure it isn't in use! - {:?}",  &crate::CONF.web_server_bind_address, &crate::CONF.web_server_port, why);
+    });
+    let address = format!("{}:{}", &archon.conf.web_server_bind_address, &archon.conf.web_server_port);
+    let bound_web_server = if tls::is_enabled(&archon) {
+        tls::bind(web_server, &*address, archon.clone())
+    } else {
+        web_server.bind(&*address)
+    };
+    bound_web_server.map_err(|why| {
+        if why.kind() == std::io::ErrorKind::InvalidData {
+            // the TLS certificates couldn't be loaded
+            println!("\n\n  ERROR: Couldn't start the web server with TLS! - {}", why);
+            error!("Couldn't start the web server with TLS! - {}", why);
+        } else {
+            println!("\n\n  ERROR: Couldn't bind to {}:{}! Please ensure it isn't in use! - {:?}",  &archon.conf.web_server_bind_address, &archon.conf.web_server_port, why);
+            error!("Couldn't bind to {}:{}! Please ensure it isn't in use! - {:?}",  &archon.conf.web_server_bind_address, &archon.conf.web_server_port, why);
+        }
         println!(
             "\n  {}",
             "Execution completed. Press enter to exit."